@@ -0,0 +1,12 @@
+use std::path::Path;
+
+/// 重新生成 ui/examples 中内置的示例数据库
+fn main() -> anyhow::Result<()> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    for (name, bytes) in ui::writer::examples::examples()? {
+        let path = dir.join(name);
+        std::fs::write(&path, bytes)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+// 运行：cd ui && cargo +nightly fuzz run reader
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ui::parser::fuzz::exercise(data);
+});
@@ -1,3 +1,4 @@
 pub mod parser;
 pub mod ui;
 pub mod utils;
+pub mod writer;
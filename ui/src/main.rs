@@ -2,13 +2,8 @@
 
 use dioxus::prelude::*;
 use dioxus_logger::tracing::{info, Level};
-use ui::ui::home::Home;
+use ui::ui::route::Route;
 use ui::ui::state::AppState;
-#[derive(Clone, Routable, Debug, PartialEq)]
-enum Route {
-    #[route("/")]
-    Home {},
-}
 
 fn main() {
     // Init logger
@@ -18,7 +13,7 @@ fn main() {
 }
 
 fn App() -> Element {
-    use_context_provider(|| AppState::init());
+    use_context_provider(AppState::init);
     rsx! {
         Router::<Route> {}
     }
@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use super::{Page, Reader};
+
+/// 一棵 B-Tree（或若干棵 B-Tree 合计）的空间使用情况，对应 sqlite3_analyzer 报告中的一节
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SpaceUsage {
+    pub name: String,
+    pub is_index: bool,
+    pub page_size: usize,
+    /// 根页到叶子页的层数，只有根页时为 1
+    pub depth: usize,
+    pub leaf_pages: usize,
+    pub interior_pages: usize,
+    pub overflow_pages: usize,
+    /// 条目数：表为叶子页上的行数，索引的内部页单元也是条目
+    pub entries: usize,
+    pub leaf_cells: usize,
+    pub interior_cells: usize,
+    /// 负载溢出到溢出页的单元数
+    pub overflow_cells: usize,
+    /// 负载总字节数（包括保存在溢出页中的部分）
+    pub payload: usize,
+    pub max_payload: usize,
+    /// 所有单元在 B-Tree 页中占用的字节数
+    pub cell_bytes: usize,
+    /// 页头、单元指针、单元头、溢出页指针、数据库头和保留区
+    pub overhead: usize,
+    /// 未分配的空间、空闲块、碎片和溢出页末尾未用的部分
+    pub unused: usize,
+    /// 页头记录的碎片字节数之和（也计入 unused）
+    pub fragmented_bytes: usize,
+    /// 按遍历顺序，页号不紧跟在前一页之后的次数
+    pub gaps: usize,
+}
+
+impl SpaceUsage {
+    pub fn pages(&self) -> usize {
+        self.leaf_pages + self.interior_pages + self.overflow_pages
+    }
+
+    /// 占用的总字节数
+    pub fn storage(&self) -> usize {
+        self.pages() * self.page_size
+    }
+
+    /// 平均每个内部页的子页数
+    pub fn fan_out(&self) -> Option<f64> {
+        (self.interior_pages > 0).then(|| {
+            (self.interior_cells + self.interior_pages) as f64 / self.interior_pages as f64
+        })
+    }
+
+    pub fn avg_payload(&self) -> Option<f64> {
+        (self.entries > 0).then(|| self.payload as f64 / self.entries as f64)
+    }
+
+    /// 单元在页内的平均大小
+    pub fn avg_cell_size(&self) -> Option<f64> {
+        let cells = self.leaf_cells + self.interior_cells;
+        (cells > 0).then(|| self.cell_bytes as f64 / cells as f64)
+    }
+
+    pub fn avg_unused(&self) -> Option<f64> {
+        (self.entries > 0).then(|| self.unused as f64 / self.entries as f64)
+    }
+
+    /// 碎片化程度：不连续的页占所有页的百分比，与 sqlite3_analyzer 的 Fragmentation 相同
+    pub fn fragmentation(&self) -> Option<f64> {
+        (self.pages() > 1).then(|| 100.0 * self.gaps as f64 / self.pages() as f64)
+    }
+
+    /// 以百分比表示某个字节数占总空间的比例
+    pub fn percent(&self, bytes: usize) -> f64 {
+        match self.storage() {
+            0 => 0.0,
+            total => 100.0 * bytes as f64 / total as f64,
+        }
+    }
+
+    /// 把另一棵树的统计累加进来，深度取最大值
+    fn add(&mut self, other: &SpaceUsage) {
+        self.depth = self.depth.max(other.depth);
+        self.leaf_pages += other.leaf_pages;
+        self.interior_pages += other.interior_pages;
+        self.overflow_pages += other.overflow_pages;
+        self.entries += other.entries;
+        self.leaf_cells += other.leaf_cells;
+        self.interior_cells += other.interior_cells;
+        self.overflow_cells += other.overflow_cells;
+        self.payload += other.payload;
+        self.max_payload = self.max_payload.max(other.max_payload);
+        self.cell_bytes += other.cell_bytes;
+        self.overhead += other.overhead;
+        self.unused += other.unused;
+        self.fragmented_bytes += other.fragmented_bytes;
+        self.gaps += other.gaps;
+    }
+}
+
+/// 整个数据库的空间使用分析
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Analysis {
+    pub page_size: usize,
+    pub total_pages: usize,
+    pub freelist_pages: usize,
+    /// 不属于任何 B-Tree、溢出链或空闲列表的页（例如 pointer map 页或丢失的页）
+    pub other_pages: usize,
+    /// 每张表和每个索引，按占用页数从多到少排列
+    pub trees: Vec<SpaceUsage>,
+    pub all: SpaceUsage,
+    pub tables: SpaceUsage,
+    pub indexes: SpaceUsage,
+}
+
+impl Reader {
+    /// 计算每张表、每个索引和整个数据库的空间使用情况
+    pub fn analyze(&self) -> Analysis {
+        let page_size = self.header.real_page_size();
+        let summary = |name: &str, is_index| SpaceUsage {
+            name: name.to_string(),
+            is_index,
+            page_size,
+            ..Default::default()
+        };
+        let mut all = summary("All tables and indexes", false);
+        let mut tables = summary("All tables", false);
+        let mut indexes = summary("All indexes", true);
+        let mut trees = vec![];
+        for entry in &self.schema {
+            if entry.root_page == 0 {
+                continue;
+            }
+            let is_index = entry.kind == "index" || entry.is_without_rowid();
+            let mut usage = summary(&entry.name, is_index);
+            self.tree_usage(entry.root_page, &mut usage);
+            all.add(&usage);
+            if entry.kind == "index" {
+                indexes.add(&usage);
+            } else {
+                tables.add(&usage);
+            }
+            trees.push(usage);
+        }
+        trees.sort_by(|a, b| b.pages().cmp(&a.pages()).then(a.name.cmp(&b.name)));
+
+        let total_pages = self.page_count() as usize;
+        let freelist_pages = self.freelist_pages();
+        Analysis {
+            page_size,
+            total_pages,
+            freelist_pages,
+            other_pages: total_pages.saturating_sub(freelist_pages + all.pages()),
+            trees,
+            all,
+            tables,
+            indexes,
+        }
+    }
+
+    /// 先序遍历一棵 B-Tree（每个单元的溢出页紧跟在它所在的页之后），累加统计
+    fn tree_usage(&self, root: u32, usage: &mut SpaceUsage) {
+        let usable = self.header.usable_size();
+        let chunk = usable.saturating_sub(4);
+        let mut visited = HashSet::new();
+        let mut prev = 0;
+        let mut visit = |number: u32, usage: &mut SpaceUsage| {
+            if prev > 0 && number != prev + 1 {
+                usage.gaps += 1;
+            }
+            prev = number;
+        };
+        let mut stack = vec![(root, 1)];
+        while let Some((number, depth)) = stack.pop() {
+            if !visited.insert(number) {
+                continue;
+            }
+            let Some(rc) = self.page(number) else {
+                continue;
+            };
+            let Page::BTree(page) = rc.as_ref() else {
+                continue;
+            };
+            visit(number, usage);
+            usage.depth = usage.depth.max(depth);
+
+            let leaf = page.header.page_type.is_leaf();
+            let index = !page.header.page_type.is_table();
+            let mut used =
+                page.header_offset - page.info.offset + page.header.size() + 2 * page.cells.len();
+            for cell in &page.cells {
+                let size = cell.size();
+                used += size;
+                usage.cell_bytes += size;
+                if leaf || index {
+                    usage.entries += 1;
+                }
+                if let Some((total, _)) = cell.payload_size {
+                    let total = total as usize;
+                    usage.payload += total;
+                    usage.max_payload = usage.max_payload.max(total);
+                    if let Some(payload) = cell.payload.as_ref().filter(|p| !p.overflow.is_empty())
+                    {
+                        usage.overflow_cells += 1;
+                        let stored = total - payload.local_size.min(total);
+                        let last = stored.saturating_sub((payload.overflow.len() - 1) * chunk);
+                        usage.unused += chunk.saturating_sub(last);
+                        for (overflow, _) in &payload.overflow {
+                            visited.insert(*overflow);
+                            visit(*overflow, usage);
+                            usage.overflow_pages += 1;
+                        }
+                    }
+                }
+            }
+            usage.unused += usable.saturating_sub(used);
+            usage.fragmented_bytes += page.header.fragmented_free_bytes as usize;
+            if leaf {
+                usage.leaf_pages += 1;
+                usage.leaf_cells += page.cells.len();
+            } else {
+                usage.interior_pages += 1;
+                usage.interior_cells += page.cells.len();
+                // 逆序入栈，保证先访问最左边的子页
+                if let Some(right) = page.header.right_most_pointer {
+                    stack.push((right, depth + 1));
+                }
+                for cell in page.cells.iter().rev() {
+                    if let Some(child) = cell.left_child {
+                        stack.push((child, depth + 1));
+                    }
+                }
+            }
+        }
+        usage.overhead = usage.storage().saturating_sub(usage.payload + usage.unused);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::RecordValue,
+        ui::viewer::{BIG_PAGE_DB, SIMPLE_DB},
+        writer::DatabaseBuilder,
+    };
+
+    #[test]
+    fn analyze_included_databases() {
+        for bytes in [SIMPLE_DB, BIG_PAGE_DB] {
+            let reader = Reader::new(bytes).unwrap();
+            let analysis = reader.analyze();
+            assert_eq!(analysis.total_pages, 2);
+            assert_eq!(analysis.trees.len(), 2);
+            assert_eq!(analysis.all.pages(), 2);
+            assert_eq!(analysis.other_pages, 0);
+
+            let table = analysis
+                .trees
+                .iter()
+                .find(|t| t.name != "sqlite_schema")
+                .unwrap();
+            assert_eq!(table.depth, 1);
+            assert_eq!(table.leaf_pages, 1);
+            assert_eq!(table.entries, 4);
+            assert_eq!(table.fan_out(), None);
+            assert_eq!(
+                table.payload + table.overhead + table.unused,
+                table.storage()
+            );
+            assert_eq!(analysis.tables.entries, 5);
+            assert_eq!(analysis.indexes.pages(), 0);
+        }
+    }
+
+    fn tree<'a>(analysis: &'a Analysis, name: &str) -> &'a SpaceUsage {
+        analysis.trees.iter().find(|t| t.name == name).unwrap()
+    }
+
+    #[test]
+    fn analyze_multi_level_trees() {
+        // 100 行各 53 或 54 字节的负载，最后一行是 2000 字节的 blob，溢出到 4 页
+        let mut rows: Vec<Vec<RecordValue>> = (1..=100)
+            .map(|i| {
+                vec![
+                    RecordValue::Integer(i),
+                    RecordValue::Text(format!("{i:0>50}")),
+                ]
+            })
+            .collect();
+        rows.push(vec![
+            RecordValue::Integer(101),
+            RecordValue::Blob(vec![7; 2000]),
+        ]);
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a, b)", rows)
+            .index("CREATE INDEX t_b ON t(b)")
+            .build()
+            .unwrap();
+        let analysis = Reader::new(&bytes).unwrap().analyze();
+        assert_eq!(analysis.total_pages, 38);
+        assert_eq!(analysis.other_pages, 0);
+
+        // 根页 2 下有 13 个叶子页 8–20，blob 的溢出页 4–7 接在第 20 页之后
+        let table = tree(&analysis, "t");
+        assert_eq!(table.depth, 2);
+        assert_eq!(
+            (table.interior_pages, table.leaf_pages, table.overflow_pages),
+            (1, 13, 4)
+        );
+        assert_eq!((table.entries, table.interior_cells), (101, 12));
+        assert_eq!(table.fan_out(), Some(13.0));
+        // 第 1 行的整数 1 不占字节：99 × 54 + 53 + 2005
+        assert_eq!(table.payload, 7404);
+        assert_eq!(table.max_payload, 2005);
+        assert_eq!(table.overflow_cells, 1);
+        // 2 → 8 和 20 → 4 不连续
+        assert_eq!(table.gaps, 2);
+        assert_eq!(table.fragmentation(), Some(100.0 * 2.0 / 18.0));
+
+        // 索引有三层，内部页单元也是条目
+        let index = tree(&analysis, "t_b");
+        assert!(index.is_index);
+        assert_eq!(index.depth, 3);
+        assert_eq!(
+            (index.interior_pages, index.leaf_pages, index.overflow_pages),
+            (3, 12, 4)
+        );
+        assert_eq!((index.leaf_cells, index.interior_cells), (90, 11));
+        assert_eq!(index.entries, 101);
+        assert_eq!(index.fan_out(), Some(14.0 / 3.0));
+        assert_eq!(index.payload, 7404);
+        // 3 → 37 → 25、33 → 38 → 34 和 36 → 21 不连续
+        assert_eq!(index.gaps, 5);
+
+        assert_eq!(analysis.indexes.pages(), 19);
+        assert_eq!(analysis.tables.pages(), 19);
+    }
+
+    #[test]
+    fn unused_space_of_overflow_pages() {
+        let rows = vec![vec![RecordValue::Blob(vec![7; 2000])]];
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(b)", rows)
+            .build()
+            .unwrap();
+        let analysis = Reader::new(&bytes).unwrap().analyze();
+        let table = tree(&analysis, "t");
+        assert_eq!((table.leaf_pages, table.overflow_pages), (1, 4));
+        // 负载 2003 字节，页内保存 39 字节，溢出页每页 508 字节，最后一页只用了 440 字节
+        assert_eq!(table.payload, 2003);
+        // 叶子页：8 字节页头、2 字节单元指针、46 字节单元；最后一个溢出页空 68 字节
+        assert_eq!(table.unused, (512 - 8 - 2 - 46) + (508 - 440));
+        // 页头、单元指针、负载长度、rowid、溢出页指针和 4 个溢出页的下一页指针
+        assert_eq!(table.overhead, 8 + 2 + 2 + 1 + 4 + 4 * 4);
+        assert_eq!(table.fragmentation(), Some(0.0));
+    }
+}
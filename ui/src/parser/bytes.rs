@@ -0,0 +1,96 @@
+use std::{fmt, ops::Range, rc::Rc};
+
+use serde::{Serialize, Serializer};
+
+/// 页缓冲区中一段字节的共享视图。解析出的结构保存视图而不是复制内容，
+/// 克隆只增加引用计数。跨越多页的内容（例如带溢出链的负载）拼接后单独保存。
+/// 视图会留住整个页缓冲区，所以只保存在 Reader 有限的解析缓存中的页里，
+/// 页被淘汰后缓冲区随之释放。需要长期保存的内容应复制出来。
+#[derive(Clone)]
+pub struct Bytes {
+    buf: Rc<[u8]>,
+    range: Range<usize>,
+}
+
+impl Bytes {
+    /// buf 中 range 的视图，range 超出 buf 时 panic
+    pub fn new(buf: Rc<[u8]>, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= buf.len());
+        Self { buf, range }
+    }
+
+    /// 视图中的一段，range 相对于视图的开头
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.len());
+        let start = self.range.start;
+        Self::new(self.buf.clone(), start + range.start..start + range.end)
+    }
+
+    /// 视图是否引用 buf 中的内容
+    pub fn is_view_of(&self, buf: &Rc<[u8]>) -> bool {
+        Rc::ptr_eq(&self.buf, buf)
+    }
+}
+
+impl std::ops::Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[self.range.clone()]
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(v: Vec<u8>) -> Self {
+        let len = v.len();
+        Self::new(Rc::from(v), 0..len)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(v: &[u8]) -> Self {
+        Self::new(Rc::from(v), 0..v.len())
+    }
+}
+
+impl PartialEq for Bytes {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// 与 `Vec<u8>` 相同，序列化为字节数组
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_share_the_buffer() {
+        let buf: Rc<[u8]> = Rc::from(&b"SQLite format 3\0"[..]);
+        let view = Bytes::new(buf.clone(), 7..15);
+        assert_eq!(&*view, b"format 3");
+        let inner = view.slice(0..6);
+        assert_eq!(&*inner, b"format");
+        assert!(inner.is_view_of(&buf));
+        assert_eq!(inner, Bytes::from(b"format".to_vec()));
+        assert_eq!(serde_json::to_string(&view.slice(6..8)).unwrap(), "[32,51]");
+    }
+}
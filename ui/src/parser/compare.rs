@@ -0,0 +1,303 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use super::{schema::ColumnInfo, Affinity, RecordValue};
+
+/// 内置的排序规则，参见 https://www.sqlite.org/datatype3.html#collating_sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Collation {
+    /// 逐字节比较
+    Binary,
+    /// 忽略 ASCII 字母的大小写
+    NoCase,
+    /// 忽略末尾的空格
+    Rtrim,
+}
+
+impl Collation {
+    /// 按名字查找排序规则，未指定或不认识的排序规则按 BINARY 处理
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some(n) if n.eq_ignore_ascii_case("NOCASE") => Self::NoCase,
+            Some(n) if n.eq_ignore_ascii_case("RTRIM") => Self::Rtrim,
+            _ => Self::Binary,
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            Self::Binary => a.as_bytes().cmp(b.as_bytes()),
+            Self::NoCase => a
+                .bytes()
+                .map(|c| c.to_ascii_lowercase())
+                .cmp(b.bytes().map(|c| c.to_ascii_lowercase())),
+            Self::Rtrim => a.trim_end_matches(' ').cmp(b.trim_end_matches(' ')),
+        }
+    }
+}
+
+impl std::fmt::Display for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Binary => write!(f, "BINARY"),
+            Self::NoCase => write!(f, "NOCASE"),
+            Self::Rtrim => write!(f, "RTRIM"),
+        }
+    }
+}
+
+/// 不同存储类之间的顺序：NULL < INTEGER/REAL < TEXT < BLOB
+fn type_rank(v: &RecordValue) -> u8 {
+    match v {
+        RecordValue::Null => 0,
+        RecordValue::Integer(_) | RecordValue::Real(_) => 1,
+        RecordValue::Text(_) => 2,
+        RecordValue::Blob(_) => 3,
+    }
+}
+
+/// 整数和浮点数的比较，与 sqlite3IntFloatCompare 一致，避免大整数转换成浮点数时丢失精度
+fn int_float_compare(i: i64, r: f64) -> Ordering {
+    if r.is_nan() {
+        // SQLite 不会保存 NaN，这里把它当作最小的数
+        return Ordering::Greater;
+    }
+    if r < -9223372036854775808.0 {
+        return Ordering::Greater;
+    }
+    if r >= 9223372036854775808.0 {
+        return Ordering::Less;
+    }
+    let y = r as i64;
+    match i.cmp(&y) {
+        Ordering::Equal => (i as f64).partial_cmp(&r).unwrap_or(Ordering::Equal),
+        o => o,
+    }
+}
+
+/// 按 SQLite 的规则比较两个值，文本使用给定的排序规则
+pub fn compare_values(a: &RecordValue, b: &RecordValue, collation: Collation) -> Ordering {
+    use RecordValue::*;
+    match (a, b) {
+        (Integer(x), Integer(y)) => x.cmp(y),
+        (Real(x), Real(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (Integer(x), Real(y)) => int_float_compare(*x, *y),
+        (Real(x), Integer(y)) => int_float_compare(*y, *x).reverse(),
+        (Text(x), Text(y)) => collation.compare(x, y),
+        (Blob(x), Blob(y)) => x.cmp(y),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+/// 比较索引 record 和查找的键，返回 record 相对于键的顺序。
+/// 只比较键给出的前几列（前缀匹配），每列使用该列的排序规则，DESC 列的顺序相反。
+pub fn compare_key(
+    values: &[&RecordValue],
+    key: &[RecordValue],
+    columns: &[ColumnInfo],
+) -> Ordering {
+    for (i, (v, k)) in values.iter().zip(key).enumerate() {
+        let column = columns.get(i);
+        let collation = Collation::from_name(column.and_then(|c| c.collation.as_deref()));
+        let mut o = compare_values(v, k, collation);
+        if column.is_some_and(|c| c.desc) {
+            o = o.reverse();
+        }
+        if o != Ordering::Equal {
+            return o;
+        }
+    }
+    Ordering::Equal
+}
+
+/// 比较之前先对键应用列的亲和性，例如 INTEGER 列中查找 '5' 等同于查找 5
+pub fn apply_affinity(value: RecordValue, affinity: Affinity) -> RecordValue {
+    match (affinity, value) {
+        (Affinity::Text, RecordValue::Integer(i)) => RecordValue::Text(i.to_string()),
+        (Affinity::Text, RecordValue::Real(r)) => RecordValue::Text(r.to_string()),
+        (Affinity::Integer | Affinity::Numeric | Affinity::Real, RecordValue::Text(t)) => {
+            let s = t.trim();
+            if let Ok(i) = s.parse::<i64>() {
+                RecordValue::Integer(i)
+            } else if let Ok(r) = s.parse::<f64>() {
+                RecordValue::Real(r)
+            } else {
+                RecordValue::Text(t)
+            }
+        }
+        (_, v) => v,
+    }
+}
+
+/// 解析用户输入的键：逗号分隔的 SQL 字面量，支持 NULL、整数、浮点数、'文本' 和 X'十六进制'
+pub fn parse_key(input: &str) -> Result<Vec<RecordValue>> {
+    let b = input.as_bytes();
+    let mut values = vec![];
+    let mut i = 0;
+    loop {
+        while i < b.len() && b[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= b.len() {
+            bail!("Missing value");
+        }
+        let blob = (b[i] == b'x' || b[i] == b'X') && b.get(i + 1) == Some(&b'\'');
+        if b[i] == b'\'' || blob {
+            let start = if blob { i + 2 } else { i + 1 };
+            let mut text = String::new();
+            let mut j = start;
+            loop {
+                match input[j..].find('\'') {
+                    None => bail!("Unterminated string starting at {i}"),
+                    Some(n) => {
+                        text.push_str(&input[j..j + n]);
+                        j += n + 1;
+                        // '' 表示一个单引号
+                        if b.get(j) == Some(&b'\'') {
+                            text.push('\'');
+                            j += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            values.push(if blob {
+                // 先排除非十六进制字符，之后按字节切分不会落在多字节字符中间
+                if !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    bail!("Blob literal X'{text}' is not hex");
+                }
+                if !text.len().is_multiple_of(2) {
+                    bail!("Blob literal X'{text}' has an odd number of digits");
+                }
+                let bytes = (0..text.len())
+                    .step_by(2)
+                    .map(|k| u8::from_str_radix(&text[k..k + 2], 16))
+                    .collect::<Result<Vec<u8>, _>>()?;
+                RecordValue::Blob(bytes)
+            } else {
+                RecordValue::Text(text)
+            });
+            i = j;
+        } else {
+            let end = input[i..].find(',').map_or(b.len(), |n| i + n);
+            let word = input[i..end].trim();
+            values.push(if word.eq_ignore_ascii_case("NULL") {
+                RecordValue::Null
+            } else if let Ok(n) = word.parse::<i64>() {
+                RecordValue::Integer(n)
+            } else if let Ok(r) = word.parse::<f64>() {
+                RecordValue::Real(r)
+            } else {
+                bail!("Cannot parse {word:?}, quote text values like '{word}'");
+            });
+            i = end;
+        }
+        while i < b.len() && b[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        match b.get(i) {
+            None => return Ok(values),
+            Some(b',') => i += 1,
+            Some(c) => bail!("Expected ',' at {i}, found {:?}", *c as char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RecordValue::*;
+
+    #[test]
+    fn value_ordering() {
+        let ordered = [
+            Null,
+            Integer(-5),
+            Real(-1.5),
+            Integer(0),
+            Real(0.5),
+            Integer(1),
+            Text("A".into()),
+            Text("a".into()),
+            Text("b".into()),
+            Blob(vec![0]),
+            Blob(vec![0, 0]),
+            Blob(vec![1]),
+        ];
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(
+                    compare_values(a, b, Collation::Binary),
+                    i.cmp(&j),
+                    "{a:?} vs {b:?}"
+                );
+            }
+        }
+        assert_eq!(
+            compare_values(&Integer(3), &Real(3.0), Collation::Binary),
+            Ordering::Equal
+        );
+        // 2^53 + 1 无法用 f64 精确表示
+        assert_eq!(
+            compare_values(
+                &Integer((1 << 53) + 1),
+                &Real((1u64 << 53) as f64),
+                Collation::Binary
+            ),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(&Integer(i64::MAX), &Real(1e19), Collation::Binary),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn collations() {
+        let nocase = Collation::from_name(Some("nocase"));
+        assert_eq!(nocase.compare("ABC", "abc"), Ordering::Equal);
+        assert_eq!(nocase.compare("abc", "ABD"), Ordering::Less);
+        assert_eq!(Collation::Binary.compare("ABC", "abc"), Ordering::Less);
+        let rtrim = Collation::from_name(Some("RTRIM"));
+        assert_eq!(rtrim.compare("abc  ", "abc"), Ordering::Equal);
+        assert_eq!(rtrim.compare(" abc", "abc"), Ordering::Less);
+        assert_eq!(Collation::from_name(Some("unknown")), Collation::Binary);
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(
+            parse_key(" 1, -2.5 ,'it''s', X'00ff', null").unwrap(),
+            [
+                Integer(1),
+                Real(-2.5),
+                Text("it's".into()),
+                Blob(vec![0, 0xff]),
+                Null
+            ]
+        );
+        assert_eq!(parse_key("'a,b'").unwrap(), [Text("a,b".into())]);
+        assert!(parse_key("abc").is_err());
+        assert!(parse_key("1,").is_err());
+        assert!(parse_key("'abc").is_err());
+        assert!(parse_key("X'0'").is_err());
+        assert!(parse_key("X'aé0'").is_err());
+        assert!(parse_key("X'+1'").is_err());
+    }
+
+    #[test]
+    fn affinity_conversion() {
+        assert_eq!(
+            apply_affinity(Text(" 5".into()), Affinity::Integer),
+            Integer(5)
+        );
+        assert_eq!(apply_affinity(Integer(5), Affinity::Text), Text("5".into()));
+        assert_eq!(
+            apply_affinity(Text("5".into()), Affinity::Blob),
+            Text("5".into())
+        );
+    }
+}
@@ -0,0 +1,579 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+};
+
+use serde::Serialize;
+
+use super::{
+    page::{BTreePage, Cell, FreelistTrunkPage, OverflowPage, Payload},
+    schema::SchemaEntry,
+    Page, Reader,
+};
+
+/// 一段字节属于哪种结构。顺序即嵌套顺序：范围相同时排在前面的是外层结构。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum SpanKind {
+    Page,
+    DatabaseHeader,
+    PageHeader,
+    CellPointerArray,
+    CellPointer,
+    Unallocated,
+    Freeblock,
+    /// 单元内容区中小于 4 字节、无法成为空闲块的碎片
+    Fragment,
+    Reserved,
+    Cell,
+    LeftChild,
+    PayloadSize,
+    Rowid,
+    OverflowPointer,
+    OverflowNext,
+    OverflowContent,
+    OverflowUnused,
+    Payload,
+    Record,
+    RecordHeader,
+    HeaderSize,
+    SerialType,
+    Column,
+    FreelistNext,
+    FreelistCount,
+    FreelistLeafPointer,
+    FreelistUnused,
+    FreelistLeaf,
+}
+
+/// 文件中被某个结构解释的一段字节（文件偏移）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub kind: SpanKind,
+    pub label: String,
+}
+
+impl Span {
+    fn new(range: Range<usize>, kind: SpanKind, label: impl Into<String>) -> Self {
+        Self {
+            range,
+            kind,
+            label: label.into(),
+        }
+    }
+}
+
+/// 整个文件的字节覆盖情况：每一页中各个结构占用的字节
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+    page_size: usize,
+    file_size: usize,
+    /// 每一页的结构，不包括页本身
+    spans: HashMap<u32, Vec<Span>>,
+    pages: HashMap<u32, Span>,
+}
+
+impl Coverage {
+    /// 页中的所有结构，按起始偏移排列
+    pub fn page_spans(&self, number: u32) -> Vec<Span> {
+        let mut spans = self.spans.get(&number).cloned().unwrap_or_default();
+        spans.sort_by_key(|s| (s.range.start, std::cmp::Reverse(s.range.end), s.kind));
+        spans
+    }
+
+    /// 覆盖 offset 的所有结构，从外到内：页 → 单元 → record → 列
+    pub fn explain(&self, offset: usize) -> Vec<Span> {
+        let number = (offset / self.page_size) as u32 + 1;
+        let Some(page) = self.pages.get(&number) else {
+            return vec![];
+        };
+        let mut chain: Vec<Span> = self
+            .spans
+            .get(&number)
+            .into_iter()
+            .flatten()
+            .filter(|s| s.range.contains(&offset))
+            .cloned()
+            .collect();
+        chain.sort_by_key(|s| (std::cmp::Reverse(s.range.len()), s.kind));
+        chain.insert(0, page.clone());
+        chain
+    }
+
+    /// range 中每个字节最内层的结构，即 explain 的最后一项。没有被页内结构解释的字节为 None。
+    pub fn innermost(&self, range: Range<usize>) -> Vec<Option<SpanKind>> {
+        let mut kinds = vec![None; range.len()];
+        if range.is_empty() {
+            return kinds;
+        }
+        let first = (range.start / self.page_size) as u32 + 1;
+        let last = ((range.end - 1) / self.page_size) as u32 + 1;
+        let mut spans: Vec<&Span> = (first..=last)
+            .filter_map(|n| self.spans.get(&n))
+            .flatten()
+            .collect();
+        // 外层结构先填，内层结构覆盖它们
+        spans.sort_by_key(|s| (std::cmp::Reverse(s.range.len()), s.kind));
+        for span in spans {
+            let start = span.range.start.max(range.start);
+            let end = span.range.end.min(range.end);
+            if start < end {
+                kinds[start - range.start..end - range.start].fill(Some(span.kind));
+            }
+        }
+        kinds
+    }
+
+    /// 没有被任何结构解释的字节范围（页本身不算）
+    pub fn unexplained(&self) -> Vec<Range<usize>> {
+        let mut covered: Vec<Range<usize>> = self
+            .spans
+            .values()
+            .flatten()
+            .map(|s| s.range.clone())
+            .collect();
+        covered.sort_by_key(|r| r.start);
+        let mut gaps = vec![];
+        let mut pos = 0;
+        for range in covered {
+            if range.start > pos {
+                gaps.push(pos..range.start);
+            }
+            pos = pos.max(range.end);
+        }
+        if pos < self.file_size {
+            gaps.push(pos..self.file_size);
+        }
+        gaps
+    }
+}
+
+impl Reader {
+    /// 计算每一页中各个结构占用的字节
+    pub fn coverage(&self) -> Coverage {
+        self.coverage_of(1..=self.page_count())
+    }
+
+    /// 只计算 numbers 中的页。溢出页中的 record 属于引用这条溢出链的单元，从单元所在的页得到
+    pub fn coverage_of(&self, numbers: impl IntoIterator<Item = u32>) -> Coverage {
+        let page_size = self.header.real_page_size();
+        let usable = self.header.usable_size();
+        let wanted: HashSet<u32> = numbers.into_iter().collect();
+        let mut spans: HashMap<u32, Vec<Span>> = HashMap::new();
+        let mut pages = HashMap::new();
+        let mut parents = BTreeSet::new();
+        let mut numbers: Vec<u32> = wanted.iter().copied().collect();
+        numbers.sort();
+        for number in numbers {
+            let Some(page) = self.page(number) else {
+                continue;
+            };
+            let info = page.info();
+            let range = info.offset..info.offset + info.size;
+            pages.insert(
+                number,
+                Span::new(range.clone(), SpanKind::Page, page_label(&page)),
+            );
+            let list = spans.entry(number).or_default();
+            if number == 1 {
+                list.push(Span::new(
+                    0..100,
+                    SpanKind::DatabaseHeader,
+                    "Database header",
+                ));
+            }
+            if usable < page_size && !matches!(page.as_ref(), Page::Unknown(_)) {
+                let reserved = info.offset + usable..range.end;
+                list.push(Span::new(reserved, SpanKind::Reserved, "Reserved space"));
+            }
+            match page.as_ref() {
+                Page::BTree(p) => {
+                    let raw = self.cache().page(number).ok();
+                    btree_spans(p, raw.as_deref(), usable, &mut spans);
+                }
+                Page::Overflow(p) => {
+                    overflow_spans(p, usable, list);
+                    parents.extend(self.overflow_parent(number).filter(|p| !wanted.contains(p)));
+                }
+                Page::FreelistTrunk(p) => freelist_trunk_spans(p, usable, list),
+                Page::FreelistLeaf(info) => list.push(Span::new(
+                    info.offset..info.offset + usable,
+                    SpanKind::FreelistLeaf,
+                    "Unused freelist leaf page",
+                )),
+                Page::Unknown(_) => {}
+            }
+        }
+        for parent in parents {
+            if let Some(Page::BTree(p)) = self.page(parent).as_deref() {
+                let raw = self.cache().page(parent).ok();
+                btree_spans(p, raw.as_deref(), usable, &mut spans);
+            }
+        }
+        spans.retain(|n, _| wanted.contains(n));
+        Coverage {
+            page_size,
+            file_size: self.cache().size() as usize,
+            spans,
+            pages,
+        }
+    }
+}
+
+fn page_label(page: &Page) -> String {
+    let n = page.info().number;
+    match page {
+        Page::BTree(p) => format!("Page {n} ({})", p.header.page_type),
+        Page::Overflow(_) => format!("Page {n} (Overflow)"),
+        Page::FreelistTrunk(_) => format!("Page {n} (Freelist Trunk)"),
+        Page::FreelistLeaf(_) => format!("Page {n} (Freelist Leaf)"),
+        Page::Unknown(_) => format!("Page {n}"),
+    }
+}
+
+fn btree_spans(
+    page: &BTreePage,
+    raw: Option<&[u8]>,
+    usable: usize,
+    spans: &mut HashMap<u32, Vec<Span>>,
+) {
+    let number = page.info.number;
+    let base = page.info.offset;
+    let h = page.header_offset;
+    let mut list = vec![Span::new(
+        h..h + page.header.size(),
+        SpanKind::PageHeader,
+        "Page header",
+    )];
+
+    let pointers = page.cell_pointers_offset();
+    let pointers_end = pointers + 2 * page.cell_pointers.len();
+    if !page.cell_pointers.is_empty() {
+        list.push(Span::new(
+            pointers..pointers_end,
+            SpanKind::CellPointerArray,
+            "Cell pointer array",
+        ));
+    }
+    for i in 0..page.cell_pointers.len() {
+        let p = pointers + 2 * i;
+        list.push(Span::new(
+            p..p + 2,
+            SpanKind::CellPointer,
+            format!("Cell pointer {i}"),
+        ));
+    }
+
+    let content_start = match page.header.cell_content_start {
+        0 => 65536,
+        n => n as usize,
+    };
+    let content_start = (base + content_start).clamp(pointers_end, base + usable);
+    if content_start > pointers_end {
+        list.push(Span::new(
+            pointers_end..content_start,
+            SpanKind::Unallocated,
+            "Unallocated space",
+        ));
+    }
+
+    // 空闲块链表：每个空闲块以 2 字节的下一个空闲块偏移和 2 字节的大小开头
+    let mut freeblocks = vec![];
+    if let Some(raw) = raw {
+        let mut next = page.header.first_freeblock as usize;
+        while next != 0 && next + 4 <= usable && freeblocks.len() < usable / 4 {
+            let following = u16::from_be_bytes([raw[next], raw[next + 1]]) as usize;
+            let size = u16::from_be_bytes([raw[next + 2], raw[next + 3]]) as usize;
+            let size = size.min(usable - next);
+            freeblocks.push(base + next..base + next + size);
+            if following <= next {
+                break;
+            }
+            next = following;
+        }
+    }
+    for (i, r) in freeblocks.iter().enumerate() {
+        list.push(Span::new(
+            r.clone(),
+            SpanKind::Freeblock,
+            format!("Freeblock {i}"),
+        ));
+    }
+
+    let mut cell_ranges = vec![];
+    for (i, cell) in page.cells.iter().enumerate() {
+        if cell.size() == 0 {
+            continue;
+        }
+        cell_ranges.push(cell.offset..cell.offset + cell.size());
+        cell_spans(i, cell, page, spans, &mut list);
+    }
+
+    // 单元内容区中剩下的小空隙是碎片
+    let mut used: Vec<Range<usize>> = cell_ranges.into_iter().chain(freeblocks).collect();
+    used.sort_by_key(|r| r.start);
+    let mut pos = content_start;
+    for r in used.iter().chain([&(base + usable..base + usable)]) {
+        if r.start > pos && r.start - pos < 4 {
+            list.push(Span::new(pos..r.start, SpanKind::Fragment, "Fragment"));
+        }
+        pos = pos.max(r.end);
+    }
+
+    spans.entry(number).or_default().extend(list);
+}
+
+fn column_name(owner: Option<&SchemaEntry>, i: usize) -> String {
+    match owner.and_then(|o| o.columns.get(i)) {
+        Some(c) => format!("column {i} `{}`", c.name),
+        None => format!("column {i}"),
+    }
+}
+
+fn cell_spans(
+    i: usize,
+    cell: &Cell,
+    page: &BTreePage,
+    spans: &mut HashMap<u32, Vec<Span>>,
+    list: &mut Vec<Span>,
+) {
+    let number = page.info.number;
+    let mut pos = cell.offset;
+    list.push(Span::new(
+        pos..pos + cell.size(),
+        SpanKind::Cell,
+        format!("Cell {i}"),
+    ));
+    if let Some(child) = cell.left_child {
+        list.push(Span::new(
+            pos..pos + 4,
+            SpanKind::LeftChild,
+            format!("Left child pointer → page {child}"),
+        ));
+        pos += 4;
+    }
+    if let Some((size, len)) = cell.payload_size {
+        list.push(Span::new(
+            pos..pos + len,
+            SpanKind::PayloadSize,
+            format!("Payload size {size}"),
+        ));
+        pos += len;
+    }
+    if let Some((rowid, len)) = cell.rowid {
+        list.push(Span::new(
+            pos..pos + len,
+            SpanKind::Rowid,
+            format!("Rowid {rowid}"),
+        ));
+    }
+    let Some(payload) = &cell.payload else {
+        return;
+    };
+    let local_end = payload.offset + payload.local_size;
+    list.push(Span::new(
+        payload.offset..local_end,
+        SpanKind::Payload,
+        "Payload",
+    ));
+    if let Some(first) = payload.first_overflow {
+        list.push(Span::new(
+            local_end..local_end + 4,
+            SpanKind::OverflowPointer,
+            format!("First overflow page → page {first}"),
+        ));
+    }
+
+    let Some(record) = &payload.record else {
+        return;
+    };
+    let owner = page.owner.as_deref();
+    let cell_name = format!("cell {i} on page {number}");
+    // 把 record 中的一段（负载偏移）加到它所在的每一页
+    let mut add = |range: Range<usize>, kind: SpanKind, label: String| {
+        for piece in payload.file_ranges(range) {
+            let on = page_of(payload, &piece, number);
+            let label = if on == number {
+                label.clone()
+            } else {
+                format!("{label} of {cell_name}")
+            };
+            if on == number {
+                list.push(Span::new(piece, kind, label));
+            } else {
+                spans
+                    .entry(on)
+                    .or_default()
+                    .push(Span::new(piece, kind, label));
+            }
+        }
+    };
+    add(
+        0..payload.bytes.len(),
+        SpanKind::Record,
+        "Record".to_string(),
+    );
+    let header_size = record.header_size.max(0) as usize;
+    add(
+        0..header_size,
+        SpanKind::RecordHeader,
+        "Record header".to_string(),
+    );
+    add(
+        0..record.header_size_len,
+        SpanKind::HeaderSize,
+        format!("Record header size {header_size}"),
+    );
+    for (k, column) in record.columns.iter().enumerate() {
+        let name = column_name(owner, k);
+        add(
+            column.serial_type_offset..column.serial_type_offset + column.serial_type_size,
+            SpanKind::SerialType,
+            format!("Serial type {} of {name}", column.serial_type),
+        );
+        add(
+            column.offset..column.offset + column.size,
+            SpanKind::Column,
+            format!("Value of {name}: {}", column.value),
+        );
+    }
+}
+
+/// 负载中的一段文件范围在哪一页
+fn page_of(payload: &Payload, piece: &Range<usize>, local: u32) -> u32 {
+    payload
+        .overflow
+        .iter()
+        .find(|&&(_, offset)| {
+            piece.start >= offset && piece.start < offset + payload.overflow_chunk
+        })
+        .map_or(local, |&(page, _)| page)
+}
+
+fn overflow_spans(page: &OverflowPage, usable: usize, list: &mut Vec<Span>) {
+    let base = page.info.offset;
+    let next = if page.next == 0 {
+        "Next overflow page: none".to_string()
+    } else {
+        format!("Next overflow page → page {}", page.next)
+    };
+    list.push(Span::new(base..base + 4, SpanKind::OverflowNext, next));
+    let end = base + 4 + page.content.len();
+    list.push(Span::new(
+        base + 4..end,
+        SpanKind::OverflowContent,
+        "Overflow content",
+    ));
+    if end < base + usable {
+        list.push(Span::new(
+            end..base + usable,
+            SpanKind::OverflowUnused,
+            "Unused space after the last overflow content",
+        ));
+    }
+}
+
+fn freelist_trunk_spans(page: &FreelistTrunkPage, usable: usize, list: &mut Vec<Span>) {
+    let base = page.info.offset;
+    list.push(Span::new(
+        base..base + 4,
+        SpanKind::FreelistNext,
+        format!("Next freelist trunk page {}", page.next),
+    ));
+    list.push(Span::new(
+        base + 4..base + 8,
+        SpanKind::FreelistCount,
+        format!("Number of leaf pages {}", page.leaves.len()),
+    ));
+    for (i, leaf) in page.leaves.iter().enumerate() {
+        let p = base + 8 + 4 * i;
+        list.push(Span::new(
+            p..p + 4,
+            SpanKind::FreelistLeafPointer,
+            format!("Freelist leaf page {i} → page {leaf}"),
+        ));
+    }
+    let end = base + 8 + 4 * page.leaves.len();
+    if end < base + usable {
+        list.push(Span::new(
+            end..base + usable,
+            SpanKind::FreelistUnused,
+            "Unused space in the trunk page",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::{BIG_PAGE_DB, SIMPLE_DB};
+
+    #[test]
+    fn every_byte_is_explained() {
+        for bytes in [SIMPLE_DB, BIG_PAGE_DB] {
+            let reader = Reader::new(bytes).unwrap();
+            assert_eq!(reader.coverage().unexplained(), vec![]);
+        }
+    }
+
+    #[test]
+    fn explain_a_column() {
+        let reader = Reader::new(SIMPLE_DB).unwrap();
+        let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        let cell = &page.cells[1];
+        let payload = cell.payload.as_ref().unwrap();
+        let column = &payload.record.as_ref().unwrap().columns[0];
+        let offset = payload.file_offset(column.offset);
+
+        let coverage = reader.coverage();
+        let chain = coverage.explain(offset);
+        let kinds: Vec<SpanKind> = chain.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SpanKind::Page,
+                SpanKind::Cell,
+                SpanKind::Payload,
+                SpanKind::Record,
+                SpanKind::Column
+            ]
+        );
+        assert_eq!(chain[1].label, "Cell 1");
+        assert!(chain[4].label.contains("`int`"), "{}", chain[4].label);
+
+        let chain = coverage.explain(10);
+        assert_eq!(chain[1].kind, SpanKind::DatabaseHeader);
+
+        // 每个字节最内层的结构就是 explain 的最后一项
+        let range = page.info.offset..page.info.offset + page.info.size;
+        let kinds = coverage.innermost(range.clone());
+        assert_eq!(kinds[offset - range.start], Some(SpanKind::Column));
+        for (i, kind) in range.clone().zip(&kinds).step_by(7) {
+            assert_eq!(*kind, coverage.explain(i).last().map(|s| s.kind));
+        }
+        assert!(coverage.explain(bytes_len(&reader)).is_empty());
+    }
+
+    fn bytes_len(reader: &Reader) -> usize {
+        reader.page_count() as usize * reader.header.real_page_size()
+    }
+
+    #[test]
+    fn unexplained_bytes_of_unknown_pages() {
+        let mut bytes = SIMPLE_DB.to_vec();
+        let page_size = Reader::new(SIMPLE_DB).unwrap().header.real_page_size();
+        bytes.extend(vec![0; page_size]);
+        let coverage = Reader::new(&bytes).unwrap().coverage();
+        assert_eq!(coverage.unexplained(), vec![2 * page_size..3 * page_size]);
+
+        // 末尾不满一页的字节也没有被解释
+        bytes.extend([0; 10]);
+        let coverage = Reader::new(&bytes).unwrap().coverage();
+        assert_eq!(
+            coverage.unexplained(),
+            vec![2 * page_size..3 * page_size + 10]
+        );
+    }
+}
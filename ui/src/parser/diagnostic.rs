@@ -0,0 +1,283 @@
+use serde::Serialize;
+
+use super::DBHeader;
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    /// 不影响解析，只是值得注意，例如文件末尾多出的字节
+    Info,
+    /// 数据不一致，但仍然可以继续解析
+    Warning,
+    /// 结构无法解析，相关的数据被跳过
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Info => write!(f, "Info"),
+            Self::Warning => write!(f, "Warning"),
+            Self::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// 出问题时正在解析的结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Structure {
+    File,
+    Header,
+    PageHeader,
+    CellPointer,
+    Cell,
+    Payload,
+    Record,
+    OverflowChain,
+    BTree,
+    Freelist,
+    Schema,
+}
+
+impl std::fmt::Display for Structure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File => write!(f, "file"),
+            Self::Header => write!(f, "database header"),
+            Self::PageHeader => write!(f, "page header"),
+            Self::CellPointer => write!(f, "cell pointer"),
+            Self::Cell => write!(f, "cell"),
+            Self::Payload => write!(f, "payload"),
+            Self::Record => write!(f, "record"),
+            Self::OverflowChain => write!(f, "overflow chain"),
+            Self::BTree => write!(f, "b-tree"),
+            Self::Freelist => write!(f, "freelist"),
+            Self::Schema => write!(f, "schema"),
+        }
+    }
+}
+
+/// 解析中发现的一个问题。Severity::Error 的问题同时也是解析函数的错误类型。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub structure: Structure,
+    /// 问题所在的页，数据库头和整个文件的问题没有页号
+    pub page: Option<u32>,
+    /// 问题所在的文件偏移
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        structure: Structure,
+        page: Option<u32>,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            structure,
+            page,
+            offset,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(
+        structure: Structure,
+        page: Option<u32>,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Error, structure, page, offset, message)
+    }
+
+    pub fn warning(
+        structure: Structure,
+        page: Option<u32>,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Warning, structure, page, offset, message)
+    }
+
+    pub fn info(
+        structure: Structure,
+        page: Option<u32>,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Info, structure, page, offset, message)
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.structure)?;
+        if let Some(page) = self.page {
+            write!(f, " on page {page}")?;
+        }
+        write!(f, " at offset {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// 检查数据库头中各字段的取值是否合法，file_size 为文件的总字节数
+pub fn check_header(header: &DBHeader, file_size: u64) -> Vec<Diagnostic> {
+    let mut problems = vec![];
+    let mut warn = |offset: usize, message: String| {
+        problems.push(Diagnostic::warning(
+            Structure::Header,
+            None,
+            offset,
+            message,
+        ))
+    };
+    if header.header != *b"SQLite format 3\0" {
+        warn(0, format!("Unexpected magic string {:?}", header.magic()));
+    }
+    if header.write_version == 0 || header.write_version > 2 {
+        warn(
+            18,
+            format!("Invalid write version {}", header.write_version),
+        );
+    }
+    if header.read_version == 0 || header.read_version > 2 {
+        warn(19, format!("Invalid read version {}", header.read_version));
+    }
+    if header.max_embeded_payload_fraction != 64 {
+        warn(
+            21,
+            format!(
+                "Maximum embedded payload fraction must be 64, found {}",
+                header.max_embeded_payload_fraction
+            ),
+        );
+    }
+    if header.min_embeded_payload_fraction != 32 {
+        warn(
+            22,
+            format!(
+                "Minimum embedded payload fraction must be 32, found {}",
+                header.min_embeded_payload_fraction
+            ),
+        );
+    }
+    if header.leaf_payload_fraction != 32 {
+        warn(
+            23,
+            format!(
+                "Leaf payload fraction must be 32, found {}",
+                header.leaf_payload_fraction
+            ),
+        );
+    }
+    if !(1..=4).contains(&header.schema_format) && header.schema_format != 0 {
+        warn(
+            44,
+            format!("Unknown schema format {}", header.schema_format),
+        );
+    }
+    if !(1..=3).contains(&header.text_encoding) {
+        warn(
+            56,
+            format!(
+                "Invalid text encoding {}, decoding text as UTF-8",
+                header.text_encoding
+            ),
+        );
+    }
+    if header.expansion_reserved.iter().any(|&b| b != 0) {
+        warn(72, "Reserved bytes for expansion are not zero".to_string());
+    }
+
+    let page_size = header.real_page_size() as u64;
+    if let Some(pages) = file_size.checked_div(page_size) {
+        // 只有 change counter 与 version-valid-for 相同时，头中的页数才有效
+        if header.file_change_counter == header.version_valid_for && header.db_size as u64 != pages
+        {
+            warn(
+                28,
+                format!(
+                    "The header says the database has {} pages, but the file has {pages}",
+                    header.db_size
+                ),
+            );
+        }
+        if !file_size.is_multiple_of(page_size) {
+            problems.push(Diagnostic::info(
+                Structure::File,
+                None,
+                (pages * page_size) as usize,
+                format!(
+                    "The file ends with {} bytes that are not a whole page",
+                    file_size % page_size
+                ),
+            ));
+        }
+    }
+    problems
+}
+
+/// 页大小必须是 512 到 65536 之间的 2 的幂，否则无法定位任何页
+pub fn check_page_size(header: &DBHeader) -> Result<(), Diagnostic> {
+    let size = header.real_page_size();
+    if !(512..=65536).contains(&size) || !size.is_power_of_two() {
+        return Err(Diagnostic::error(
+            Structure::Header,
+            None,
+            16,
+            format!("Invalid page size {}", header.page_size),
+        ));
+    }
+    if header.usable_size() < 480 {
+        return Err(Diagnostic::error(
+            Structure::Header,
+            None,
+            20,
+            format!(
+                "{} reserved bytes leave less than 480 usable bytes per page",
+                header.reserved_page_size
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::SIMPLE_DB;
+
+    fn header(bytes: &[u8]) -> DBHeader {
+        DBHeader::try_from(<&[u8; 100]>::try_from(&bytes[..100]).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn valid_header_has_no_problems() {
+        let h = header(SIMPLE_DB);
+        assert_eq!(check_header(&h, SIMPLE_DB.len() as u64), vec![]);
+        assert!(check_page_size(&h).is_ok());
+    }
+
+    #[test]
+    fn header_problems() {
+        let mut bytes = SIMPLE_DB[..100].to_vec();
+        bytes[0] = b's';
+        bytes[21] = 10;
+        bytes[59] = 9;
+        let h = header(&bytes);
+        let problems = check_header(&h, SIMPLE_DB.len() as u64 + 10);
+        let offsets: Vec<usize> = problems.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, [0, 21, 56, SIMPLE_DB.len()]);
+        assert_eq!(problems[3].severity, Severity::Info);
+
+        bytes[16..18].copy_from_slice(&1000u16.to_be_bytes());
+        let err = check_page_size(&header(&bytes)).unwrap_err();
+        assert_eq!((err.severity, err.offset), (Severity::Error, 16));
+    }
+}
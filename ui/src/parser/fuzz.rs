@@ -0,0 +1,172 @@
+use std::rc::Rc;
+
+use super::{
+    journal::Journal, reader::DEFAULT_CACHE_PAGES, shm::WalIndex, MemorySource, Reader,
+    RecordValue, Wal,
+};
+
+/// 对任意字节调用 Reader 的所有入口：解析、按需读取、覆盖图、空间分析和 B-Tree 查找，
+/// 以及 WAL、wal-index 和回滚日志的解析。
+/// 无论输入是什么都不能 panic、死循环或耗尽内存，模糊测试和下面的测试都通过它检查这一点。
+pub fn exercise(bytes: &[u8]) {
+    let bytes: Rc<[u8]> = Rc::from(bytes);
+
+    // 同一份字节也当作伴随文件解析，WAL 写回到它自己上
+    if let Ok(wal) = Wal::parse(bytes.clone()) {
+        let _ = wal.apply(&bytes);
+    }
+    let _ = WalIndex::parse(&bytes);
+    let _ = Journal::parse(bytes.clone());
+
+    // 先按需读取：缓存很小时页会被反复换出
+    if let Ok(reader) = Reader::open(MemorySource(bytes.clone()), 2) {
+        for n in (1..=reader.page_count()).take(64) {
+            reader.page(n);
+        }
+        reader.load_all();
+    }
+
+    let Ok(reader) = Reader::open(MemorySource(bytes.clone()), DEFAULT_CACHE_PAGES) else {
+        return;
+    };
+    reader.load_all();
+    reader.diagnostics();
+    reader.pages().count();
+    reader.analyze();
+    let coverage = reader.coverage();
+    coverage.unexplained();
+    for offset in (0..bytes.len()).step_by(97) {
+        coverage.explain(offset);
+    }
+
+    for entry in &reader.schema {
+        if entry.root_page == 0 {
+            continue;
+        }
+        if entry.kind == "table" && !entry.is_without_rowid() {
+            for rowid in [i64::MIN, -1, 0, 1, 2, 1000, i64::MAX] {
+                let _ = reader.find_rowid(entry.root_page, rowid);
+            }
+        } else {
+            for key in [
+                vec![RecordValue::Null],
+                vec![RecordValue::Integer(1)],
+                vec![RecordValue::Text("a".to_string()), RecordValue::Integer(0)],
+                vec![RecordValue::Blob(vec![0xff])],
+            ] {
+                let _ = reader.find_index_key(entry, &key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::{BIG_PAGE_DB, SIMPLE_DB};
+    use crate::writer::{wal::build_wal, DatabaseBuilder};
+
+    /// xorshift64，测试结果可以复现
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n.max(1) as u64) as usize
+        }
+    }
+
+    /// 有多层内部页、溢出链、索引和 WITHOUT ROWID 表的小数据库
+    fn rich_db() -> Vec<u8> {
+        let rows = (1..=300)
+            .map(|i| {
+                vec![
+                    RecordValue::Null,
+                    RecordValue::Text(format!("row {i}")),
+                    RecordValue::Blob(vec![i as u8; if i % 40 == 0 { 1500 } else { 10 }]),
+                ]
+            })
+            .collect();
+        let pairs = (0..200)
+            .map(|i| {
+                vec![
+                    RecordValue::Integer(i),
+                    RecordValue::Text(format!("{i:>20}")),
+                ]
+            })
+            .collect();
+        DatabaseBuilder::new(512)
+            .table(
+                "CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT UNIQUE, body)",
+                rows,
+            )
+            .index("CREATE INDEX t_body ON t(body)")
+            .table("CREATE TABLE w(a PRIMARY KEY, b) WITHOUT ROWID", pairs)
+            .build()
+            .unwrap()
+    }
+
+    /// 第二个事务没有提交的 WAL
+    fn wal() -> Vec<u8> {
+        let db = rich_db();
+        let frames: Vec<(u32, &[u8], u32)> = db
+            .chunks(512)
+            .take(6)
+            .enumerate()
+            .map(|(i, page)| (i as u32 + 1, page, if i == 3 { 40 } else { 0 }))
+            .collect();
+        build_wal(512, &frames)
+    }
+
+    /// 在随机位置写入随机字节、小的页号、或者截断文件
+    fn mutate(rng: &mut Rng, bytes: &mut Vec<u8>) {
+        let page_count = (bytes.len() / 512).max(1) as u32;
+        for _ in 0..1 + rng.below(8) {
+            let pos = rng.below(bytes.len());
+            match rng.below(5) {
+                0 => bytes[pos] = rng.next() as u8,
+                1 => bytes[pos] ^= 1 << rng.below(8),
+                // 指向已有页的页号最容易造成环
+                2 if pos + 4 <= bytes.len() => {
+                    let page = rng.below(page_count as usize + 2) as u32;
+                    bytes[pos..pos + 4].copy_from_slice(&page.to_be_bytes());
+                }
+                3 if pos + 2 <= bytes.len() => {
+                    let v = rng.below(0x10000) as u16;
+                    bytes[pos..pos + 2].copy_from_slice(&v.to_be_bytes());
+                }
+                4 if rng.below(8) == 0 => bytes.truncate(pos.max(100)),
+                _ => bytes[pos] = 0xff,
+            }
+        }
+    }
+
+    #[test]
+    fn mutated_files_never_panic() {
+        let seeds = [SIMPLE_DB.to_vec(), BIG_PAGE_DB.to_vec(), rich_db(), wal()];
+        let mut rng = Rng(0x5eed_1234_abcd_0001);
+        for i in 0..600 {
+            let mut bytes = seeds[i % seeds.len()].clone();
+            mutate(&mut rng, &mut bytes);
+            exercise(&bytes);
+        }
+    }
+
+    #[test]
+    fn huge_wal_commit_is_rejected() {
+        // 校验和正确的 WAL 提交了 2^32 - 1 页、每页 64 KiB 的数据库
+        let page = vec![0; 65536];
+        let bytes = build_wal(65536, &[(u32::MAX, &page, u32::MAX)]);
+        exercise(&bytes);
+        let wal = Wal::parse(Rc::from(bytes)).unwrap();
+        assert_eq!(wal.db_size(), Some(u32::MAX));
+        let e = wal.apply(&[]).unwrap_err();
+        assert!(e.to_string().contains("larger than 1024 MiB"), "{e}");
+    }
+}
@@ -1,12 +1,16 @@
-use std::str::from_utf8;
+use std::borrow::Cow;
+
+use serde::{Serialize, Serializer};
 
 use crate::slc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DBHeader {
-    /// “SQLite format 3\0”，用于标识这是一个SQLite 3.x格式的数据库文件
+    /// “SQLite format 3\0”，用于标识这是一个SQLite 3.x格式的数据库文件。
+    /// 保存原始字节，序列化为字符串
     /// offset: 0, size: 16
-    pub header: String,
+    #[serde(serialize_with = "magic_str")]
+    pub header: [u8; 16],
     /// 数据库文件中每页的大小(Byte), value between 512 and 32768 inclusive
     /// 0x0001 for 65536
     /// offset: 16, size: 2
@@ -93,13 +97,17 @@ pub struct DBHeader {
     pub sqlite_version_number: u32,
 }
 
+fn magic_str<S: Serializer>(magic: &[u8; 16], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(magic))
+}
+
 impl TryFrom<&[u8; 100]> for DBHeader {
     type Error = anyhow::Error;
 
     fn try_from(value: &[u8; 100]) -> Result<Self, Self::Error> {
         Ok(Self::new(
             // header
-            std::str::from_utf8(&slc!(value, 0, 16))?.to_string(),
+            slc!(value, 0, 16).try_into()?,
             slc!(value, 16, 2, u16),
             slc!(value, 18, 1, u8),
             slc!(value, 19, 1, u8),
@@ -126,9 +134,46 @@ impl TryFrom<&[u8; 100]> for DBHeader {
     }
 }
 
+/// TryFrom<&[u8; 100]> 的逆操作
+impl From<&DBHeader> for [u8; 100] {
+    fn from(h: &DBHeader) -> Self {
+        let mut buf = [0u8; 100];
+        buf[..16].copy_from_slice(&h.header);
+        buf[16..18].copy_from_slice(&h.page_size.to_be_bytes());
+        buf[18] = h.write_version;
+        buf[19] = h.read_version;
+        buf[20] = h.reserved_page_size;
+        buf[21] = h.max_embeded_payload_fraction;
+        buf[22] = h.min_embeded_payload_fraction;
+        buf[23] = h.leaf_payload_fraction;
+        let words = [
+            (24, h.file_change_counter),
+            (28, h.db_size),
+            (32, h.first_freelist_trunk_page_number),
+            (36, h.total_number_of_freelist_pages),
+            (40, h.schema_cookie),
+            (44, h.schema_format),
+            (48, h.default_page_cache_size),
+            (52, h.lagest_root_btree_page_number),
+            (56, h.text_encoding),
+            (60, h.user_version),
+            (64, h.is_incremental_vacuum_mode),
+            (68, h.application_id),
+            (92, h.version_valid_for),
+            (96, h.sqlite_version_number),
+        ];
+        for (offset, value) in words {
+            buf[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+        }
+        buf[72..92].copy_from_slice(&h.expansion_reserved);
+        buf
+    }
+}
+
 impl DBHeader {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        header: String,
+        header: [u8; 16],
         page_size: u16,
         write_version: u8,
         read_version: u8,
@@ -180,9 +225,37 @@ impl DBHeader {
             sqlite_version_number,
         }
     }
+
+    /// 真实的页大小，page_size 为 1 时表示 65536
+    pub fn real_page_size(&self) -> usize {
+        match self.page_size {
+            1 => 65536,
+            n => n as usize,
+        }
+    }
+
+    /// 每页中可用于保存数据的字节数（去掉尾部保留区）
+    pub fn usable_size(&self) -> usize {
+        self.real_page_size() - self.reserved_page_size as usize
+    }
+
+    /// 序列化为文件开头的 100 字节
+    pub fn to_bytes(&self) -> [u8; 100] {
+        self.into()
+    }
+
+    /// 魔数按 UTF-8 解释的字符串，合法时不复制
+    pub fn magic(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.header)
+    }
+
+    /// 文本编码，非法值按 UTF-8 处理
+    pub fn encoding(&self) -> TextEncoding {
+        TextEncoding::try_from(self.text_encoding).unwrap_or(TextEncoding::UTF8)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum TextEncoding {
     UTF8,
     UTF16le,
@@ -215,9 +288,40 @@ impl std::fmt::Display for TextEncoding {
 impl TextEncoding {
     pub fn to_be_bytes(&self) -> [u8; 4] {
         match self {
-            Self::UTF8 => (1 as u32).to_be_bytes(),
-            Self::UTF16le => (2 as u32).to_be_bytes(),
-            Self::UTF16be => (3 as u32).to_be_bytes(),
+            Self::UTF8 => 1u32.to_be_bytes(),
+            Self::UTF16le => 2u32.to_be_bytes(),
+            Self::UTF16be => 3u32.to_be_bytes(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::{BIG_PAGE_DB, SIMPLE_DB};
+
+    #[test]
+    fn header_round_trip() {
+        for bytes in [SIMPLE_DB, BIG_PAGE_DB] {
+            let raw = <&[u8; 100]>::try_from(&bytes[..100]).unwrap();
+            let mut header = DBHeader::try_from(raw).unwrap();
+            assert_eq!(&header.to_bytes(), raw);
+
+            header.user_version = 7;
+            header.application_id = 0x0f0e0d0c;
+            let patched = header.to_bytes();
+            assert_eq!(patched[60..64], 7u32.to_be_bytes());
+            assert_eq!(patched[68..72], [15, 14, 13, 12]);
+            assert_eq!(patched[..60], raw[..60]);
+        }
+    }
+
+    #[test]
+    fn invalid_magic_round_trip() {
+        let mut raw = <[u8; 100]>::try_from(&SIMPLE_DB[..100]).unwrap();
+        raw[..16].copy_from_slice(b"SQLite\xff\xfeformat 3");
+        let header = DBHeader::try_from(&raw).unwrap();
+        assert_eq!(header.to_bytes(), raw);
+        assert!(header.magic().contains('\u{fffd}'));
+    }
+}
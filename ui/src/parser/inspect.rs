@@ -0,0 +1,228 @@
+/// 整数的宽度，3 和 6 字节是 record 中整数的宽度
+pub const INTEGER_SIZES: [usize; 6] = [1, 2, 3, 4, 6, 8];
+
+/// 按某种宽度读出的整数，大端和小端两种字节序
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegerReading {
+    pub size: usize,
+    pub signed: bool,
+    pub big: i128,
+    pub little: i128,
+}
+
+impl IntegerReading {
+    /// 例如 u16、i24
+    pub fn name(&self) -> String {
+        let sign = if self.signed { "i" } else { "u" };
+        format!("{sign}{}", self.size * 8)
+    }
+}
+
+/// bytes 开头能读出的所有宽度的无符号和有符号整数
+pub fn integers(bytes: &[u8]) -> Vec<IntegerReading> {
+    let mut readings = vec![];
+    for size in INTEGER_SIZES {
+        let Some(bytes) = bytes.get(..size) else {
+            break;
+        };
+        let unsigned =
+            |bytes: &mut dyn Iterator<Item = &u8>| bytes.fold(0u128, |v, &b| (v << 8) | b as u128);
+        let big = unsigned(&mut bytes.iter());
+        let little = unsigned(&mut bytes.iter().rev());
+        // 最高位为 1 时减去 2^(8*size) 得到补码表示的负数
+        let signed = |v: u128| {
+            let bits = 8 * size as u32;
+            if v >> (bits - 1) == 1 {
+                v as i128 - (1i128 << bits)
+            } else {
+                v as i128
+            }
+        };
+        readings.push(IntegerReading {
+            size,
+            signed: false,
+            big: big as i128,
+            little: little as i128,
+        });
+        readings.push(IntegerReading {
+            size,
+            signed: true,
+            big: signed(big),
+            little: signed(little),
+        });
+    }
+    readings
+}
+
+/// IEEE 754 双精度浮点数的三部分
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Float {
+    pub value: f64,
+    pub sign: u8,
+    /// 11 位的指数，偏移 1023
+    pub exponent: u16,
+    /// 52 位的尾数，不含隐含的最高位
+    pub mantissa: u64,
+}
+
+impl Float {
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            value: f64::from_bits(bits),
+            sign: (bits >> 63) as u8,
+            exponent: ((bits >> 52) & 0x7ff) as u16,
+            mantissa: bits & ((1 << 52) - 1),
+        }
+    }
+
+    /// 按字节序读取 bytes 开头的 8 个字节
+    pub fn read(bytes: &[u8], big_endian: bool) -> Option<Self> {
+        let bytes: [u8; 8] = bytes.get(..8)?.try_into().ok()?;
+        let bits = if big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        };
+        Some(Self::from_bits(bits))
+    }
+
+    /// 值的种类，决定指数和尾数怎样组成值
+    pub fn class(&self) -> &'static str {
+        match (self.exponent, self.mantissa) {
+            (0, 0) => "zero",
+            (0, _) => "subnormal",
+            (0x7ff, 0) => "infinity",
+            (0x7ff, _) => "NaN",
+            _ => "normal",
+        }
+    }
+
+    /// 值的计算方法，例如 (-1)^0 × 1.5 × 2^1
+    pub fn formula(&self) -> String {
+        let fraction = self.mantissa as f64 / (1u64 << 52) as f64;
+        match self.class() {
+            "normal" => format!(
+                "(-1)^{} × {} × 2^{}",
+                self.sign,
+                1.0 + fraction,
+                self.exponent as i32 - 1023
+            ),
+            "subnormal" => format!("(-1)^{} × {fraction} × 2^-1022", self.sign),
+            _ => self.class().to_string(),
+        }
+    }
+}
+
+/// 解码文本的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextReading {
+    Utf8,
+    Utf16le,
+    Utf16be,
+}
+
+impl TextReading {
+    pub const ALL: [TextReading; 3] = [Self::Utf8, Self::Utf16le, Self::Utf16be];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16le => "UTF-16le",
+            Self::Utf16be => "UTF-16be",
+        }
+    }
+
+    /// 解码 bytes。无法解码的部分显示为 U+FFFD，并返回第一个无法解码的字节的位置
+    pub fn decode(self, bytes: &[u8]) -> (String, Option<usize>) {
+        let units = |to_u16: fn([u8; 2]) -> u16| -> Vec<u16> {
+            bytes
+                .chunks_exact(2)
+                .map(|c| to_u16([c[0], c[1]]))
+                .collect()
+        };
+        match self {
+            Self::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(text) => (text.to_string(), None),
+                Err(e) => (
+                    String::from_utf8_lossy(bytes).into_owned(),
+                    Some(e.valid_up_to()),
+                ),
+            },
+            Self::Utf16le | Self::Utf16be => {
+                let units = units(if self == Self::Utf16le {
+                    u16::from_le_bytes
+                } else {
+                    u16::from_be_bytes
+                });
+                let mut text = String::new();
+                let mut error = (bytes.len() % 2 == 1).then_some(bytes.len() - 1);
+                let mut pos = 0;
+                for c in char::decode_utf16(units) {
+                    match c {
+                        Ok(c) => {
+                            text.push(c);
+                            pos += 2 * c.len_utf16();
+                        }
+                        Err(_) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            error = Some(error.map_or(pos, |e: usize| e.min(pos)));
+                            pos += 2;
+                        }
+                    }
+                }
+                (text, error)
+            }
+        }
+    }
+}
+
+/// 每个字节的 8 位，字节之间用空格分隔
+pub fn bits(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:08b}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_bytes_in_every_way() {
+        let bytes = [0xff, 0xfe, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let readings = integers(&bytes);
+        assert_eq!(readings.len(), 12);
+        let find = |name: &str| readings.iter().find(|r| r.name() == name).unwrap();
+        assert_eq!(find("u8").big, 255);
+        assert_eq!(find("i8").big, -1);
+        assert_eq!((find("u16").big, find("u16").little), (0xfffe, 0xfeff));
+        assert_eq!(find("i16").big, -2);
+        assert_eq!(find("i24").big, -512);
+        assert_eq!(find("u64").little, 0x0100_feff);
+        assert_eq!(integers(&bytes[..3]).last().unwrap().name(), "i24");
+
+        let one_and_a_half = Float::read(&1.5f64.to_be_bytes(), true).unwrap();
+        assert_eq!((one_and_a_half.sign, one_and_a_half.exponent), (0, 1023));
+        assert_eq!(one_and_a_half.mantissa, 1 << 51);
+        assert_eq!(one_and_a_half.formula(), "(-1)^0 × 1.5 × 2^0");
+        let negative = Float::read(&(-4.0f64).to_le_bytes(), false).unwrap();
+        assert_eq!((negative.value, negative.sign), (-4.0, 1));
+        assert_eq!(Float::from_bits(f64::NAN.to_bits()).class(), "NaN");
+        assert_eq!(Float::from_bits(1).class(), "subnormal");
+        assert!(Float::read(&bytes[..7], true).is_none());
+
+        assert_eq!(TextReading::Utf8.decode(b"abc"), ("abc".to_string(), None));
+        assert_eq!(TextReading::Utf8.decode(b"ab\xff").1, Some(2));
+        assert_eq!(
+            TextReading::Utf16le.decode(&[b'h', 0, b'i', 0]),
+            ("hi".to_string(), None)
+        );
+        assert_eq!(
+            TextReading::Utf16be.decode(&[0, b'h', 0xdc, 0x00, 0]),
+            ("h\u{fffd}".to_string(), Some(2))
+        );
+        assert_eq!(bits(&[5, 0x80]), "00000101 10000000");
+    }
+}
@@ -0,0 +1,190 @@
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use super::Bytes;
+use crate::slc;
+
+/// 回滚日志头开头的 8 字节
+pub const JOURNAL_MAGIC: [u8; 8] = [0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7];
+
+/// 日志头中有意义的字节数，日志头占满一个扇区
+pub const JOURNAL_HEADER_SIZE: usize = 28;
+
+/// 回滚日志中一段的头。缓存溢出时 SQLite 会在日志中开始新的一段，每段都有自己的头。
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalHeader {
+    /// 段序号，从 1 开始
+    pub index: usize,
+    /// 头在日志文件中的偏移，是扇区大小的倍数
+    pub offset: usize,
+    /// 0xd9d505f920a163d7，提交后被清零时日志不再有效
+    /// offset: 0, size: 8
+    pub magic: [u8; 8],
+    /// 这一段中的记录数，0xffffffff 表示一直到文件末尾
+    /// offset: 8, size: 4
+    pub page_count: u32,
+    /// 计算记录校验和的随机初值
+    /// offset: 12, size: 4
+    pub nonce: u32,
+    /// 事务开始前数据库的页数，回滚时截断到这个大小
+    /// offset: 16, size: 4
+    pub initial_size: u32,
+    /// 磁盘扇区的大小，头占用一个扇区
+    /// offset: 20, size: 4
+    pub sector_size: u32,
+    /// 数据库的页大小
+    /// offset: 24, size: 4
+    pub page_size: u32,
+}
+
+/// 事务修改一页前保存的原始内容
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalRecord {
+    /// 记录序号，从 1 开始
+    pub index: usize,
+    /// 所在段的序号
+    pub segment: usize,
+    /// 记录在日志文件中的偏移
+    pub offset: usize,
+    /// 原始内容所属的页
+    /// offset: 0, size: 4
+    pub page_number: u32,
+    /// 修改前的页内容
+    /// offset: 4, size: page size
+    pub content: Bytes,
+    /// nonce 加上页中从末尾起每隔 200 字节取一个字节的和
+    /// offset: 4 + page size, size: 4
+    pub checksum: u32,
+    /// 校验和是否正确
+    pub valid: bool,
+}
+
+/// 解析后的回滚日志。日志头被清零的日志已经提交，不会被回滚。
+#[derive(Debug, Clone, Serialize)]
+pub struct Journal {
+    pub headers: Vec<Rc<JournalHeader>>,
+    pub records: Vec<Rc<JournalRecord>>,
+}
+
+/// 记录的校验和
+pub fn record_checksum(nonce: u32, content: &[u8]) -> u32 {
+    (1..)
+        .map(|i| content.len() as isize - 200 * i)
+        .take_while(|&i| i > 0)
+        .fold(nonce, |sum, i| sum.wrapping_add(content[i as usize] as u32))
+}
+
+impl Journal {
+    /// 依次解析每一段的头和记录。第一个头的 magic 不对时日志没有有效内容，不算错误。
+    pub fn parse(bytes: Rc<[u8]>) -> Result<Self> {
+        let value = &bytes[..];
+        let mut headers: Vec<Rc<JournalHeader>> = vec![];
+        let mut records = vec![];
+        let mut offset = 0;
+        while offset + JOURNAL_HEADER_SIZE <= value.len()
+            && value[offset..offset + 8] == JOURNAL_MAGIC
+        {
+            let header = JournalHeader {
+                index: headers.len() + 1,
+                offset,
+                magic: JOURNAL_MAGIC,
+                page_count: slc!(value, offset + 8, 4, u32),
+                nonce: slc!(value, offset + 12, 4, u32),
+                initial_size: slc!(value, offset + 16, 4, u32),
+                sector_size: slc!(value, offset + 20, 4, u32),
+                page_size: slc!(value, offset + 24, 4, u32),
+            };
+            // 之后的段沿用第一段的页大小和扇区大小
+            let (page_size, sector_size) = match headers.first() {
+                Some(first) => (first.page_size, first.sector_size),
+                None => (header.page_size, header.sector_size),
+            };
+            for (name, size) in [("page", page_size), ("sector", sector_size)] {
+                if !(512..=65536).contains(&size) || !size.is_power_of_two() {
+                    bail!("Invalid journal {name} size {size}");
+                }
+            }
+            let (page_size, sector_size) = (page_size as usize, sector_size as usize);
+            let record_size = page_size + 8;
+            let start = offset + sector_size;
+            let available = value.len().saturating_sub(start) / record_size;
+            let count = match header.page_count {
+                u32::MAX => available,
+                n => (n as usize).min(available),
+            };
+            for i in 0..count {
+                let pos = start + i * record_size;
+                let content = Bytes::new(bytes.clone(), pos + 4..pos + 4 + page_size);
+                let checksum = slc!(value, pos + 4 + page_size, 4, u32);
+                records.push(Rc::new(JournalRecord {
+                    index: records.len() + 1,
+                    segment: header.index,
+                    offset: pos,
+                    page_number: slc!(value, pos, 4, u32),
+                    valid: checksum == record_checksum(header.nonce, &content),
+                    checksum,
+                    content,
+                }));
+            }
+            headers.push(Rc::new(header));
+            // 下一段从扇区边界开始
+            offset = (start + count * record_size).div_ceil(sector_size) * sector_size;
+        }
+        Ok(Self { headers, records })
+    }
+
+    /// 日志是否会在下次打开数据库时被回滚
+    pub fn is_hot(&self) -> bool {
+        !self.headers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 两段的日志，第二段的记录数写作 0xffffffff
+    fn journal(page_size: usize, pages: &[(u32, u8)]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let (first, second) = pages.split_at(1);
+        for (count, pages) in [(first.len() as u32, first), (u32::MAX, second)] {
+            bytes.resize(bytes.len().div_ceil(512) * 512, 0);
+            bytes.extend(JOURNAL_MAGIC);
+            for v in [count, 42, 3, 512, page_size as u32] {
+                bytes.extend(v.to_be_bytes());
+            }
+            bytes.resize(bytes.len().div_ceil(512) * 512, 0);
+            for &(number, fill) in pages {
+                let content = vec![fill; page_size];
+                bytes.extend(number.to_be_bytes());
+                bytes.extend(&content);
+                bytes.extend(record_checksum(42, &content).to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_segments() {
+        let mut bytes = journal(1024, &[(2, 0xaa), (5, 0xbb), (3, 0xcc)]);
+        // 损坏第三条记录中参与校验和的字节
+        let last = bytes.len() - 4 - 200;
+        bytes[last] ^= 0xff;
+        let journal = Journal::parse(Rc::from(bytes)).unwrap();
+        assert!(journal.is_hot());
+        assert_eq!(journal.headers.len(), 2);
+        assert_eq!(journal.headers[1].offset, 2048);
+        let pages: Vec<_> = journal.records.iter().map(|r| r.page_number).collect();
+        assert_eq!(pages, vec![2, 5, 3]);
+        assert!(journal.records[0].valid && journal.records[1].valid);
+        assert!(!journal.records[2].valid);
+        assert!(journal.records[1].content.iter().all(|&b| b == 0xbb));
+
+        // 提交后日志头被清零
+        let journal = Journal::parse(Rc::from(vec![0; 2048])).unwrap();
+        assert!(!journal.is_hot());
+        assert!(journal.records.is_empty());
+    }
+}
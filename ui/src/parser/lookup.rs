@@ -0,0 +1,292 @@
+use std::{cmp::Ordering, rc::Rc};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use super::{
+    compare::{apply_affinity, compare_key},
+    reader::MAX_DEPTH,
+    Page, Reader, RecordValue, SchemaEntry,
+};
+
+/// 一次 B-Tree 查找的全过程，从根页一直到叶子页
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Descent {
+    pub steps: Vec<DescentStep>,
+    /// 找到的目标所在的页和单元。索引的目标也可能在路径上的某个内部页里
+    pub found: Option<(u32, usize)>,
+}
+
+/// 查找过程中访问的一页
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DescentStep {
+    pub page: u32,
+    /// 页内二分查找依次比较过的单元下标
+    pub probes: Vec<usize>,
+    /// 最终选中的单元。内部页上为 None 表示走最右指针；叶子页上为 None 表示没找到
+    pub cell: Option<usize>,
+    /// 下一步访问的子页，叶子页为 None
+    pub child: Option<u32>,
+}
+
+/// 页内二分查找，与 SQLite 的 moveto 相同。`cmp(i)` 返回第 i 个单元相对于目标的顺序。
+/// 返回比较过的单元、第一个 >= 目标的单元下标、以及相等的单元。
+/// `stop_at_equal` 为 false 时遇到相等的单元继续向左找，得到第一个相等的单元。
+fn binary_search(
+    n: usize,
+    stop_at_equal: bool,
+    mut cmp: impl FnMut(usize) -> Result<Ordering>,
+) -> Result<(Vec<usize>, usize, Option<usize>)> {
+    let mut probes = vec![];
+    let (mut lwr, mut upr) = (0i64, n as i64 - 1);
+    let mut hit = None;
+    while lwr <= upr {
+        let idx = ((lwr + upr) / 2) as usize;
+        probes.push(idx);
+        match cmp(idx)? {
+            Ordering::Less => lwr = idx as i64 + 1,
+            Ordering::Greater => upr = idx as i64 - 1,
+            Ordering::Equal => {
+                hit = Some(idx);
+                if stop_at_equal {
+                    lwr = idx as i64;
+                    break;
+                }
+                upr = idx as i64 - 1;
+            }
+        }
+    }
+    Ok((probes, lwr as usize, hit))
+}
+
+impl Reader {
+    /// 下降过程中取下一页，检查循环和深度
+    fn descent_page(&self, root: u32, number: u32, steps: &[DescentStep]) -> Result<Rc<Page>> {
+        if steps.len() >= MAX_DEPTH || steps.iter().any(|s| s.page == number) {
+            bail!("B-Tree rooted at page {root} has a cycle at page {number}");
+        }
+        match self.page(number) {
+            Some(page) if matches!(*page, Page::BTree(_)) => Ok(page),
+            _ => bail!("Page {number} is not a b-tree page"),
+        }
+    }
+
+    /// 按 SQLite 的方式在表 B-Tree 中查找 rowid：
+    /// 在每一页上对单元的 rowid 做二分查找，内部页上走第一个 rowid >= 目标的单元的左子页，
+    /// 没有这样的单元时走最右指针，直到叶子页。
+    pub fn find_rowid(&self, root: u32, rowid: i64) -> Result<Descent> {
+        let mut steps: Vec<DescentStep> = vec![];
+        let mut number = root;
+        loop {
+            let rc = self.descent_page(root, number, &steps)?;
+            let Page::BTree(page) = rc.as_ref() else {
+                unreachable!()
+            };
+            if !page.header.page_type.is_table() {
+                bail!("Page {number} is not a table b-tree page");
+            }
+            let (probes, lwr, hit) = binary_search(page.cells.len(), true, |i| {
+                Ok(page.cells[i].rowid.map_or(0, |r| r.0).cmp(&rowid))
+            })?;
+
+            if page.header.page_type.is_leaf() {
+                steps.push(DescentStep {
+                    page: number,
+                    probes,
+                    cell: hit,
+                    child: None,
+                });
+                return Ok(Descent {
+                    found: hit.map(|i| (number, i)),
+                    steps,
+                });
+            }
+
+            // 内部页：第 lwr 个单元的 rowid 是第一个 >= 目标的键
+            let (cell, child) = match page.cells.get(lwr) {
+                Some(c) => (Some(lwr), c.left_child),
+                None => (None, page.header.right_most_pointer),
+            };
+            let Some(child) = child else {
+                bail!("Interior page {number} has no child pointer");
+            };
+            steps.push(DescentStep {
+                page: number,
+                probes,
+                cell,
+                child: Some(child),
+            });
+            number = child;
+        }
+    }
+
+    /// 在索引 B-Tree（或 WITHOUT ROWID 表）中查找第一个 >= key 的条目，key 可以只给出前几列。
+    /// 每列按索引的排序规则、DESC 和 SQLite 的类型顺序比较，比较之前对 key 应用列的亲和性。
+    /// 索引的内部页单元本身也是条目，所以目标可能在路径上的内部页里。
+    pub fn find_index_key(&self, entry: &SchemaEntry, key: &[RecordValue]) -> Result<Descent> {
+        let root = entry.root_page;
+        if key.is_empty() {
+            bail!("The key is empty");
+        }
+        if !entry.columns.is_empty() && key.len() > entry.columns.len() {
+            bail!(
+                "{} has only {} column(s), got {} value(s)",
+                entry.name,
+                entry.columns.len(),
+                key.len()
+            );
+        }
+        let key: Vec<RecordValue> = key
+            .iter()
+            .enumerate()
+            .map(|(i, v)| match entry.columns.get(i) {
+                Some(c) => apply_affinity(v.clone(), c.affinity),
+                None => v.clone(),
+            })
+            .collect();
+
+        let mut steps: Vec<DescentStep> = vec![];
+        // 最近一次走左子页的内部页单元，它是左子树中所有条目的后继
+        let mut successor: Option<(u32, usize, bool)> = None;
+        let mut number = root;
+        loop {
+            let rc = self.descent_page(root, number, &steps)?;
+            let Page::BTree(page) = rc.as_ref() else {
+                unreachable!()
+            };
+            if page.header.page_type.is_table() {
+                bail!("Page {number} is not an index b-tree page");
+            }
+            let cmp = |i: usize| -> Result<Ordering> {
+                let Some(record) = page.cells[i]
+                    .payload
+                    .as_ref()
+                    .and_then(|p| p.record.as_ref())
+                else {
+                    bail!("Cannot decode the key in cell {i} of page {number}");
+                };
+                let values: Vec<&RecordValue> = record.columns.iter().map(|c| &c.value).collect();
+                Ok(compare_key(&values, &key, &entry.columns))
+            };
+            let (probes, lwr, hit) = binary_search(page.cells.len(), false, cmp)?;
+
+            if page.header.page_type.is_leaf() {
+                let found = match hit {
+                    Some(i) => Some((number, i)),
+                    // 叶子页上所有条目都小于目标，后继单元就是第一个 >= 目标的条目
+                    None if lwr == page.cells.len() => {
+                        successor.filter(|s| s.2).map(|(p, i, _)| (p, i))
+                    }
+                    None => None,
+                };
+                steps.push(DescentStep {
+                    page: number,
+                    probes,
+                    cell: hit,
+                    child: None,
+                });
+                return Ok(Descent { steps, found });
+            }
+
+            let (cell, child) = match page.cells.get(lwr) {
+                Some(c) => {
+                    successor = Some((number, lwr, hit.is_some()));
+                    (Some(lwr), c.left_child)
+                }
+                None => (None, page.header.right_most_pointer),
+            };
+            let Some(child) = child else {
+                bail!("Interior page {number} has no child pointer");
+            };
+            steps.push(DescentStep {
+                page: number,
+                probes,
+                cell,
+                child: Some(child),
+            });
+            number = child;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ui::viewer::SIMPLE_DB, writer::DatabaseBuilder};
+
+    #[test]
+    fn find_rowid_in_leaf_root() {
+        let reader = Reader::new(SIMPLE_DB).unwrap();
+        let descent = reader.find_rowid(2, 3).unwrap();
+        assert_eq!(descent.found, Some((2, 2)));
+        assert_eq!(descent.steps.len(), 1);
+        assert_eq!(descent.steps[0].cell, Some(2));
+        assert_eq!(descent.steps[0].probes, [1, 2]);
+
+        let descent = reader.find_rowid(2, 10).unwrap();
+        assert_eq!(descent.found, None);
+        assert!(reader.find_rowid(1, 1).is_ok());
+    }
+
+    #[test]
+    fn find_index_key_with_desc_and_collation() {
+        let rows = (1..=300)
+            .map(|i| {
+                let name = if i % 2 == 0 { "Name" } else { "NAME" };
+                vec![
+                    RecordValue::Text(format!("{name}-{i:03}")),
+                    RecordValue::Integer(i),
+                ]
+            })
+            .collect();
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(name TEXT, n INTEGER)", rows)
+            .index("CREATE INDEX t_name ON t(name COLLATE NOCASE)")
+            .index("CREATE INDEX t_n ON t(n DESC)")
+            .build()
+            .unwrap();
+        let reader = Reader::new(&bytes).unwrap();
+        let entry = |name: &str| reader.schema.iter().find(|e| e.name == name).unwrap();
+        let key = |found: Option<(u32, usize)>| {
+            let (page, cell) = found.expect("key not found");
+            let Page::BTree(page) = &*reader.page(page).unwrap() else {
+                panic!("not a b-tree page");
+            };
+            let record = page.cells[cell].payload.as_ref().unwrap().record.as_ref();
+            record.unwrap().columns[0].value.clone()
+        };
+
+        // NOCASE 忽略大小写，整数按 TEXT 亲和性转为文本
+        let name = entry("t_name");
+        let text = |s: &str| RecordValue::Text(s.to_string());
+        let descent = reader.find_index_key(name, &[text("name-123")]).unwrap();
+        assert!(descent.steps.len() > 1);
+        assert_eq!(key(descent.found), text("NAME-123"));
+        let descent = reader.find_index_key(name, &[text("name-301")]).unwrap();
+        assert_eq!(descent.found, None);
+
+        // DESC 列从大到小排列
+        let n = entry("t_n");
+        for i in [1, 150, 300] {
+            let descent = reader
+                .find_index_key(n, &[RecordValue::Integer(i)])
+                .unwrap();
+            assert_eq!(key(descent.found), RecordValue::Integer(i));
+        }
+        let descent = reader
+            .find_index_key(n, &[RecordValue::Integer(0)])
+            .unwrap();
+        assert_eq!(descent.found, None);
+        assert!(reader.find_index_key(n, &[]).is_err());
+        assert!(reader
+            .find_index_key(
+                n,
+                &[
+                    RecordValue::Integer(1),
+                    RecordValue::Integer(2),
+                    RecordValue::Null
+                ]
+            )
+            .is_err());
+    }
+}
@@ -1,4 +1,32 @@
+pub mod analysis;
+pub mod bytes;
+pub mod compare;
+pub mod coverage;
+pub mod diagnostic;
+pub mod fuzz;
 mod header;
+pub mod inspect;
+pub mod journal;
+pub mod lookup;
+pub mod page;
 pub mod reader;
+pub mod record;
+pub mod schema;
+pub mod search;
+pub mod shm;
+pub mod source;
+pub mod varint;
+pub mod wal;
+pub use analysis::{Analysis, SpaceUsage};
+pub use bytes::Bytes;
+pub use compare::{compare_values, Collation};
+pub use coverage::{Coverage, Span, SpanKind};
+pub use diagnostic::{Diagnostic, Severity, Structure};
 pub use header::{DBHeader, TextEncoding};
-pub use reader::Reader;
+pub use lookup::{Descent, DescentStep};
+pub use page::{BTreePage, Cell, Page, PageType};
+pub use reader::{ParseResult, Reader};
+pub use record::{Record, RecordValue};
+pub use schema::{Affinity, SchemaEntry, Statement};
+pub use source::{FileSource, Lru, MemorySource, PageCache, PageSource};
+pub use wal::{Wal, WalFrame, WalHeader};
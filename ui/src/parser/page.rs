@@ -0,0 +1,310 @@
+use std::{ops::Range, rc::Rc};
+
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+
+use super::{bytes::Bytes, record::Record, schema::SchemaEntry};
+use crate::slc;
+
+/// 数据库文件中的一页
+#[derive(Debug, Clone, Serialize)]
+pub enum Page {
+    BTree(BTreePage),
+    Overflow(OverflowPage),
+    FreelistTrunk(FreelistTrunkPage),
+    FreelistLeaf(PageInfo),
+    /// 没有被任何 B-Tree、溢出链或空闲列表引用的页，例如 lock-byte 页和 pointer map 页
+    Unknown(PageInfo),
+}
+
+impl Page {
+    pub fn info(&self) -> &PageInfo {
+        match self {
+            Self::BTree(p) => &p.info,
+            Self::Overflow(p) => &p.info,
+            Self::FreelistTrunk(p) => &p.info,
+            Self::FreelistLeaf(info) | Self::Unknown(info) => info,
+        }
+    }
+}
+
+/// 所有页共有的信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageInfo {
+    /// 页号，从 1 开始
+    pub number: u32,
+    /// 页在文件中的起始偏移
+    pub offset: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PageType {
+    InteriorIndex,
+    InteriorTable,
+    LeafIndex,
+    LeafTable,
+}
+
+impl TryFrom<u8> for PageType {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            2 => Ok(Self::InteriorIndex),
+            5 => Ok(Self::InteriorTable),
+            10 => Ok(Self::LeafIndex),
+            13 => Ok(Self::LeafTable),
+            _ => Err(format!("Wrong b-tree page type: {}", value)),
+        }
+    }
+}
+
+impl std::fmt::Display for PageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InteriorIndex => write!(f, "Interior Index"),
+            Self::InteriorTable => write!(f, "Interior Table"),
+            Self::LeafIndex => write!(f, "Leaf Index"),
+            Self::LeafTable => write!(f, "Leaf Table"),
+        }
+    }
+}
+
+impl PageType {
+    pub fn is_leaf(&self) -> bool {
+        matches!(self, Self::LeafIndex | Self::LeafTable)
+    }
+
+    pub fn is_table(&self) -> bool {
+        matches!(self, Self::InteriorTable | Self::LeafTable)
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            Self::InteriorIndex => 2,
+            Self::InteriorTable => 5,
+            Self::LeafIndex => 10,
+            Self::LeafTable => 13,
+        }
+    }
+}
+
+/// B-Tree 页头，叶子页 8 字节，内部页 12 字节
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageHeader {
+    /// offset: 0, size: 1
+    pub page_type: PageType,
+    /// 第一个空闲块的偏移，0 表示没有空闲块
+    /// offset: 1, size: 2
+    pub first_freeblock: u16,
+    /// 页中的单元数
+    /// offset: 3, size: 2
+    pub cell_count: u16,
+    /// 单元内容区的起始偏移，0 表示 65536
+    /// offset: 5, size: 2
+    pub cell_content_start: u16,
+    /// 单元内容区中碎片字节的总数
+    /// offset: 7, size: 1
+    pub fragmented_free_bytes: u8,
+    /// 最右子页的页号，只有内部页才有
+    /// offset: 8, size: 4
+    pub right_most_pointer: Option<u32>,
+}
+
+impl PageHeader {
+    pub fn parse(value: &[u8]) -> Result<Self> {
+        let page_type = PageType::try_from(slc!(value, 0, 1, u8)).map_err(anyhow::Error::msg)?;
+        Ok(Self {
+            page_type,
+            first_freeblock: slc!(value, 1, 2, u16),
+            cell_count: slc!(value, 3, 2, u16),
+            cell_content_start: slc!(value, 5, 2, u16),
+            fragmented_free_bytes: slc!(value, 7, 1, u8),
+            right_most_pointer: if page_type.is_leaf() {
+                None
+            } else {
+                Some(slc!(value, 8, 4, u32))
+            },
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        if self.page_type.is_leaf() {
+            8
+        } else {
+            12
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BTreePage {
+    pub info: PageInfo,
+    /// 页头在文件中的偏移，第 1 页的页头在数据库头之后（offset 100）
+    pub header_offset: usize,
+    pub header: PageHeader,
+    /// 单元指针数组，每个指针是单元相对页起始的偏移
+    pub cell_pointers: Vec<u16>,
+    pub cells: Vec<Cell>,
+    /// 这一页所属的表或索引，序列化时只保留名字
+    #[serde(serialize_with = "owner_name")]
+    pub owner: Option<Rc<SchemaEntry>>,
+}
+
+fn owner_name<S: Serializer>(owner: &Option<Rc<SchemaEntry>>, s: S) -> Result<S::Ok, S::Error> {
+    owner.as_ref().map(|o| &o.name).serialize(s)
+}
+
+impl BTreePage {
+    /// 单元指针数组在文件中的偏移
+    pub fn cell_pointers_offset(&self) -> usize {
+        self.header_offset + self.header.size()
+    }
+}
+
+/// B-Tree 单元。不同类型的页包含的部分不同：
+///
+/// | 页类型         | left child | payload size | rowid | payload |
+/// |----------------|------------|--------------|-------|---------|
+/// | Leaf Table     |            | ✓            | ✓     | ✓       |
+/// | Interior Table | ✓          |              | ✓     |         |
+/// | Leaf Index     |            | ✓            |       | ✓       |
+/// | Interior Index | ✓          | ✓            |       | ✓       |
+#[derive(Debug, Clone, Serialize)]
+pub struct Cell {
+    /// 单元在文件中的偏移
+    pub offset: usize,
+    pub left_child: Option<u32>,
+    /// (payload 总大小, varint 字节数)
+    pub payload_size: Option<(u64, usize)>,
+    /// (rowid, varint 字节数)
+    pub rowid: Option<(i64, usize)>,
+    pub payload: Option<Payload>,
+}
+
+impl Cell {
+    /// 单元保存在本页内的字节数（包括溢出页指针）
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        if self.left_child.is_some() {
+            size += 4;
+        }
+        if let Some((_, len)) = self.payload_size {
+            size += len;
+        }
+        if let Some((_, len)) = self.rowid {
+            size += len;
+        }
+        if let Some(payload) = &self.payload {
+            size += payload.local_size;
+            if payload.first_overflow.is_some() {
+                size += 4;
+            }
+        }
+        size
+    }
+}
+
+/// 单元的负载。超过本地上限的部分保存在溢出页链表中。
+#[derive(Debug, Clone, Serialize)]
+pub struct Payload {
+    /// 本地部分在文件中的偏移
+    pub offset: usize,
+    pub local_size: usize,
+    /// 第一个溢出页的页号，保存在本地部分之后
+    pub first_overflow: Option<u32>,
+    /// 溢出链上的页（页号, 内容在文件中的偏移）
+    pub overflow: Vec<(u32, usize)>,
+    /// 每个溢出页中保存的负载字节数（usable size - 4）
+    pub overflow_chunk: usize,
+    /// 完整的负载。没有溢出时是页缓冲区的视图，有溢出时是拼接后的副本
+    pub bytes: Bytes,
+    pub record: Option<Record>,
+}
+
+impl Payload {
+    /// 负载中的一段在文件中对应的若干段：本地部分和各个溢出页中的部分
+    pub fn file_ranges(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let end = range.end.min(self.bytes.len());
+        let mut pieces = vec![(0, self.local_size, self.offset)];
+        pieces.extend(self.overflow.iter().enumerate().map(|(k, &(_, offset))| {
+            let start = self.local_size + k * self.overflow_chunk;
+            (start, start + self.overflow_chunk, offset)
+        }));
+        pieces
+            .into_iter()
+            .filter_map(|(start, stop, offset)| {
+                let from = range.start.max(start);
+                let to = end.min(stop);
+                (from < to).then(|| offset + from - start..offset + to - start)
+            })
+            .collect()
+    }
+
+    /// 负载中的偏移对应的文件偏移
+    pub fn file_offset(&self, payload_offset: usize) -> usize {
+        if payload_offset < self.local_size || self.overflow.is_empty() {
+            return self.offset + payload_offset;
+        }
+        let rest = payload_offset - self.local_size;
+        let idx = (rest / self.overflow_chunk).min(self.overflow.len() - 1);
+        self.overflow[idx].1 + rest - idx * self.overflow_chunk
+    }
+}
+
+/// 计算负载保存在本地的字节数。
+/// 见 https://www.sqlite.org/fileformat2.html#b_tree_pages 中关于溢出的说明。
+pub fn local_payload_size(page_type: PageType, usable_size: usize, payload_size: usize) -> usize {
+    let u = usable_size;
+    let x = if page_type.is_table() {
+        u - 35
+    } else {
+        (u - 12) * 64 / 255 - 23
+    };
+    if payload_size <= x {
+        return payload_size;
+    }
+    let m = (u - 12) * 32 / 255 - 23;
+    let k = m + (payload_size - m) % (u - 4);
+    if k <= x {
+        k
+    } else {
+        m
+    }
+}
+
+/// 溢出页：4 字节的下一页页号，之后是负载内容
+#[derive(Debug, Clone, Serialize)]
+pub struct OverflowPage {
+    pub info: PageInfo,
+    /// 下一个溢出页，0 表示链表结束
+    pub next: u32,
+    /// 本页中保存的负载内容，页缓冲区的视图
+    pub content: Bytes,
+}
+
+/// 空闲列表主干页：下一个主干页页号、叶子页数量、叶子页页号数组
+#[derive(Debug, Clone, Serialize)]
+pub struct FreelistTrunkPage {
+    pub info: PageInfo,
+    pub next: u32,
+    pub leaves: Vec<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_payload_sizes() {
+        // 4096 字节的页，表叶子页本地最多 4061 字节
+        assert_eq!(local_payload_size(PageType::LeafTable, 4096, 4061), 4061);
+        let local = local_payload_size(PageType::LeafTable, 4096, 5000);
+        assert!(local < 4061);
+        assert_eq!(local, 489 + (5000 - 489) % 4092);
+        // 索引页的上限更小
+        assert_eq!(local_payload_size(PageType::LeafIndex, 4096, 1002), 1002);
+        assert_eq!(local_payload_size(PageType::LeafIndex, 4096, 1003), 489);
+    }
+}
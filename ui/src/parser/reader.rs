@@ -1,17 +1,1040 @@
-use anyhow::Result;
-use std::rc::Rc;
+use std::{
+    cell::{self, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use super::{
+    bytes::Bytes,
+    diagnostic::{check_header, check_page_size, Diagnostic, Structure},
+    page::{
+        local_payload_size, BTreePage, Cell, FreelistTrunkPage, OverflowPage, Page, PageHeader,
+        PageInfo, PageType, Payload,
+    },
+    record::{Record, RecordValue},
+    schema::{SchemaEntry, SQLITE_SCHEMA_SQL},
+    source::{Lru, MemorySource, PageCache, PageSource},
+    varint::read_varint,
+    DBHeader, TextEncoding,
+};
+
+/// 解析函数的结果，错误中带有出错的位置和结构
+pub type ParseResult<T> = std::result::Result<T, Diagnostic>;
+
+/// 默认缓存的页数
+pub const DEFAULT_CACHE_PAGES: usize = 256;
+
+/// B-Tree 的最大深度，超过这个深度的 B-Tree 一定是损坏的
+pub const MAX_DEPTH: usize = 64;
+
+/// 单元负载的最大字节数，与 SQLite 的 SQLITE_MAX_LENGTH 相同
+pub const MAX_PAYLOAD: u64 = 1_000_000_000;
+
+/// 遍历时对页的分类，只保存重新解析这一页所需的信息。
+/// 解析出的页被缓存淘汰后，按分类重新解析得到同样的页。
+#[derive(Debug, Clone)]
+enum PageKind {
+    BTree(Option<Rc<SchemaEntry>>),
+    /// 溢出链中的一页：引用这条链的单元所在的页，以及本页中属于负载的字节数
+    Overflow {
+        parent: u32,
+        len: usize,
+    },
+    FreelistTrunk,
+    FreelistLeaf,
+    Unknown,
+}
 
-use super::DBHeader;
 #[derive(Debug)]
 pub struct Reader {
     pub header: Rc<DBHeader>,
+    /// sqlite_schema 中的对象，第一个是 sqlite_schema 本身
+    pub schema: Vec<Rc<SchemaEntry>>,
+    cache: PageCache,
+    /// 从根页遍历 B-Tree、溢出链和空闲列表时分类过的页
+    kinds: RefCell<HashMap<u32, PageKind>>,
+    /// 解析出的页，与页缓存一样最多保留 cache_pages 页
+    parsed: RefCell<Lru<Rc<Page>>>,
+    /// 是否已经遍历了所有 B-Tree，此后没有被分类的页就是未知页
+    loaded_all: cell::Cell<bool>,
+    /// 到目前为止发现的问题
+    diagnostics: RefCell<Vec<Diagnostic>>,
 }
 
 impl Reader {
-    pub fn new(bytes: &'static [u8]) -> Result<Self> {
+    /// 解析整个数据库文件。解析出的结构不借用 `bytes`，
+    /// 所以可以传入 `Vec<u8>`、`Rc<[u8]>` 或任意生命周期的切片。
+    /// 只有数据库头无法使用时才返回错误，其它问题记录在 diagnostics 中。
+    pub fn new(bytes: impl AsRef<[u8]>) -> ParseResult<Self> {
+        let bytes: Rc<[u8]> = Rc::from(bytes.as_ref());
+        let reader = Self::open(MemorySource(bytes), DEFAULT_CACHE_PAGES)?;
+        reader.load_all();
+        Ok(reader)
+    }
+
+    /// 通过 PageSource 打开数据库，只读取数据库头、sqlite_schema 和空闲列表主干页，
+    /// 其它页在用到时才读取，最多缓存 cache_pages 页。
+    pub fn open(source: impl PageSource + 'static, cache_pages: usize) -> ParseResult<Self> {
+        let file_size = source.size();
+        if file_size < 100 {
+            return Err(Diagnostic::error(
+                Structure::File,
+                None,
+                0,
+                format!("The file is {file_size} bytes, too short for a database header"),
+            ));
+        }
         let mut bheader = [0; 100];
-        bheader.clone_from_slice(&bytes[..100]);
-        let header = Rc::new(DBHeader::try_from(&bheader)?);
-        Ok(Self { header })
+        source
+            .read_at(0, &mut bheader)
+            .map_err(|e| Diagnostic::error(Structure::Header, None, 0, e.to_string()))?;
+        let header = DBHeader::try_from(&bheader)
+            .map_err(|e| Diagnostic::error(Structure::Header, None, 0, e.to_string()))?;
+        check_page_size(&header)?;
+        let header = Rc::new(header);
+        let mut diagnostics = check_header(&header, file_size);
+        let cache = PageCache::new(Box::new(source), header.real_page_size(), cache_pages);
+
+        let mut walker = Walker::new(&cache, &header, HashMap::new());
+        let schema = walker.read_schema();
+        walker.walk_freelist(header.first_freelist_trunk_page_number);
+        diagnostics.append(&mut walker.diagnostics);
+        let kinds = walker.kinds;
+        Ok(Self {
+            header,
+            schema,
+            cache,
+            kinds: RefCell::new(kinds),
+            parsed: RefCell::new(Lru::new(cache_pages)),
+            loaded_all: cell::Cell::new(false),
+            diagnostics: RefCell::new(diagnostics),
+        })
+    }
+
+    /// 遍历以 root 为根的 B-Tree，给其中的页和溢出页分类，问题记录在 diagnostics 中
+    pub fn load_tree(&self, root: u32) {
+        if self.kinds.borrow().contains_key(&root) {
+            return;
+        }
+        let owner = self.schema.iter().find(|e| e.root_page == root).cloned();
+        let kinds = std::mem::take(&mut *self.kinds.borrow_mut());
+        let mut walker = Walker::new(&self.cache, &self.header, kinds);
+        walker.walk_btree(root, owner, &mut |_| {});
+        *self.kinds.borrow_mut() = walker.kinds;
+        self.diagnostics
+            .borrow_mut()
+            .append(&mut walker.diagnostics);
+        // 之前单独解析的页现在可能知道属于哪棵树了
+        self.parsed.borrow_mut().clear();
+    }
+
+    /// 遍历所有表和索引
+    pub fn load_all(&self) {
+        if self.loaded_all.get() {
+            return;
+        }
+        for entry in self.schema.iter().skip(1) {
+            if entry.root_page > 0 {
+                self.load_tree(entry.root_page);
+            }
+        }
+        self.loaded_all.set(true);
+        self.parsed.borrow_mut().clear();
+    }
+
+    /// 还没有分类的页逐棵遍历 B-Tree，直到找到它所在的树。都找不到时它是未知页
+    pub fn classify(&self, number: u32) {
+        if self.loaded_all.get() || self.is_classified(number) {
+            return;
+        }
+        for entry in self.schema.iter().skip(1) {
+            if entry.root_page > 0 {
+                self.load_tree(entry.root_page);
+                if self.is_classified(number) {
+                    return;
+                }
+            }
+        }
+        self.loaded_all.set(true);
+    }
+
+    /// 页是否已经在遍历中分类过
+    pub fn is_classified(&self, number: u32) -> bool {
+        self.kinds.borrow().contains_key(&number)
+    }
+
+    /// 溢出页所在的链由哪一页上的单元引用
+    pub fn overflow_parent(&self, number: u32) -> Option<u32> {
+        match self.kinds.borrow().get(&number) {
+            Some(PageKind::Overflow { parent, .. }) => Some(*parent),
+            _ => None,
+        }
+    }
+
+    /// 空闲列表中的主干页和叶子页数
+    pub fn freelist_pages(&self) -> usize {
+        self.kinds
+            .borrow()
+            .values()
+            .filter(|k| matches!(k, PageKind::FreelistTrunk | PageKind::FreelistLeaf))
+            .count()
+    }
+
+    /// 到目前为止发现的所有问题，按文件偏移排序
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.borrow().clone();
+        diagnostics.sort_by_key(|d| d.offset);
+        diagnostics
+    }
+
+    /// 文件中的页数
+    pub fn page_count(&self) -> u32 {
+        self.cache.page_count()
+    }
+
+    /// 读取页面的缓存
+    pub fn cache(&self) -> &PageCache {
+        &self.cache
+    }
+
+    /// 按页号（从 1 开始）取页。还没有分类的页先找到它所在的 B-Tree，再按分类解析；
+    /// 不属于任何结构的页是未知页。
+    /// 解析出的页只在缓存中保留有限的数量，淘汰后再取时重新解析。
+    pub fn page(&self, number: u32) -> Option<Rc<Page>> {
+        if number == 0 || number > self.page_count() {
+            return None;
+        }
+        if let Some(page) = self.parsed.borrow_mut().get(number) {
+            return Some(page);
+        }
+        self.classify(number);
+        let kind = self.kinds.borrow().get(&number).cloned();
+        let page = Rc::new(self.parse_page(number, kind)?);
+        self.parsed.borrow_mut().insert(number, page.clone());
+        Some(page)
+    }
+
+    /// 按分类解析一页。问题在遍历时已经记录过，不再记录
+    fn parse_page(&self, number: u32, kind: Option<PageKind>) -> Option<Page> {
+        let mut walker = Walker::new(&self.cache, &self.header, HashMap::new());
+        let info = walker.page_info(number).ok()?;
+        let page = match kind {
+            Some(PageKind::BTree(owner)) => walker.parse_btree_page(number, owner).map(Page::BTree),
+            Some(PageKind::Overflow { len, .. }) => walker
+                .parse_overflow_page(info.clone(), len)
+                .map(Page::Overflow),
+            Some(PageKind::FreelistTrunk) => walker
+                .parse_freelist_trunk(info.clone())
+                .map(|(page, _)| Page::FreelistTrunk(page)),
+            Some(PageKind::FreelistLeaf) => Ok(Page::FreelistLeaf(info.clone())),
+            Some(PageKind::Unknown) | None => Ok(Page::Unknown(info.clone())),
+        };
+        Some(page.unwrap_or(Page::Unknown(info)))
+    }
+
+    /// 按页号顺序逐页解析。解析出的页只在缓存中保留有限的数量，遍历整个文件也不会全部留在内存中
+    pub fn pages(&self) -> impl Iterator<Item = Rc<Page>> + '_ {
+        (1..=self.page_count()).filter_map(|n| self.page(n))
+    }
+}
+
+/// 从根页开始遍历 B-Tree、溢出链和空闲列表，给每一页分类。
+/// 遇到损坏的结构时记录问题并跳过它，继续解析其它部分。
+struct Walker<'a> {
+    cache: &'a PageCache,
+    page_size: usize,
+    usable_size: usize,
+    encoding: TextEncoding,
+    page_count: u32,
+    kinds: HashMap<u32, PageKind>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(cache: &'a PageCache, header: &DBHeader, kinds: HashMap<u32, PageKind>) -> Self {
+        Self {
+            cache,
+            page_size: header.real_page_size(),
+            usable_size: header.usable_size(),
+            encoding: header.encoding(),
+            page_count: cache.page_count(),
+            kinds,
+            diagnostics: vec![],
+        }
+    }
+
+    /// structure 中引用了 number 页，offset 是引用所在的位置
+    fn page_info_at(
+        &self,
+        number: u32,
+        structure: Structure,
+        page: Option<u32>,
+        offset: usize,
+    ) -> ParseResult<PageInfo> {
+        if number == 0 || number > self.page_count {
+            return Err(Diagnostic::error(
+                structure,
+                page,
+                offset,
+                format!(
+                    "Page {number} is out of range (the file has {} pages)",
+                    self.page_count
+                ),
+            ));
+        }
+        Ok(PageInfo {
+            number,
+            offset: (number as usize - 1) * self.page_size,
+            size: self.page_size,
+        })
+    }
+
+    fn page_info(&self, number: u32) -> ParseResult<PageInfo> {
+        self.page_info_at(number, Structure::File, None, 0)
+    }
+
+    fn page_bytes(&self, info: &PageInfo) -> ParseResult<Rc<[u8]>> {
+        self.cache.page(info.number).map_err(|e| {
+            Diagnostic::error(
+                Structure::File,
+                Some(info.number),
+                info.offset,
+                e.to_string(),
+            )
+        })
+    }
+
+    /// 页是否已经被分类过
+    fn is_parsed(&self, number: u32) -> bool {
+        self.kinds.contains_key(&number)
+    }
+
+    /// 解析 sqlite_schema（根页为第 1 页）中的每一行
+    fn read_schema(&mut self) -> Vec<Rc<SchemaEntry>> {
+        let mut schema_table = SchemaEntry::new(
+            "table".to_string(),
+            "sqlite_schema".to_string(),
+            "sqlite_schema".to_string(),
+            1,
+            Some(SQLITE_SCHEMA_SQL.to_string()),
+        );
+        schema_table.resolve_columns(None);
+        let schema_table = Rc::new(schema_table);
+
+        let mut entries = vec![];
+        let mut unparsed = vec![];
+        self.walk_btree(1, Some(schema_table.clone()), &mut |page| {
+            for cell in &page.cells {
+                let Some(record) = cell.payload.as_ref().and_then(|p| p.record.as_ref()) else {
+                    continue;
+                };
+                let text = |i: usize| match record.columns.get(i).map(|c| &c.value) {
+                    Some(RecordValue::Text(s)) => Some(s.clone()),
+                    _ => None,
+                };
+                let root_page = match record.columns.get(3).map(|c| &c.value) {
+                    Some(RecordValue::Integer(n)) => *n as u32,
+                    _ => 0,
+                };
+                let entry = SchemaEntry::new(
+                    text(0).unwrap_or_default(),
+                    text(1).unwrap_or_default(),
+                    text(2).unwrap_or_default(),
+                    root_page,
+                    text(4),
+                );
+                if entry.sql.is_some() && entry.statement.is_none() {
+                    unparsed.push(Diagnostic::warning(
+                        Structure::Schema,
+                        Some(page.info.number),
+                        cell.offset,
+                        format!("Cannot parse the SQL of {} {}", entry.kind, entry.name),
+                    ));
+                }
+                entries.push(entry);
+            }
+        });
+        self.diagnostics.append(&mut unparsed);
+
+        // 索引的列信息依赖所属表的定义
+        let tables: Vec<SchemaEntry> = entries.clone();
+        for entry in entries.iter_mut() {
+            let table = tables
+                .iter()
+                .find(|t| t.kind == "table" && t.name.eq_ignore_ascii_case(&entry.tbl_name))
+                .and_then(|t| t.table());
+            entry.resolve_columns(table);
+        }
+
+        let mut schema = vec![schema_table];
+        schema.extend(entries.into_iter().map(Rc::new));
+        schema
+    }
+
+    /// 深度优先遍历一棵 B-Tree，按键顺序对每个叶子页调用 leaf。
+    /// 无法解析的页标记为未知页，不再继续向下遍历。解析出的页用完就丢弃，只记录分类。
+    fn walk_btree(
+        &mut self,
+        root: u32,
+        owner: Option<Rc<SchemaEntry>>,
+        leaf: &mut dyn FnMut(&BTreePage),
+    ) {
+        // (页号, 引用它的页, 引用所在的文件偏移, 深度)
+        let mut stack = vec![(root, None, 0, 1)];
+        while let Some((number, parent, offset, depth)) = stack.pop() {
+            if depth > MAX_DEPTH {
+                self.diagnostics.push(Diagnostic::error(
+                    Structure::BTree,
+                    parent,
+                    offset,
+                    format!("B-Tree rooted at page {root} is deeper than {MAX_DEPTH} levels"),
+                ));
+                continue;
+            }
+            // 已经解析过的页不再重复访问，避免损坏的文件中出现环
+            if self.is_parsed(number) {
+                self.diagnostics.push(Diagnostic::warning(
+                    Structure::BTree,
+                    parent,
+                    offset,
+                    format!("Page {number} is referenced more than once"),
+                ));
+                continue;
+            }
+            if let Err(d) = self.page_info_at(number, Structure::BTree, parent, offset) {
+                self.diagnostics.push(d);
+                continue;
+            }
+            let page = match self.parse_btree_page(number, owner.clone()) {
+                Ok(page) => page,
+                Err(d) => {
+                    self.diagnostics.push(d);
+                    self.kinds.insert(number, PageKind::Unknown);
+                    continue;
+                }
+            };
+            if page.header.page_type.is_leaf() {
+                leaf(&page);
+            } else {
+                let mut children: Vec<(u32, Option<u32>, usize, usize)> = page
+                    .cells
+                    .iter()
+                    .filter_map(|c| Some((c.left_child?, Some(number), c.offset, depth + 1)))
+                    .collect();
+                children.extend(
+                    page.header
+                        .right_most_pointer
+                        .map(|p| (p, Some(number), page.header_offset + 8, depth + 1)),
+                );
+                stack.extend(children.into_iter().rev());
+            }
+            self.kinds.insert(number, PageKind::BTree(owner.clone()));
+        }
+    }
+
+    /// 解析一个 B-Tree 页。页头无法解析时返回错误；
+    /// 单个单元损坏时记录问题，用一个空的单元代替，保持单元和单元指针一一对应。
+    fn parse_btree_page(
+        &mut self,
+        number: u32,
+        owner: Option<Rc<SchemaEntry>>,
+    ) -> ParseResult<BTreePage> {
+        let info = self.page_info(number)?;
+        let bytes = self.page_bytes(&info)?;
+        let header_offset = if number == 1 { 100 } else { 0 };
+        let header = PageHeader::parse(&bytes[header_offset..]).map_err(|e| {
+            Diagnostic::error(
+                Structure::PageHeader,
+                Some(number),
+                info.offset + header_offset,
+                e.to_string(),
+            )
+        })?;
+
+        let pointers_start = header_offset + header.size();
+        let max_pointers = (self.usable_size.saturating_sub(pointers_start)) / 2;
+        let count = header.cell_count as usize;
+        if count > max_pointers {
+            self.diagnostics.push(Diagnostic::error(
+                Structure::CellPointer,
+                Some(number),
+                info.offset + header_offset + 3,
+                format!("{count} cells do not fit in the page, only reading {max_pointers}"),
+            ));
+        }
+        let cell_pointers: Vec<u16> = (0..count.min(max_pointers))
+            .map(|i| {
+                let p = pointers_start + 2 * i;
+                u16::from_be_bytes([bytes[p], bytes[p + 1]])
+            })
+            .collect();
+
+        let mut cells = Vec::with_capacity(cell_pointers.len());
+        for (i, &pointer) in cell_pointers.iter().enumerate() {
+            let pointer = pointer as usize;
+            let cell = if pointer < pointers_start + 2 * cell_pointers.len()
+                || pointer >= self.usable_size
+            {
+                Err(Diagnostic::error(
+                    Structure::CellPointer,
+                    Some(number),
+                    info.offset + pointers_start + 2 * i,
+                    format!("Cell pointer {i} points outside the cell content area: {pointer}"),
+                ))
+            } else {
+                self.parse_cell(&info, header.page_type, pointer)
+            };
+            cells.push(cell.unwrap_or_else(|d| {
+                self.diagnostics.push(d);
+                Cell {
+                    offset: info.offset + pointer,
+                    left_child: None,
+                    payload_size: None,
+                    rowid: None,
+                    payload: None,
+                }
+            }));
+        }
+
+        Ok(BTreePage {
+            header_offset: info.offset + header_offset,
+            info,
+            header,
+            cell_pointers,
+            cells,
+            owner,
+        })
+    }
+
+    fn parse_cell(
+        &mut self,
+        info: &PageInfo,
+        page_type: PageType,
+        pointer: usize,
+    ) -> ParseResult<Cell> {
+        let bytes = self.page_bytes(info)?;
+        let truncated = || {
+            Diagnostic::error(
+                Structure::Cell,
+                Some(info.number),
+                info.offset + pointer,
+                format!("Cell at {pointer} is truncated"),
+            )
+        };
+        let varint = |pos: usize| {
+            bytes
+                .get(pos..self.usable_size)
+                .and_then(read_varint)
+                .ok_or_else(truncated)
+        };
+
+        let mut pos = pointer;
+        let left_child = if page_type.is_leaf() {
+            None
+        } else {
+            let b = bytes.get(pos..pos + 4).ok_or_else(truncated)?;
+            pos += 4;
+            Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let payload_size = if page_type == PageType::InteriorTable {
+            None
+        } else {
+            let (size, len) = varint(pos)?;
+            pos += len;
+            Some((size as u64, len))
+        };
+        let rowid = if page_type.is_table() {
+            let (rowid, len) = varint(pos)?;
+            pos += len;
+            Some((rowid, len))
+        } else {
+            None
+        };
+        let payload = match payload_size {
+            Some((size, _)) if size > MAX_PAYLOAD => {
+                return Err(Diagnostic::error(
+                    Structure::Payload,
+                    Some(info.number),
+                    info.offset + pointer,
+                    format!("Payload size {size} is larger than the maximum of {MAX_PAYLOAD}"),
+                ));
+            }
+            Some((size, _)) => Some(self.read_payload(info, page_type, pos, size as usize)?),
+            None => None,
+        };
+        Ok(Cell {
+            offset: info.offset + pointer,
+            left_child,
+            payload_size,
+            rowid,
+            payload,
+        })
+    }
+
+    /// 读取单元的负载，超出本地部分的内容从溢出链中拼接。
+    /// 溢出链断开时记录问题，保留已经读到的部分。
+    fn read_payload(
+        &mut self,
+        info: &PageInfo,
+        page_type: PageType,
+        pos: usize,
+        size: usize,
+    ) -> ParseResult<Payload> {
+        let bytes = self.page_bytes(info)?;
+        let truncated = || {
+            Diagnostic::error(
+                Structure::Payload,
+                Some(info.number),
+                info.offset + pos,
+                format!("Payload of {size} bytes at {pos} does not fit in the page"),
+            )
+        };
+        let local_size = local_payload_size(page_type, self.usable_size, size);
+        let local_end = pos + local_size;
+        let overflow_chunk = self.usable_size - 4;
+        let local = bytes
+            .get(pos..local_end)
+            .filter(|_| local_end <= self.usable_size)
+            .ok_or_else(truncated)?;
+        if local_size == size {
+            // 整个负载都在本页中，不需要复制
+            let bytes = Bytes::new(bytes.clone(), pos..local_end);
+            let record = self.parse_record(info, pos, &bytes);
+            return Ok(Payload {
+                offset: info.offset + pos,
+                local_size,
+                first_overflow: None,
+                overflow: vec![],
+                overflow_chunk,
+                bytes,
+                record,
+            });
+        }
+        let mut payload = local.to_vec();
+        let mut overflow = vec![];
+        let b = bytes
+            .get(local_end..local_end + 4)
+            .filter(|_| local_end + 4 <= self.usable_size)
+            .ok_or_else(truncated)?;
+        let mut next = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        let first_overflow = Some(next);
+        // 引用下一个溢出页的位置
+        let (mut from, mut from_offset) = (info.number, info.offset + local_end);
+        while payload.len() < size {
+            let problem = if next == 0 {
+                Some("ends early".to_string())
+            } else if self.is_parsed(next) {
+                Some(format!("reaches page {next}, which is already used"))
+            } else {
+                None
+            };
+            let oinfo = match problem {
+                Some(p) => Err(Diagnostic::error(
+                    Structure::OverflowChain,
+                    Some(from),
+                    from_offset,
+                    format!(
+                        "Overflow chain {p}: {} of {size} payload bytes read",
+                        payload.len()
+                    ),
+                )),
+                None => self.page_info_at(next, Structure::OverflowChain, Some(from), from_offset),
+            };
+            let content = oinfo.and_then(|oinfo| Ok((self.page_bytes(&oinfo)?, oinfo)));
+            let (content, oinfo) = match content {
+                Ok(c) => c,
+                Err(d) => {
+                    self.diagnostics.push(d);
+                    break;
+                }
+            };
+            let following = u32::from_be_bytes([content[0], content[1], content[2], content[3]]);
+            let take = overflow_chunk.min(size - payload.len());
+            payload.extend_from_slice(&content[4..4 + take]);
+            overflow.push((next, oinfo.offset + 4));
+            (from, from_offset) = (next, oinfo.offset);
+            self.kinds.insert(
+                next,
+                PageKind::Overflow {
+                    parent: info.number,
+                    len: take,
+                },
+            );
+            next = following;
+        }
+
+        let record = if payload.len() == size {
+            self.parse_record(info, pos, &payload)
+        } else {
+            None
+        };
+        Ok(Payload {
+            offset: info.offset + pos,
+            local_size,
+            first_overflow,
+            overflow,
+            overflow_chunk,
+            bytes: payload.into(),
+            record,
+        })
+    }
+
+    /// 把完整的负载解析成 record，不是合法的 record 时记录问题
+    fn parse_record(&mut self, info: &PageInfo, pos: usize, payload: &[u8]) -> Option<Record> {
+        match Record::parse(payload, self.encoding) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                self.diagnostics.push(Diagnostic::warning(
+                    Structure::Record,
+                    Some(info.number),
+                    info.offset + pos,
+                    format!("Payload is not a valid record: {e}"),
+                ));
+                None
+            }
+        }
+    }
+
+    /// 遍历空闲列表的主干页链表
+    fn walk_freelist(&mut self, first_trunk: u32) {
+        let (mut from, mut from_offset) = (None, 32);
+        let mut next = first_trunk;
+        while next != 0 {
+            if self.is_parsed(next) {
+                self.diagnostics.push(Diagnostic::error(
+                    Structure::Freelist,
+                    from,
+                    from_offset,
+                    format!("Freelist trunk page {next} is already used"),
+                ));
+                return;
+            }
+            let page = self
+                .page_info_at(next, Structure::Freelist, from, from_offset)
+                .and_then(|info| self.parse_freelist_trunk(info));
+            let (page, problem) = match page {
+                Ok(page) => page,
+                Err(d) => {
+                    self.diagnostics.push(d);
+                    return;
+                }
+            };
+            self.diagnostics.extend(problem);
+            for (i, &leaf) in page.leaves.iter().enumerate() {
+                let offset = page.info.offset + 8 + 4 * i;
+                match self.page_info_at(leaf, Structure::Freelist, Some(next), offset) {
+                    Ok(_) if self.is_parsed(leaf) => self.diagnostics.push(Diagnostic::error(
+                        Structure::Freelist,
+                        Some(next),
+                        offset,
+                        format!("Freelist leaf page {leaf} is already used"),
+                    )),
+                    Ok(_) => {
+                        self.kinds.insert(leaf, PageKind::FreelistLeaf);
+                    }
+                    Err(d) => self.diagnostics.push(d),
+                }
+            }
+            (from, from_offset) = (Some(next), page.info.offset);
+            self.kinds.insert(next, PageKind::FreelistTrunk);
+            next = page.next;
+        }
+    }
+
+    /// 解析一个空闲列表主干页。叶子页号数组超出本页时只读取本页中的部分，并返回这个问题
+    fn parse_freelist_trunk(
+        &self,
+        info: PageInfo,
+    ) -> ParseResult<(FreelistTrunkPage, Option<Diagnostic>)> {
+        let bytes = self.page_bytes(&info)?;
+        let next = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let count = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+        // 叶子页号数组不能超出本页
+        let max = self.usable_size / 4 - 2;
+        let problem = (count > max).then(|| {
+            Diagnostic::error(
+                Structure::Freelist,
+                Some(info.number),
+                info.offset + 4,
+                format!("{count} leaf pages do not fit in the trunk page, only reading {max}"),
+            )
+        });
+        let leaves: Vec<u32> = (0..count.min(max))
+            .map(|i| {
+                let p = 8 + 4 * i;
+                u32::from_be_bytes([bytes[p], bytes[p + 1], bytes[p + 2], bytes[p + 3]])
+            })
+            .collect();
+        Ok((FreelistTrunkPage { info, next, leaves }, problem))
+    }
+
+    /// 解析溢出链中的一页，本页中属于负载的是下一页页号之后的 len 个字节
+    fn parse_overflow_page(&self, info: PageInfo, len: usize) -> ParseResult<OverflowPage> {
+        let bytes = self.page_bytes(&info)?;
+        let next = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let len = len.min(bytes.len() - 4);
+        Ok(OverflowPage {
+            info,
+            next,
+            content: Bytes::new(bytes, 4..4 + len),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Severity;
+    use crate::ui::viewer::{BIG_PAGE_DB, SIMPLE_DB};
+
+    #[test]
+    fn read_included_databases() {
+        for bytes in [SIMPLE_DB, BIG_PAGE_DB] {
+            let reader = Reader::new(bytes).unwrap();
+            assert_eq!(reader.page_count(), 2);
+            assert_eq!(reader.schema.len(), 2);
+            let table = &reader.schema[1];
+            assert_eq!(table.root_page, 2);
+            assert_eq!(table.columns[0].name, "int");
+
+            let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+                panic!("page 2 should be a b-tree page");
+            };
+            assert_eq!(page.header.page_type, PageType::LeafTable);
+            assert_eq!(page.cells.len(), 4);
+            assert_eq!(page.owner.as_ref().map(|o| &o.name), Some(&table.name));
+        }
+    }
+
+    #[test]
+    fn open_reads_pages_on_demand() {
+        let reader = Reader::open(MemorySource(SIMPLE_DB), 4).unwrap();
+        // 只读取了第 1 页（sqlite_schema）
+        assert_eq!(reader.cache().reads(), 1);
+        assert_eq!(reader.schema[1].name, "simple");
+
+        let descent = reader.find_rowid(2, 4).unwrap();
+        assert_eq!(descent.found, Some((2, 3)));
+        assert_eq!(reader.cache().reads(), 2);
+        // 取页时先找到它所在的表，页已经在缓存中，不再读取
+        let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        assert_eq!(
+            page.owner.map(|o| o.name.clone()),
+            Some("simple".to_string())
+        );
+        assert!(reader.is_classified(2));
+        assert_eq!(reader.cache().reads(), 2);
+        assert!(reader.page(3).is_none());
+        assert!(Reader::open(MemorySource(&SIMPLE_DB[..50]), 4).is_err());
+    }
+
+    #[test]
+    fn parsed_pages_are_bounded() {
+        use crate::writer::DatabaseBuilder;
+
+        let rows = (0..400)
+            .map(|i| vec![RecordValue::Integer(i), RecordValue::Text("x".repeat(100))])
+            .collect();
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+        let reader = Reader::open(MemorySource(bytes), 4).unwrap();
+        assert!(reader.page_count() > 20);
+        let first = Rc::downgrade(&reader.page(2).unwrap());
+        assert!(first.upgrade().is_some());
+        for n in 3..=reader.page_count() {
+            reader.page(n).unwrap();
+        }
+        // 第 2 页已被淘汰，再取时重新解析出同样的页
+        assert!(first.upgrade().is_none());
+        assert!(reader.cache().cached() <= 4);
+        let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        assert_eq!(page.owner.map(|o| o.name.clone()), Some("t".to_string()));
+    }
+
+    #[test]
+    fn continue_past_corrupt_pages() {
+        let page_size = Reader::new(SIMPLE_DB).unwrap().header.real_page_size();
+        assert!(Reader::new(SIMPLE_DB).unwrap().diagnostics().is_empty());
+
+        // 第 2 页的页类型无效，这一页变成未知页，其它部分照常解析
+        let mut bytes = SIMPLE_DB.to_vec();
+        bytes[page_size] = 7;
+        let reader = Reader::new(&bytes).unwrap();
+        assert_eq!(reader.schema.len(), 2);
+        assert!(matches!(reader.page(2).as_deref(), Some(Page::Unknown(_))));
+        let problems = reader.diagnostics();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].severity, Severity::Error);
+        assert_eq!(problems[0].structure, Structure::PageHeader);
+        assert_eq!((problems[0].page, problems[0].offset), (Some(2), page_size));
+
+        // 单元指针指向页外，单元被替换成空单元，其余单元不受影响
+        let mut bytes = SIMPLE_DB.to_vec();
+        bytes[page_size + 8..page_size + 10].copy_from_slice(&0xfff0u16.to_be_bytes());
+        let reader = Reader::new(&bytes).unwrap();
+        let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        assert_eq!(page.cells.len(), 4);
+        assert!(page.cells[0].payload.is_none());
+        assert!(page.cells[1].payload.is_some());
+        assert_eq!(reader.diagnostics()[0].structure, Structure::CellPointer);
+
+        // 只有第 1 页，表的根页不存在
+        let reader = Reader::new(&SIMPLE_DB[..page_size]).unwrap();
+        assert_eq!(reader.diagnostics()[0].structure, Structure::BTree);
+
+        let err = Reader::new(&SIMPLE_DB[..99]).unwrap_err();
+        assert_eq!(err.structure, Structure::File);
+    }
+
+    /// 页号指回自身的 B-Tree、溢出链和空闲列表，以及过深的 B-Tree，都被截断并记录问题
+    #[test]
+    fn cut_off_cycles_and_deep_trees() {
+        use crate::writer::DatabaseBuilder;
+
+        let has = |reader: &Reader, structure: Structure, text: &str| {
+            reader
+                .diagnostics()
+                .iter()
+                .any(|d| d.structure == structure && d.message.contains(text))
+        };
+        let set_u32 = |bytes: &mut [u8], offset: usize, v: u32| {
+            bytes[offset..offset + 4].copy_from_slice(&v.to_be_bytes())
+        };
+
+        // 根页（第 2 页）的最右指针指向自己
+        let rows = (0..100).map(|i| vec![RecordValue::Integer(i)]).collect();
+        let mut bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(x)", rows)
+            .build()
+            .unwrap();
+        set_u32(&mut bytes, 512 + 8, 2);
+        let reader = Reader::new(&bytes).unwrap();
+        assert!(has(&reader, Structure::BTree, "referenced more than once"));
+
+        // 第一个溢出页（第 3 页）的下一页指向自己
+        let rows = vec![vec![RecordValue::Blob(vec![7; 2000])]];
+        let mut bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(x)", rows)
+            .build()
+            .unwrap();
+        set_u32(&mut bytes, 2 * 512, 3);
+        let reader = Reader::new(&bytes).unwrap();
+        assert!(has(&reader, Structure::OverflowChain, "already used"));
+
+        // 追加一个下一页指向自己的空闲列表主干页
+        let mut bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(x)", vec![])
+            .build()
+            .unwrap();
+        bytes.resize(3 * 512, 0);
+        set_u32(&mut bytes, 2 * 512, 3);
+        set_u32(&mut bytes, 28, 3);
+        set_u32(&mut bytes, 32, 3);
+        set_u32(&mut bytes, 36, 1);
+        let reader = Reader::new(&bytes).unwrap();
+        assert!(has(&reader, Structure::Freelist, "already used"));
+
+        // 第 2 页到第 70 页是只有最右指针的内部页，一页接一页，最后是叶子页
+        let mut bytes = bytes[..2 * 512].to_vec();
+        set_u32(&mut bytes, 32, 0);
+        set_u32(&mut bytes, 36, 0);
+        for n in 2..=71u32 {
+            let mut page = vec![0; 512];
+            page[5..7].copy_from_slice(&512u16.to_be_bytes());
+            if n < 71 {
+                page[0] = PageType::InteriorTable.to_u8();
+                set_u32(&mut page, 8, n + 1);
+            } else {
+                page[0] = PageType::LeafTable.to_u8();
+            }
+            bytes.truncate((n as usize - 1) * 512);
+            bytes.extend(page);
+        }
+        set_u32(&mut bytes, 28, 71);
+        let reader = Reader::new(&bytes).unwrap();
+        assert!(has(&reader, Structure::BTree, "deeper than 64 levels"));
+        assert!(reader.find_rowid(2, 1).is_err());
+    }
+
+    #[test]
+    fn payloads_are_views_of_pages() {
+        let reader = Reader::new(SIMPLE_DB).unwrap();
+        let buf = reader.cache().page(2).unwrap();
+        let Page::BTree(page) = reader.page(2).unwrap().as_ref().clone() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        let payload = page.cells[0].payload.as_ref().unwrap();
+        assert!(payload.bytes.is_view_of(&buf));
+        let start = payload.offset - page.info.offset;
+        assert_eq!(*payload.bytes, buf[start..start + payload.local_size]);
+    }
+
+    #[test]
+    fn views_are_released_with_their_pages() {
+        use crate::writer::DatabaseBuilder;
+
+        let mut rows: Vec<Vec<RecordValue>> = (0..200)
+            .map(|i| vec![RecordValue::Integer(i), RecordValue::Text("x".repeat(100))])
+            .collect();
+        rows.push(vec![
+            RecordValue::Integer(200),
+            RecordValue::Text("y".repeat(2000)),
+        ]);
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+        let reader = Reader::open(MemorySource(bytes), 4).unwrap();
+        reader.load_all();
+        let overflow = (1..=reader.page_count())
+            .find(|&n| reader.overflow_parent(n).is_some())
+            .unwrap();
+        let leaf = (1..=reader.page_count())
+            .find(|&n| matches!(reader.page(n).as_deref(), Some(Page::BTree(p)) if p.header.page_type == PageType::LeafTable))
+            .unwrap();
+        let mut bufs = vec![];
+        for number in [leaf, overflow] {
+            let page = reader.page(number).unwrap();
+            let buf = reader.cache().page(number).unwrap();
+            let view = match page.as_ref() {
+                Page::BTree(p) => &p.cells[0].payload.as_ref().unwrap().bytes,
+                Page::Overflow(p) => &p.content,
+                _ => panic!("page {number} should have a view"),
+            };
+            assert!(view.is_view_of(&buf));
+            bufs.push(Rc::downgrade(&buf));
+        }
+        // 页被两个缓存淘汰后，其中的视图也随之释放，不会留住页缓冲区
+        for number in 1..=reader.page_count() {
+            if number != leaf && number != overflow {
+                reader.page(number);
+            }
+        }
+        assert!(bufs.iter().all(|buf| buf.upgrade().is_none()));
+    }
+
+    #[test]
+    fn read_owned_and_borrowed_buffers() {
+        let owned: Vec<u8> = SIMPLE_DB.to_vec();
+        let shared: Rc<[u8]> = Rc::from(SIMPLE_DB);
+        let from_vec = Reader::new(owned.clone()).unwrap();
+        let from_rc = Reader::new(shared.clone()).unwrap();
+        // 借用一个局部缓冲区，Reader 活得比它更久
+        let from_slice = {
+            let local = owned.clone();
+            Reader::new(&local[..]).unwrap()
+        };
+        for reader in [from_vec, from_rc, from_slice] {
+            assert_eq!(reader.page_count(), 2);
+            assert_eq!(reader.schema[1].name, "simple");
+        }
     }
 }
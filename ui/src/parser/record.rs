@@ -0,0 +1,162 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+use super::{varint::read_varint, TextEncoding};
+
+/// record 中一列的值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RecordValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl std::fmt::Display for RecordValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "NULL"),
+            Self::Integer(v) => write!(f, "{v}"),
+            Self::Real(v) => write!(f, "{v}"),
+            Self::Text(v) => write!(f, "{v:?}"),
+            Self::Blob(v) => write!(f, "{v:?}"),
+        }
+    }
+}
+
+/// record 中的一列
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordColumn {
+    pub serial_type: i64,
+    /// serial type 这个 varint 在 payload 中的偏移和长度
+    pub serial_type_offset: usize,
+    pub serial_type_size: usize,
+    /// 值在 payload 中的偏移和长度
+    pub offset: usize,
+    pub size: usize,
+    pub value: RecordValue,
+}
+
+/// record 格式：header size(varint) + serial types(varint...) + 各列的值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Record {
+    /// header 的总字节数，包括它自己
+    pub header_size: i64,
+    /// header size 这个 varint 占用的字节数
+    pub header_size_len: usize,
+    pub columns: Vec<RecordColumn>,
+}
+
+/// serial type 对应的值的字节数
+pub fn serial_type_size(serial_type: i64) -> usize {
+    match serial_type {
+        0 | 8 | 9 | 10 | 11 => 0,
+        1..=4 => serial_type as usize,
+        5 => 6,
+        6 | 7 => 8,
+        n if n >= 12 => ((n - 12) / 2) as usize,
+        _ => 0,
+    }
+}
+
+impl Record {
+    pub fn parse(payload: &[u8], encoding: TextEncoding) -> Result<Self> {
+        let (header_size, header_size_len) =
+            read_varint(payload).ok_or_else(|| anyhow!("Record header size is truncated"))?;
+        if header_size < header_size_len as i64 || header_size as usize > payload.len() {
+            bail!("Invalid record header size {header_size}");
+        }
+        let header_size_usize = header_size as usize;
+
+        let mut columns = vec![];
+        let mut pos = header_size_len;
+        let mut offset = header_size_usize;
+        while pos < header_size_usize {
+            let (serial_type, len) = read_varint(&payload[pos..header_size_usize])
+                .ok_or_else(|| anyhow!("Serial type at {pos} is truncated"))?;
+            let size = serial_type_size(serial_type);
+            let bytes = payload
+                .get(offset..offset.saturating_add(size))
+                .ok_or_else(|| {
+                    anyhow!("Value of column {} is out of the payload", columns.len())
+                })?;
+            columns.push(RecordColumn {
+                serial_type,
+                serial_type_offset: pos,
+                serial_type_size: len,
+                offset,
+                size,
+                value: decode_value(serial_type, bytes, encoding)?,
+            });
+            pos += len;
+            offset += size;
+        }
+        Ok(Self {
+            header_size,
+            header_size_len,
+            columns,
+        })
+    }
+}
+
+fn decode_value(serial_type: i64, bytes: &[u8], encoding: TextEncoding) -> Result<RecordValue> {
+    Ok(match serial_type {
+        0 => RecordValue::Null,
+        1..=6 => {
+            // 符号扩展后按大端解释
+            let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+            let mut buf = [fill; 8];
+            buf[8 - bytes.len()..].copy_from_slice(bytes);
+            RecordValue::Integer(i64::from_be_bytes(buf))
+        }
+        7 => RecordValue::Real(f64::from_be_bytes(bytes.try_into()?)),
+        8 => RecordValue::Integer(0),
+        9 => RecordValue::Integer(1),
+        10 | 11 => bail!("Serial type {serial_type} is reserved"),
+        n if n < 0 => bail!("Invalid serial type {n}"),
+        n if n % 2 == 0 => RecordValue::Blob(bytes.to_vec()),
+        _ => RecordValue::Text(decode_text(bytes, encoding)),
+    })
+}
+
+/// 按数据库的文本编码解码字符串
+pub fn decode_text(bytes: &[u8], encoding: TextEncoding) -> String {
+    let utf16 = |f: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| f([c[0], c[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+    match encoding {
+        TextEncoding::UTF8 => String::from_utf8_lossy(bytes).to_string(),
+        TextEncoding::UTF16le => utf16(u16::from_le_bytes),
+        TextEncoding::UTF16be => utf16(u16::from_be_bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_record() {
+        // header: size 6, types: NULL, 1-byte int, integer 1, text "hi", float
+        let mut payload = vec![0x06, 0x00, 0x01, 0x09, 0x11, 0x07];
+        payload.push(0xfe);
+        payload.extend_from_slice(b"hi");
+        payload.extend_from_slice(&1.5f64.to_be_bytes());
+        let record = Record::parse(&payload, TextEncoding::UTF8).unwrap();
+        let values: Vec<_> = record.columns.iter().map(|c| c.value.clone()).collect();
+        assert_eq!(
+            values,
+            [
+                RecordValue::Null,
+                RecordValue::Integer(-2),
+                RecordValue::Integer(1),
+                RecordValue::Text("hi".to_string()),
+                RecordValue::Real(1.5),
+            ]
+        );
+        assert_eq!(record.columns[3].offset, 7);
+        assert!(Record::parse(&payload[..8], TextEncoding::UTF8).is_err());
+    }
+}
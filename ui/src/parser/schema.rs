@@ -0,0 +1,1220 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+use super::Record;
+
+/// sqlite_schema 表本身的定义，第 1 页就是这张表的根页
+pub const SQLITE_SCHEMA_SQL: &str =
+    "CREATE TABLE sqlite_schema(type text, name text, tbl_name text, rootpage integer, sql text)";
+
+/// 列的类型亲和性（type affinity），由声明类型按 SQLite 的规则推导
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Affinity {
+    Text,
+    Numeric,
+    Integer,
+    Real,
+    Blob,
+}
+
+impl Affinity {
+    /// 按 https://www.sqlite.org/datatype3.html#determination_of_column_affinity 的顺序推导
+    pub fn from_type_name(type_name: Option<&str>) -> Self {
+        let t = match type_name {
+            Some(t) => t.to_ascii_uppercase(),
+            None => return Self::Blob,
+        };
+        if t.contains("INT") {
+            Self::Integer
+        } else if t.contains("CHAR") || t.contains("CLOB") || t.contains("TEXT") {
+            Self::Text
+        } else if t.contains("BLOB") || t.is_empty() {
+            Self::Blob
+        } else if t.contains("REAL") || t.contains("FLOA") || t.contains("DOUB") {
+            Self::Real
+        } else {
+            Self::Numeric
+        }
+    }
+}
+
+impl std::fmt::Display for Affinity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "TEXT"),
+            Self::Numeric => write!(f, "NUMERIC"),
+            Self::Integer => write!(f, "INTEGER"),
+            Self::Real => write!(f, "REAL"),
+            Self::Blob => write!(f, "BLOB"),
+        }
+    }
+}
+
+/// 解析后的 CREATE 语句
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Statement {
+    CreateTable(CreateTable),
+    CreateIndex(CreateIndex),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreateTable {
+    pub name: String,
+    pub temporary: bool,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDef>,
+    /// 主键列，来自列约束或表约束
+    pub primary_key: Vec<IndexedColumn>,
+    /// 按出现顺序排列的 PRIMARY KEY / UNIQUE 约束，用于还原 sqlite_autoindex_* 的列
+    pub unique_constraints: Vec<UniqueConstraint>,
+    pub without_rowid: bool,
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UniqueConstraint {
+    pub primary_key: bool,
+    pub columns: Vec<IndexedColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnDef {
+    pub name: String,
+    /// 声明类型，例如 `VARCHAR(10)`，可以为空
+    pub type_name: Option<String>,
+    pub affinity: Affinity,
+    pub primary_key: bool,
+    /// `PRIMARY KEY DESC`，此时 INTEGER 列不会成为 rowid 的别名
+    pub primary_key_desc: bool,
+    pub autoincrement: bool,
+    pub not_null: bool,
+    pub unique: bool,
+    /// DEFAULT 子句的原始文本
+    pub default: Option<String>,
+    pub collation: Option<String>,
+    pub generated: Option<Generated>,
+}
+
+/// 生成列（generated column）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Generated {
+    pub expr: String,
+    /// STORED 列保存在 record 中，VIRTUAL 列不保存
+    pub stored: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table: String,
+    pub unique: bool,
+    pub if_not_exists: bool,
+    pub columns: Vec<IndexedColumn>,
+    /// 部分索引（partial index）的 WHERE 条件
+    pub where_clause: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexedColumn {
+    /// 列名，若为表达式索引则是表达式文本
+    pub name: String,
+    pub is_expression: bool,
+    pub collation: Option<String>,
+    pub desc: bool,
+}
+
+impl CreateTable {
+    pub fn column(&self, name: &str) -> Option<&ColumnDef> {
+        self.columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// INTEGER PRIMARY KEY 列是 rowid 的别名，在 record 中保存为 NULL。
+    /// 返回该列的下标。
+    pub fn rowid_alias(&self) -> Option<usize> {
+        if self.without_rowid || self.primary_key.len() != 1 {
+            return None;
+        }
+        let pk = &self.primary_key[0];
+        let idx = self
+            .columns
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(&pk.name))?;
+        let col = &self.columns[idx];
+        let is_integer = col
+            .type_name
+            .as_deref()
+            .is_some_and(|t| t.eq_ignore_ascii_case("INTEGER"));
+        // 列约束中的 "INTEGER PRIMARY KEY DESC" 是历史遗留的特例，不是别名
+        if is_integer && !(col.primary_key && col.primary_key_desc) {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+
+    /// 主键中名为 name 的列的位置
+    pub fn primary_key_position(&self, name: &str) -> Option<usize> {
+        self.primary_key
+            .iter()
+            .position(|pk| pk.name.eq_ignore_ascii_case(name))
+    }
+
+    /// record 中各列对应的声明位置。VIRTUAL 生成列不保存在 record 中；
+    /// WITHOUT ROWID 表先保存主键列（按主键顺序），再按声明顺序保存其余的列。
+    pub fn storage_order(&self) -> Vec<usize> {
+        let stored = |i: &usize| self.columns[*i].generated.as_ref().is_none_or(|g| g.stored);
+        if !self.without_rowid {
+            return (0..self.columns.len()).filter(stored).collect();
+        }
+        let mut order: Vec<usize> = vec![];
+        for pk in &self.primary_key {
+            if let Some(i) = self
+                .columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(&pk.name))
+            {
+                if !order.contains(&i) {
+                    order.push(i);
+                }
+            }
+        }
+        let rest: Vec<usize> = (0..self.columns.len())
+            .filter(|i| !order.contains(i))
+            .filter(stored)
+            .collect();
+        order.extend(rest);
+        order
+    }
+
+    /// 第 n 个（从 1 开始）sqlite_autoindex_<table>_n 索引覆盖的列
+    pub fn autoindex_columns(&self, n: usize) -> Option<&[IndexedColumn]> {
+        let rowid_alias = self.rowid_alias();
+        let mut seen: Vec<&[IndexedColumn]> = vec![];
+        for c in &self.unique_constraints {
+            if c.primary_key && (self.without_rowid || rowid_alias.is_some()) {
+                continue;
+            }
+            // 完全相同的约束只会建一个索引
+            if seen.iter().any(|s| same_columns(s, &c.columns)) {
+                continue;
+            }
+            seen.push(&c.columns);
+        }
+        seen.get(n.checked_sub(1)?).copied()
+    }
+}
+
+fn same_columns(a: &[IndexedColumn], b: &[IndexedColumn]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(x, y)| x.name.eq_ignore_ascii_case(&y.name))
+}
+
+/// sqlite_schema 表中的一行
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaEntry {
+    /// "table", "index", "view" 或 "trigger"
+    pub kind: String,
+    pub name: String,
+    pub tbl_name: String,
+    pub root_page: u32,
+    pub sql: Option<String>,
+    /// 解析后的 sql，视图、触发器、虚表等为 None
+    pub statement: Option<Statement>,
+    /// 这个 B-Tree 中 record 的各列，按存储顺序排列。为空时按位置显示。
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// record 中一列的含义
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub affinity: Affinity,
+    pub collation: Option<String>,
+    pub desc: bool,
+    /// INTEGER PRIMARY KEY 列，record 中保存为 NULL，真实值是 rowid
+    pub rowid_alias: bool,
+    /// 是否属于表的 PRIMARY KEY
+    pub primary_key: bool,
+    /// 在 CREATE TABLE 中声明的位置（从 0 开始），rowid 和表达式为 None
+    pub declared: Option<usize>,
+}
+
+impl SchemaEntry {
+    pub fn new(
+        kind: String,
+        name: String,
+        tbl_name: String,
+        root_page: u32,
+        sql: Option<String>,
+    ) -> Self {
+        let statement = match kind.as_str() {
+            "table" | "index" => sql.as_deref().and_then(|sql| parse_create(sql).ok()),
+            _ => None,
+        };
+        Self {
+            kind,
+            name,
+            tbl_name,
+            root_page,
+            sql,
+            statement,
+            columns: vec![],
+        }
+    }
+
+    pub fn table(&self) -> Option<&CreateTable> {
+        match &self.statement {
+            Some(Statement::CreateTable(t)) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// WITHOUT ROWID 表保存在以主键为键的索引 B-Tree 中
+    pub fn is_without_rowid(&self) -> bool {
+        self.table().is_some_and(|t| t.without_rowid)
+    }
+
+    /// 把 record 中按存储顺序排列的列，重新按 CREATE TABLE 中声明的顺序排列。
+    /// 返回 record 中列的下标。
+    pub fn declared_order(&self, record: &Record) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..record.columns.len()).collect();
+        order.sort_by_key(|&i| {
+            self.columns
+                .get(i)
+                .and_then(|c| c.declared)
+                .unwrap_or(usize::MAX)
+        });
+        order
+    }
+
+    /// 推导 record 各列的含义。索引需要所属表的定义来确定亲和性和排序规则。
+    pub fn resolve_columns(&mut self, table: Option<&CreateTable>) {
+        self.columns = match &self.statement {
+            Some(Statement::CreateTable(t)) => {
+                let alias = t.rowid_alias();
+                t.storage_order()
+                    .into_iter()
+                    .map(|i| {
+                        let c = &t.columns[i];
+                        let pk = t.primary_key_position(&c.name);
+                        ColumnInfo {
+                            name: c.name.clone(),
+                            affinity: c.affinity,
+                            collation: c.collation.clone(),
+                            desc: pk.is_some_and(|p| t.primary_key[p].desc) && t.without_rowid,
+                            rowid_alias: alias == Some(i),
+                            primary_key: pk.is_some(),
+                            declared: Some(i),
+                        }
+                    })
+                    .collect()
+            }
+            Some(Statement::CreateIndex(index)) => index_columns(&index.columns, table),
+            None if self.kind == "index" => {
+                // sqlite_autoindex_<table>_<n> 没有 sql，列来自表的 PRIMARY KEY / UNIQUE 约束
+                let n = self.name.rsplit('_').next().and_then(|n| n.parse().ok());
+                match (table, n) {
+                    (Some(t), Some(n)) => t
+                        .autoindex_columns(n)
+                        .map(|cs| index_columns(cs, table))
+                        .unwrap_or_default(),
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        };
+    }
+}
+
+/// 索引 record 的列：被索引的列，最后是 rowid
+fn index_columns(columns: &[IndexedColumn], table: Option<&CreateTable>) -> Vec<ColumnInfo> {
+    let mut infos: Vec<ColumnInfo> = columns
+        .iter()
+        .map(|ic| {
+            let def = table
+                .and_then(|t| t.column(&ic.name))
+                .filter(|_| !ic.is_expression);
+            ColumnInfo {
+                name: ic.name.clone(),
+                // 表达式没有亲和性
+                affinity: def.map_or(Affinity::Blob, |d| d.affinity),
+                collation: ic
+                    .collation
+                    .clone()
+                    .or_else(|| def.and_then(|d| d.collation.clone())),
+                desc: ic.desc,
+                rowid_alias: false,
+                primary_key: false,
+                declared: None,
+            }
+        })
+        .collect();
+    match table {
+        // WITHOUT ROWID 表的索引以主键代替 rowid，已经被索引的主键列不会重复保存
+        Some(t) if t.without_rowid => {
+            for pk in &t.primary_key {
+                if !columns
+                    .iter()
+                    .any(|c| c.name.eq_ignore_ascii_case(&pk.name))
+                {
+                    let def = t.column(&pk.name);
+                    infos.push(ColumnInfo {
+                        name: pk.name.clone(),
+                        affinity: def.map_or(Affinity::Blob, |d| d.affinity),
+                        collation: pk
+                            .collation
+                            .clone()
+                            .or_else(|| def.and_then(|d| d.collation.clone())),
+                        desc: pk.desc,
+                        rowid_alias: false,
+                        primary_key: true,
+                        declared: None,
+                    });
+                }
+            }
+        }
+        _ => infos.push(ColumnInfo {
+            name: "rowid".to_string(),
+            affinity: Affinity::Integer,
+            collation: None,
+            desc: false,
+            rowid_alias: false,
+            primary_key: false,
+            declared: None,
+        }),
+    }
+    infos
+}
+
+/// 解析 sqlite_schema.sql 中保存的 CREATE TABLE / CREATE INDEX 语句
+pub fn parse_create(sql: &str) -> Result<Statement> {
+    let tokens = tokenize(sql)?;
+    let mut p = SqlParser {
+        sql,
+        tokens,
+        pos: 0,
+    };
+    p.expect_kw("CREATE")?;
+    let temporary = p.eat_kw("TEMP") || p.eat_kw("TEMPORARY");
+    let unique = p.eat_kw("UNIQUE");
+    if p.eat_kw("TABLE") {
+        if unique {
+            bail!("UNIQUE is not allowed before TABLE");
+        }
+        p.create_table(temporary).map(Statement::CreateTable)
+    } else if p.eat_kw("INDEX") {
+        p.create_index(unique).map(Statement::CreateIndex)
+    } else {
+        bail!("Only CREATE TABLE and CREATE INDEX are supported: {sql}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    /// 标识符或关键字；quoted 表示是否带引号（带引号的不会被当作关键字）
+    Ident {
+        text: String,
+        quoted: bool,
+    },
+    Str(String),
+    Num(String),
+    Punct(char),
+    /// 其它运算符，例如 `||`、`<=`
+    Op(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>> {
+    let b = sql.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        let start = i;
+        let tok = match c {
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if b.get(i + 1) == Some(&b'-') => {
+                while i < b.len() && b[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(b.len(), |p| i + 2 + p + 2);
+                continue;
+            }
+            b'"' | b'`' | b'[' | b'\'' => {
+                let close = if c == b'[' { b']' } else { c };
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    let Some(p) = sql[i..].find(close as char) else {
+                        bail!("Unterminated quote at offset {start}");
+                    };
+                    text.push_str(&sql[i..i + p]);
+                    i += p + 1;
+                    // 引号内两个连续的引号表示一个引号字符
+                    if close != b']' && b.get(i) == Some(&close) {
+                        text.push(close as char);
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if c == b'\'' {
+                    Tok::Str(text)
+                } else {
+                    Tok::Ident { text, quoted: true }
+                }
+            }
+            c if c.is_ascii_digit()
+                || (c == b'.' && b.get(i + 1).is_some_and(|d| d.is_ascii_digit())) =>
+            {
+                while i < b.len()
+                    && (b[i].is_ascii_alphanumeric()
+                        || b[i] == b'.'
+                        || ((b[i] == b'+' || b[i] == b'-') && matches!(b[i - 1], b'e' | b'E')))
+                {
+                    i += 1;
+                }
+                Tok::Num(sql[start..i].to_string())
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 => {
+                while i < b.len()
+                    && (b[i].is_ascii_alphanumeric()
+                        || b[i] == b'_'
+                        || b[i] == b'$'
+                        || b[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                Tok::Ident {
+                    text: sql[start..i].to_string(),
+                    quoted: false,
+                }
+            }
+            b'(' | b')' | b',' | b'.' | b';' | b'+' | b'-' | b'*' | b'/' | b'%' | b'~' => {
+                i += 1;
+                Tok::Punct(c as char)
+            }
+            _ => {
+                while i < b.len() && b"|<>=!&".contains(&b[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    bail!("Unexpected character {:?} at offset {start}", c as char);
+                }
+                Tok::Op(sql[start..i].to_string())
+            }
+        };
+        tokens.push(Token { tok, start, end: i });
+    }
+    Ok(tokens)
+}
+
+/// 列约束的起始关键字，遇到它们说明类型名结束
+const COLUMN_CONSTRAINT_KWS: [&str; 11] = [
+    "CONSTRAINT",
+    "PRIMARY",
+    "NOT",
+    "NULL",
+    "UNIQUE",
+    "CHECK",
+    "DEFAULT",
+    "COLLATE",
+    "REFERENCES",
+    "GENERATED",
+    "AS",
+];
+
+struct SqlParser<'a> {
+    sql: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> SqlParser<'a> {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn is_kw(&self, kw: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident { text, quoted: false }) if text.eq_ignore_ascii_case(kw))
+    }
+
+    fn eat_kw(&mut self, kw: &str) -> bool {
+        let found = self.is_kw(kw);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_kw(&mut self, kw: &str) -> Result<()> {
+        if self.eat_kw(kw) {
+            Ok(())
+        } else {
+            bail!("Expected {kw}, found {:?}", self.peek())
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Tok::Punct(c))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.is_punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<()> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            bail!("Expected '{c}', found {:?}", self.peek())
+        }
+    }
+
+    /// 标识符（表名、列名等），关键字在这里也可以作为名字
+    fn name(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Tok::Ident { text, .. }) | Some(Tok::Str(text)) => {
+                let text = text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            other => bail!("Expected a name, found {other:?}"),
+        }
+    }
+
+    /// `[schema.]name`
+    fn qualified_name(&mut self) -> Result<String> {
+        let name = self.name()?;
+        if self.eat_punct('.') {
+            self.name()
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// 原始 SQL 中第 from..to 个 token 的文本
+    fn text(&self, from: usize, to: usize) -> String {
+        if from >= to {
+            return String::new();
+        }
+        self.sql[self.tokens[from].start..self.tokens[to - 1].end].to_string()
+    }
+
+    /// 跳过一个 `( ... )`，返回括号内的原始文本
+    fn parens(&mut self) -> Result<String> {
+        self.expect_punct('(')?;
+        let from = self.pos;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                Some(Tok::Punct('(')) => depth += 1,
+                Some(Tok::Punct(')')) => depth -= 1,
+                None => bail!("Unbalanced parentheses"),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Ok(self.text(from, self.pos - 1))
+    }
+
+    /// 跳过 token 直到同一层级的 `,` 或 `)`，或者满足 stop 条件
+    fn skip_until(&mut self, stop: impl Fn(&Self) -> bool) -> Result<()> {
+        while !(self.is_punct(',') || self.is_punct(')') || stop(self)) {
+            if self.is_punct('(') {
+                self.parens()?;
+            } else if self.peek().is_none() {
+                bail!("Unexpected end of statement");
+            } else {
+                self.pos += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn if_not_exists(&mut self) -> Result<bool> {
+        if self.eat_kw("IF") {
+            self.expect_kw("NOT")?;
+            self.expect_kw("EXISTS")?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn conflict_clause(&mut self) -> Result<()> {
+        if self.eat_kw("ON") {
+            self.expect_kw("CONFLICT")?;
+            self.name()?;
+        }
+        Ok(())
+    }
+
+    fn create_table(&mut self, temporary: bool) -> Result<CreateTable> {
+        let if_not_exists = self.if_not_exists()?;
+        let name = self.qualified_name()?;
+        let mut table = CreateTable {
+            name,
+            temporary,
+            if_not_exists,
+            columns: vec![],
+            primary_key: vec![],
+            unique_constraints: vec![],
+            without_rowid: false,
+            strict: false,
+        };
+        if self.is_kw("AS") {
+            bail!("CREATE TABLE ... AS SELECT is not supported");
+        }
+        self.expect_punct('(')?;
+        loop {
+            if self.is_table_constraint() {
+                self.table_constraint(&mut table)?;
+            } else {
+                let column = self.column_def(&mut table)?;
+                table.columns.push(column);
+            }
+            if !self.eat_punct(',') {
+                break;
+            }
+        }
+        self.expect_punct(')')?;
+        // table-options: WITHOUT ROWID, STRICT
+        loop {
+            if self.eat_kw("WITHOUT") {
+                let word = self.name()?;
+                if !word.eq_ignore_ascii_case("ROWID") {
+                    bail!("Expected ROWID after WITHOUT, found {word}");
+                }
+                table.without_rowid = true;
+            } else if self.eat_kw("STRICT") {
+                table.strict = true;
+            }
+            if !self.eat_punct(',') {
+                break;
+            }
+        }
+        self.eat_punct(';');
+        if let Some(tok) = self.peek() {
+            bail!("Unexpected token after CREATE TABLE: {tok:?}");
+        }
+        if table.columns.is_empty() {
+            bail!("Table {} has no columns", table.name);
+        }
+        Ok(table)
+    }
+
+    fn is_table_constraint(&self) -> bool {
+        ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+            .iter()
+            .any(|kw| self.is_kw(kw))
+    }
+
+    fn table_constraint(&mut self, table: &mut CreateTable) -> Result<()> {
+        if self.eat_kw("CONSTRAINT") {
+            self.name()?;
+        }
+        if self.eat_kw("PRIMARY") {
+            self.expect_kw("KEY")?;
+            let columns = self.indexed_columns()?;
+            self.conflict_clause()?;
+            table.primary_key = columns.clone();
+            table.unique_constraints.push(UniqueConstraint {
+                primary_key: true,
+                columns,
+            });
+        } else if self.eat_kw("UNIQUE") {
+            let columns = self.indexed_columns()?;
+            self.conflict_clause()?;
+            table.unique_constraints.push(UniqueConstraint {
+                primary_key: false,
+                columns,
+            });
+        } else if self.eat_kw("CHECK") {
+            self.parens()?;
+        } else if self.eat_kw("FOREIGN") {
+            self.expect_kw("KEY")?;
+            self.skip_until(|_| false)?;
+        } else {
+            bail!("Unknown table constraint: {:?}", self.peek());
+        }
+        Ok(())
+    }
+
+    fn column_def(&mut self, table: &mut CreateTable) -> Result<ColumnDef> {
+        let name = self.name()?;
+
+        // 类型名：若干个标识符，可选地跟着 (n) 或 (n, m)
+        let from = self.pos;
+        while matches!(self.peek(), Some(Tok::Ident { .. }))
+            && !COLUMN_CONSTRAINT_KWS.iter().any(|kw| self.is_kw(kw))
+        {
+            self.pos += 1;
+        }
+        if self.pos > from && self.is_punct('(') {
+            self.parens()?;
+        }
+        let type_name = (self.pos > from).then(|| self.text(from, self.pos));
+
+        let mut column = ColumnDef {
+            affinity: Affinity::from_type_name(type_name.as_deref()),
+            name,
+            type_name,
+            primary_key: false,
+            primary_key_desc: false,
+            autoincrement: false,
+            not_null: false,
+            unique: false,
+            default: None,
+            collation: None,
+            generated: None,
+        };
+
+        while !(self.is_punct(',') || self.is_punct(')')) {
+            if self.eat_kw("CONSTRAINT") {
+                self.name()?;
+            } else if self.eat_kw("PRIMARY") {
+                self.expect_kw("KEY")?;
+                column.primary_key = true;
+                column.primary_key_desc = self.eat_kw("DESC");
+                if !column.primary_key_desc {
+                    self.eat_kw("ASC");
+                }
+                self.conflict_clause()?;
+                column.autoincrement = self.eat_kw("AUTOINCREMENT");
+                let pk = IndexedColumn {
+                    name: column.name.clone(),
+                    is_expression: false,
+                    collation: None,
+                    desc: column.primary_key_desc,
+                };
+                table.primary_key = vec![pk.clone()];
+                table.unique_constraints.push(UniqueConstraint {
+                    primary_key: true,
+                    columns: vec![pk],
+                });
+            } else if self.eat_kw("NOT") {
+                self.expect_kw("NULL")?;
+                column.not_null = true;
+                self.conflict_clause()?;
+            } else if self.eat_kw("NULL") {
+                self.conflict_clause()?;
+            } else if self.eat_kw("UNIQUE") {
+                column.unique = true;
+                self.conflict_clause()?;
+                table.unique_constraints.push(UniqueConstraint {
+                    primary_key: false,
+                    columns: vec![IndexedColumn {
+                        name: column.name.clone(),
+                        is_expression: false,
+                        collation: None,
+                        desc: false,
+                    }],
+                });
+            } else if self.eat_kw("CHECK") {
+                self.parens()?;
+            } else if self.eat_kw("DEFAULT") {
+                column.default = Some(self.default_value()?);
+            } else if self.eat_kw("COLLATE") {
+                column.collation = Some(self.name()?);
+            } else if self.eat_kw("REFERENCES") {
+                self.foreign_key_clause()?;
+            } else if self.is_kw("GENERATED") || self.is_kw("AS") {
+                if self.eat_kw("GENERATED") {
+                    self.expect_kw("ALWAYS")?;
+                }
+                self.expect_kw("AS")?;
+                let expr = self.parens()?;
+                let stored = self.eat_kw("STORED");
+                if !stored {
+                    self.eat_kw("VIRTUAL");
+                }
+                column.generated = Some(Generated { expr, stored });
+            } else {
+                bail!(
+                    "Unexpected token in definition of column {}: {:?}",
+                    column.name,
+                    self.peek()
+                );
+            }
+        }
+        Ok(column)
+    }
+
+    fn default_value(&mut self) -> Result<String> {
+        let from = self.pos;
+        if self.is_punct('(') {
+            self.parens()?;
+        } else {
+            // 带符号的数字
+            if self.is_punct('+') || self.is_punct('-') {
+                self.pos += 1;
+            }
+            if self.peek().is_none() {
+                bail!("Missing DEFAULT value");
+            }
+            self.pos += 1;
+        }
+        Ok(self.text(from, self.pos))
+    }
+
+    /// REFERENCES 之后的外键子句，不影响存储格式，直接跳过
+    fn foreign_key_clause(&mut self) -> Result<()> {
+        self.name()?;
+        if self.is_punct('(') {
+            self.parens()?;
+        }
+        loop {
+            if self.eat_kw("ON") {
+                // ON DELETE|UPDATE SET NULL|SET DEFAULT|CASCADE|RESTRICT|NO ACTION
+                self.name()?;
+                let _ = self.eat_kw("SET") || self.eat_kw("NO");
+                self.name()?;
+            } else if self.eat_kw("MATCH") {
+                self.name()?;
+            } else if (self.is_kw("NOT")
+                && matches!(self.tokens.get(self.pos + 1).map(|t| &t.tok),
+                    Some(Tok::Ident { text, .. }) if text.eq_ignore_ascii_case("DEFERRABLE")))
+                || self.is_kw("DEFERRABLE")
+            {
+                self.eat_kw("NOT");
+                self.expect_kw("DEFERRABLE")?;
+                if self.eat_kw("INITIALLY") {
+                    self.name()?;
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// `( indexed-column, ... )`
+    fn indexed_columns(&mut self) -> Result<Vec<IndexedColumn>> {
+        self.expect_punct('(')?;
+        let mut columns = vec![];
+        loop {
+            columns.push(self.indexed_column()?);
+            if !self.eat_punct(',') {
+                break;
+            }
+        }
+        self.expect_punct(')')?;
+        Ok(columns)
+    }
+
+    /// `expr [COLLATE name] [ASC|DESC]`
+    fn indexed_column(&mut self) -> Result<IndexedColumn> {
+        let from = self.pos;
+        let mut collation = None;
+        let mut desc = false;
+        let mut expr_end = None;
+        while !(self.is_punct(',') || self.is_punct(')')) {
+            if self.eat_kw("COLLATE") {
+                expr_end.get_or_insert(self.pos - 1);
+                collation = Some(self.name()?);
+            } else if self.is_kw("ASC") || self.is_kw("DESC") {
+                expr_end.get_or_insert(self.pos);
+                desc = self.is_kw("DESC");
+                self.pos += 1;
+            } else if self.is_punct('(') {
+                self.parens()?;
+            } else if self.peek().is_none() {
+                bail!("Unexpected end of indexed column list");
+            } else {
+                self.pos += 1;
+            }
+        }
+        let expr_end = expr_end.unwrap_or(self.pos);
+        if expr_end == from {
+            bail!("Empty indexed column");
+        }
+        let (name, is_expression) = match &self.tokens[from].tok {
+            Tok::Ident { text, .. } | Tok::Str(text) if expr_end == from + 1 => {
+                (text.clone(), false)
+            }
+            _ => (self.text(from, expr_end), true),
+        };
+        Ok(IndexedColumn {
+            name,
+            is_expression,
+            collation,
+            desc,
+        })
+    }
+
+    fn create_index(&mut self, unique: bool) -> Result<CreateIndex> {
+        let if_not_exists = self.if_not_exists()?;
+        let name = self.qualified_name()?;
+        self.expect_kw("ON")?;
+        let table = self.name()?;
+        let columns = self.indexed_columns()?;
+        let where_clause = if self.eat_kw("WHERE") {
+            let from = self.pos;
+            while self.peek().is_some() && !self.is_punct(';') {
+                self.pos += 1;
+            }
+            Some(self.text(from, self.pos))
+        } else {
+            None
+        };
+        self.eat_punct(';');
+        if self.peek().is_some() {
+            return Err(anyhow!(
+                "Unexpected token after CREATE INDEX: {:?}",
+                self.peek()
+            ));
+        }
+        Ok(CreateIndex {
+            name,
+            table,
+            unique,
+            if_not_exists,
+            columns,
+            where_clause,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(sql: &str) -> CreateTable {
+        match parse_create(sql).unwrap() {
+            Statement::CreateTable(t) => t,
+            other => panic!("not a table: {other:?}"),
+        }
+    }
+
+    fn index(sql: &str) -> CreateIndex {
+        match parse_create(sql).unwrap() {
+            Statement::CreateIndex(i) => i,
+            other => panic!("not an index: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn affinity_rules() {
+        let cases = [
+            ("INT", Affinity::Integer),
+            ("TINYINT", Affinity::Integer),
+            ("CHARINT", Affinity::Integer),
+            ("VARCHAR(255)", Affinity::Text),
+            ("NCHAR(55)", Affinity::Text),
+            ("CLOB", Affinity::Text),
+            ("BLOB", Affinity::Blob),
+            ("DOUBLE PRECISION", Affinity::Real),
+            ("FLOATING POINT", Affinity::Integer),
+            ("DECIMAL(10,5)", Affinity::Numeric),
+            ("STRING", Affinity::Numeric),
+        ];
+        for (t, a) in cases {
+            assert_eq!(Affinity::from_type_name(Some(t)), a, "{t}");
+        }
+        assert_eq!(Affinity::from_type_name(None), Affinity::Blob);
+    }
+
+    #[test]
+    fn simple_tables() {
+        let t = table("CREATE TABLE simple(int)");
+        assert_eq!(t.columns.len(), 1);
+        assert_eq!(t.columns[0].name, "int");
+        assert_eq!(t.columns[0].type_name, None);
+        assert_eq!(t.columns[0].affinity, Affinity::Blob);
+
+        let t = table(SQLITE_SCHEMA_SQL);
+        let names: Vec<_> = t.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["type", "name", "tbl_name", "rootpage", "sql"]);
+        assert_eq!(t.columns[3].affinity, Affinity::Integer);
+    }
+
+    #[test]
+    fn column_constraints() {
+        let t = table(
+            r#"CREATE TABLE IF NOT EXISTS main."my table" (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                [name] VARCHAR(20) NOT NULL COLLATE NOCASE DEFAULT 'x''y',
+                score REAL DEFAULT -1.5e3 CHECK (score > 0),
+                owner INT REFERENCES users(id) ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED,
+                created TEXT DEFAULT (datetime('now')),
+                full GENERATED ALWAYS AS (name || ' ' || score) STORED,
+                lower_name AS (lower(name))
+            )"#,
+        );
+        assert_eq!(t.name, "my table");
+        assert!(t.if_not_exists);
+        assert_eq!(t.rowid_alias(), Some(0));
+        assert!(t.columns[0].autoincrement);
+        let name = &t.columns[1];
+        assert_eq!(name.type_name.as_deref(), Some("VARCHAR(20)"));
+        assert_eq!(name.collation.as_deref(), Some("NOCASE"));
+        assert_eq!(name.default.as_deref(), Some("'x''y'"));
+        assert!(name.not_null);
+        assert_eq!(t.columns[2].default.as_deref(), Some("-1.5e3"));
+        assert_eq!(t.columns[3].affinity, Affinity::Integer);
+        assert_eq!(t.columns[4].default.as_deref(), Some("(datetime('now'))"));
+        assert_eq!(
+            t.columns[5].generated,
+            Some(Generated {
+                expr: "name || ' ' || score".to_string(),
+                stored: true
+            })
+        );
+        assert_eq!(
+            t.columns[6].generated.as_ref().map(|g| g.stored),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn rowid_alias_rules() {
+        assert_eq!(
+            table("CREATE TABLE t(a INTEGER, PRIMARY KEY(a))").rowid_alias(),
+            Some(0)
+        );
+        assert_eq!(
+            table("CREATE TABLE t(a INT PRIMARY KEY)").rowid_alias(),
+            None
+        );
+        assert_eq!(
+            table("CREATE TABLE t(a INTEGER PRIMARY KEY DESC)").rowid_alias(),
+            None
+        );
+        assert_eq!(
+            table("CREATE TABLE t(a INTEGER, PRIMARY KEY(a DESC))").rowid_alias(),
+            Some(0)
+        );
+        assert_eq!(
+            table("CREATE TABLE t(a INTEGER, b, PRIMARY KEY(a, b))").rowid_alias(),
+            None
+        );
+        let t = table("CREATE TABLE t(a INTEGER PRIMARY KEY, b TEXT) WITHOUT ROWID, STRICT");
+        assert!(t.without_rowid);
+        assert!(t.strict);
+        assert_eq!(t.rowid_alias(), None);
+    }
+
+    #[test]
+    fn autoindexes() {
+        let t = table("CREATE TABLE t(a TEXT PRIMARY KEY, b UNIQUE, c, UNIQUE(c, b), UNIQUE(b))");
+        let names = |n| {
+            t.autoindex_columns(n)
+                .map(|cs| cs.iter().map(|c| c.name.clone()).collect::<Vec<_>>())
+        };
+        assert_eq!(names(1), Some(vec!["a".to_string()]));
+        assert_eq!(names(2), Some(vec!["b".to_string()]));
+        assert_eq!(names(3), Some(vec!["c".to_string(), "b".to_string()]));
+        assert_eq!(names(4), None);
+    }
+
+    #[test]
+    fn indexes() {
+        let i = index("CREATE UNIQUE INDEX idx ON t (a COLLATE NOCASE DESC, lower(b), c ASC) WHERE a IS NOT NULL");
+        assert!(i.unique);
+        assert_eq!(i.table, "t");
+        assert_eq!(i.columns.len(), 3);
+        assert_eq!(i.columns[0].name, "a");
+        assert_eq!(i.columns[0].collation.as_deref(), Some("NOCASE"));
+        assert!(i.columns[0].desc);
+        assert!(i.columns[1].is_expression);
+        assert_eq!(i.columns[1].name, "lower(b)");
+        assert!(!i.columns[2].desc);
+        assert_eq!(i.where_clause.as_deref(), Some("a IS NOT NULL"));
+    }
+
+    #[test]
+    fn record_columns() {
+        let table_sql = "CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT COLLATE NOCASE UNIQUE, v AS (id + 1), s AS (id * 2) STORED)";
+        let mut t = SchemaEntry::new(
+            "table".into(),
+            "t".into(),
+            "t".into(),
+            2,
+            Some(table_sql.into()),
+        );
+        t.resolve_columns(None);
+        let names: Vec<_> = t.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "name", "s"]);
+        assert!(t.columns[0].rowid_alias);
+
+        let table = t.table().unwrap();
+        let mut auto = SchemaEntry::new(
+            "index".into(),
+            "sqlite_autoindex_t_1".into(),
+            "t".into(),
+            3,
+            None,
+        );
+        auto.resolve_columns(Some(table));
+        assert_eq!(auto.columns.len(), 2);
+        assert_eq!(auto.columns[0].name, "name");
+        assert_eq!(auto.columns[0].collation.as_deref(), Some("NOCASE"));
+        assert_eq!(auto.columns[1].name, "rowid");
+    }
+
+    #[test]
+    fn without_rowid_layout() {
+        let sql =
+            "CREATE TABLE w(a TEXT, b INT, c, d AS (c + 1), PRIMARY KEY(b DESC, a)) WITHOUT ROWID";
+        let mut w = SchemaEntry::new("table".into(), "w".into(), "w".into(), 2, Some(sql.into()));
+        w.resolve_columns(None);
+        assert!(w.is_without_rowid());
+        let layout: Vec<_> = w
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.declared, c.primary_key))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                ("b", Some(1), true),
+                ("a", Some(0), true),
+                ("c", Some(2), false)
+            ]
+        );
+        assert!(w.columns[0].desc);
+
+        let mut payload = vec![0x04, 0x01, 0x0f, 0x01, 0x07];
+        payload.extend_from_slice(b"x");
+        payload.push(0x03);
+        let record = Record::parse(&payload, crate::parser::TextEncoding::UTF8).unwrap();
+        assert_eq!(w.declared_order(&record), [1, 0, 2]);
+
+        let mut index = SchemaEntry::new(
+            "index".into(),
+            "wi".into(),
+            "w".into(),
+            3,
+            Some("CREATE INDEX wi ON w(c, a)".into()),
+        );
+        index.resolve_columns(w.table());
+        let names: Vec<_> = index.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn rejects_other_statements() {
+        assert!(parse_create("CREATE VIEW v AS SELECT 1").is_err());
+        assert!(parse_create("CREATE TABLE t(").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::{anyhow, bail, Result};
+
+use super::{varint::encode_varint, Page, Reader};
+
+/// 搜索的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Hex,
+    Text,
+    Integer,
+    Rowid,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 4] = [Self::Hex, Self::Text, Self::Integer, Self::Rowid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "Hex bytes",
+            Self::Text => "Text",
+            Self::Integer => "Integer",
+            Self::Rowid => "Rowid",
+        }
+    }
+
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            Self::Hex => "e.g. 53 51 4c 69",
+            Self::Text => "UTF-8 or UTF-16 text",
+            Self::Integer => "e.g. 4096 or 0x1000",
+            Self::Rowid => "rowid",
+        }
+    }
+}
+
+/// 要查找的内容
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// 任意字节串
+    Hex(Vec<u8>),
+    /// 文本，按 UTF-8、UTF-16le 和 UTF-16be 编码查找
+    Text(String),
+    /// 整数，按能容纳它的各种宽度的大端编码和 varint 查找
+    Integer(i64),
+    /// 表 B-Tree 单元中的 rowid
+    Rowid(i64),
+}
+
+/// 一处匹配
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// 匹配在文件中的偏移
+    pub offset: usize,
+    pub len: usize,
+    /// 匹配的编码，例如 "UTF-16le"、"4-byte integer"
+    pub encoding: String,
+}
+
+/// 解析整数，支持 0x 开头的十六进制
+fn parse_integer(input: &str) -> Result<i64> {
+    let input = input.trim();
+    let (digits, negative) = match input.strip_prefix('-') {
+        Some(rest) => (rest, true),
+        None => (input, false),
+    };
+    let hex = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"));
+    // 只允许开头的一个负号，parse 和 from_str_radix 还接受 + 和 -
+    if hex.unwrap_or(digits).starts_with(['+', '-']) {
+        bail!("{input} is not an integer");
+    }
+    let value = match hex {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+    .map_err(|_| anyhow!("{input} is not an integer"))?;
+    match negative {
+        true => value
+            .checked_neg()
+            .ok_or_else(|| anyhow!("{input} is out of range")),
+        false => Ok(value),
+    }
+}
+
+impl Query {
+    pub fn parse(kind: SearchKind, input: &str) -> Result<Self> {
+        if input.trim().is_empty() {
+            bail!("Enter something to search for");
+        }
+        Ok(match kind {
+            SearchKind::Hex => {
+                let input = input.trim();
+                let digits: String = input
+                    .strip_prefix("0x")
+                    .or_else(|| input.strip_prefix("0X"))
+                    .unwrap_or(input)
+                    .split_whitespace()
+                    .collect();
+                // 先排除非十六进制字符，之后按字节切分不会落在多字节字符中间
+                if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    bail!("{input} is not a hex pattern");
+                }
+                if !digits.len().is_multiple_of(2) {
+                    bail!("A hex pattern needs two digits per byte");
+                }
+                let bytes = (0..digits.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self::Hex(bytes)
+            }
+            SearchKind::Text => Self::Text(input.to_string()),
+            SearchKind::Integer => Self::Integer(parse_integer(input)?),
+            SearchKind::Rowid => Self::Rowid(parse_integer(input)?),
+        })
+    }
+
+    /// 要在文件中查找的字节串和它们的编码。rowid 不按字节查找。
+    pub fn patterns(&self) -> Vec<(String, Vec<u8>)> {
+        match self {
+            Self::Hex(bytes) => vec![("bytes".to_string(), bytes.clone())],
+            Self::Text(text) => {
+                let utf16 = |to_bytes: fn(u16) -> [u8; 2]| {
+                    text.encode_utf16().flat_map(to_bytes).collect::<Vec<u8>>()
+                };
+                vec![
+                    ("UTF-8".to_string(), text.as_bytes().to_vec()),
+                    ("UTF-16le".to_string(), utf16(u16::to_le_bytes)),
+                    ("UTF-16be".to_string(), utf16(u16::to_be_bytes)),
+                ]
+            }
+            &Self::Integer(value) => {
+                let be = value.to_be_bytes();
+                // record 中整数的宽度。record 中的整数有符号，头中的字段无符号，
+                // 按任一种能容纳这个值的宽度查找
+                let mut patterns: Vec<(String, Vec<u8>)> = [1, 2, 3, 4, 6, 8]
+                    .into_iter()
+                    .filter(|&n| n == 8 || (-(1 << (8 * n - 1))..1 << (8 * n)).contains(&value))
+                    .map(|n| (format!("{n}-byte integer"), be[8 - n..].to_vec()))
+                    .collect();
+                patterns.push(("varint".to_string(), encode_varint(value)));
+                patterns
+            }
+            Self::Rowid(_) => vec![],
+        }
+    }
+}
+
+/// 在 bytes 中查找 query，最多返回 limit 处，按偏移排列。
+/// 被同一位置开始的更长匹配包含的匹配不单独列出，例如 4 字节整数中的 2 字节整数。
+pub fn search_bytes(bytes: &[u8], query: &Query, limit: usize) -> Vec<Match> {
+    let mut matches = vec![];
+    for (encoding, pattern) in query.patterns() {
+        if pattern.is_empty() || pattern.len() > bytes.len() {
+            continue;
+        }
+        matches.extend(
+            bytes
+                .windows(pattern.len())
+                .enumerate()
+                .filter(|(_, w)| *w == &pattern[..])
+                .take(limit)
+                .map(|(offset, _)| Match {
+                    offset,
+                    len: pattern.len(),
+                    encoding: encoding.clone(),
+                }),
+        );
+    }
+    matches.sort_by_key(|m| (m.offset + m.len, std::cmp::Reverse(m.len)));
+    let mut end_of_longest = None;
+    matches.retain(|m| {
+        let keep = end_of_longest != Some(m.offset + m.len);
+        end_of_longest = Some(m.offset + m.len);
+        keep
+    });
+    matches.sort_by_key(|m| m.offset);
+    matches.truncate(limit);
+    matches
+}
+
+impl Reader {
+    /// 表 B-Tree 中 rowid 等于 rowid 的单元中的 rowid 字段。
+    /// 叶子页的单元是那一行本身，内部页的单元是分隔子树的键。
+    pub fn find_rowids(&self, rowid: i64) -> Vec<Match> {
+        let mut matches = vec![];
+        for page in self.pages() {
+            let Page::BTree(page) = &*page else {
+                continue;
+            };
+            for cell in &page.cells {
+                let Some((_, len)) = cell.rowid.filter(|r| r.0 == rowid) else {
+                    continue;
+                };
+                let offset = cell.offset
+                    + cell.left_child.map_or(0, |_| 4)
+                    + cell.payload_size.map_or(0, |p| p.1);
+                let encoding = if page.header.page_type.is_leaf() {
+                    "rowid"
+                } else {
+                    "rowid key"
+                };
+                matches.push(Match {
+                    offset,
+                    len,
+                    encoding: encoding.to_string(),
+                });
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::RecordValue, writer::DatabaseBuilder};
+
+    #[test]
+    fn parse_queries() {
+        assert_eq!(
+            Query::parse(SearchKind::Hex, "53 51 4c").unwrap(),
+            Query::Hex(vec![0x53, 0x51, 0x4c])
+        );
+        assert!(Query::parse(SearchKind::Hex, "5 3").is_ok());
+        assert!(Query::parse(SearchKind::Hex, "535").is_err());
+        assert!(Query::parse(SearchKind::Hex, "zz").is_err());
+        assert!(Query::parse(SearchKind::Hex, "aé1").is_err());
+        assert!(Query::parse(SearchKind::Hex, "é").is_err());
+        assert_eq!(
+            Query::parse(SearchKind::Hex, "0X0a0b").unwrap(),
+            Query::Hex(vec![0x0a, 0x0b])
+        );
+        // 只去掉一个前缀
+        assert!(Query::parse(SearchKind::Hex, "0x0x01").is_err());
+        assert_eq!(
+            Query::parse(SearchKind::Integer, "0x1000").unwrap(),
+            Query::Integer(4096)
+        );
+        assert_eq!(
+            Query::parse(SearchKind::Rowid, "-3").unwrap(),
+            Query::Rowid(-3)
+        );
+        assert!(Query::parse(SearchKind::Integer, "--5").is_err());
+        assert!(Query::parse(SearchKind::Integer, "-+5").is_err());
+        assert!(Query::parse(SearchKind::Integer, "--9223372036854775808").is_err());
+        assert!(Query::parse(SearchKind::Text, " ").is_err());
+
+        // 40000 放不进有符号的 2 字节，但可以是无符号的页头字段
+        let widths: Vec<_> = Query::Integer(40000)
+            .patterns()
+            .into_iter()
+            .map(|p| p.0)
+            .collect();
+        assert_eq!(
+            widths,
+            vec![
+                "2-byte integer",
+                "3-byte integer",
+                "4-byte integer",
+                "6-byte integer",
+                "8-byte integer",
+                "varint"
+            ]
+        );
+    }
+
+    #[test]
+    fn find_values_in_a_database() {
+        let rows = vec![
+            vec![
+                RecordValue::Integer(70000),
+                RecordValue::Text("needle".to_string()),
+            ],
+            vec![RecordValue::Integer(2), RecordValue::Null],
+        ];
+        let bytes = DatabaseBuilder::new(1024)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+
+        let text = search_bytes(&bytes, &Query::Text("needle".to_string()), 100);
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[0].encoding, "UTF-8");
+        assert_eq!(&bytes[text[0].offset..text[0].offset + 6], b"needle");
+
+        // 70000 在 record 中保存为 3 字节整数，不再单独列出其中的 2 字节
+        let integer = search_bytes(&bytes, &Query::Integer(70000), 100);
+        assert_eq!(integer.len(), 1);
+        assert_eq!(integer[0].encoding, "3-byte integer");
+
+        // 页大小在数据库头的第 16 字节
+        let page_size = search_bytes(&bytes, &Query::Hex(vec![0x04, 0x00]), 100);
+        assert_eq!(page_size[0].offset, 16);
+        assert_eq!(search_bytes(&bytes, &Query::Hex(vec![0x00]), 3).len(), 3);
+
+        let reader = Reader::new(&bytes).unwrap();
+        let rowids = reader.find_rowids(2);
+        assert_eq!(rowids.len(), 1);
+        assert_eq!(rowids[0].encoding, "rowid");
+        assert_eq!(bytes[rowids[0].offset], 2);
+        assert!(reader.find_rowids(3).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use super::wal::checksum as wal_checksum;
+
+/// wal-index 头的字节数，文件开头有两份相同的头
+pub const INDEX_HEADER_SIZE: usize = 48;
+
+/// 两份 wal-index 头和检查点信息的字节数，之后是帧号的哈希表
+pub const SHM_HEADER_SIZE: usize = 136;
+
+/// wal-index 的版本号，也用来判断文件的字节序
+const INDEX_VERSION: u32 = 3007000;
+
+/// -shm 文件中的 wal-index 头。整数按写入它的机器的字节序保存。
+#[derive(Debug, Clone, Serialize)]
+pub struct WalIndexHeader {
+    /// 头在 -shm 文件中的偏移，0 或 48
+    pub offset: usize,
+    /// wal-index 格式的版本，3007000
+    /// offset: 0, size: 4
+    pub version: u32,
+    /// 每次事务提交时递增
+    /// offset: 8, size: 4
+    pub change: u32,
+    /// 头已经初始化时为 1
+    /// offset: 12, size: 1
+    pub is_init: u8,
+    /// WAL 文件的校验和按大端计算时为 1
+    /// offset: 13, size: 1
+    pub big_end_checksum: u8,
+    /// 页大小，65536 保存为 1
+    /// offset: 14, size: 2
+    pub page_size: u16,
+    /// WAL 中最后一个有效提交帧的序号
+    /// offset: 16, size: 4
+    pub max_frame: u32,
+    /// 数据库的页数
+    /// offset: 20, size: 4
+    pub db_size: u32,
+    /// 最后一帧的校验和
+    /// offset: 24, size: 8
+    pub frame_checksum: [u32; 2],
+    /// WAL 头中的 salt
+    /// offset: 32, size: 8
+    pub salt: [u32; 2],
+    /// 前 40 字节的校验和
+    /// offset: 40, size: 8
+    pub checksum: [u32; 2],
+    /// 校验和是否与前 40 字节一致
+    pub valid: bool,
+}
+
+/// 检查点的进度和读者持有的位置
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointInfo {
+    /// 已经写回数据库的帧数
+    /// offset: 96, size: 4
+    pub backfill: u32,
+    /// 五个读锁对应的 mxFrame，0xffffffff 表示没有使用
+    /// offset: 100, size: 20
+    pub read_marks: [u32; 5],
+    /// 供文件锁使用的字节，内容没有意义
+    /// offset: 120, size: 8
+    pub locks: [u8; 8],
+    /// 尝试写回数据库的帧数
+    /// offset: 128, size: 4
+    pub backfill_attempted: u32,
+    /// offset: 132, size: 4
+    pub not_used: u32,
+}
+
+/// -shm 文件的开头：wal-index 头的两份拷贝和检查点信息
+#[derive(Debug, Clone, Serialize)]
+pub struct WalIndex {
+    /// 文件中的整数是否按大端保存
+    pub big_endian: bool,
+    pub headers: [WalIndexHeader; 2],
+    pub checkpoint: CheckpointInfo,
+}
+
+impl WalIndexHeader {
+    /// 页大小的字节数
+    pub fn real_page_size(&self) -> u32 {
+        (self.page_size as u32 & 0xfe00) + ((self.page_size as u32 & 1) << 16)
+    }
+}
+
+impl WalIndex {
+    pub fn parse(value: &[u8]) -> Result<Self> {
+        if value.len() < SHM_HEADER_SIZE {
+            bail!(
+                "The file is {} bytes, too short for a wal-index header",
+                value.len()
+            );
+        }
+        let big_endian = match value[..4].try_into()? {
+            b if u32::from_le_bytes(b) == INDEX_VERSION => false,
+            b if u32::from_be_bytes(b) == INDEX_VERSION => true,
+            b => bail!("Unknown wal-index version {}", u32::from_le_bytes(b)),
+        };
+        let u32_at = |offset: usize| {
+            let b = value[offset..offset + 4].try_into().expect("4 bytes");
+            if big_endian {
+                u32::from_be_bytes(b)
+            } else {
+                u32::from_le_bytes(b)
+            }
+        };
+        let header = |offset: usize| {
+            let page_size = [value[offset + 14], value[offset + 15]];
+            let checksum = [u32_at(offset + 40), u32_at(offset + 44)];
+            let sum = wal_checksum(big_endian, (0, 0), &value[offset..offset + 40]);
+            WalIndexHeader {
+                offset,
+                version: u32_at(offset),
+                change: u32_at(offset + 8),
+                is_init: value[offset + 12],
+                big_end_checksum: value[offset + 13],
+                page_size: if big_endian {
+                    u16::from_be_bytes(page_size)
+                } else {
+                    u16::from_le_bytes(page_size)
+                },
+                max_frame: u32_at(offset + 16),
+                db_size: u32_at(offset + 20),
+                frame_checksum: [u32_at(offset + 24), u32_at(offset + 28)],
+                salt: [u32_at(offset + 32), u32_at(offset + 36)],
+                checksum,
+                valid: checksum == [sum.0, sum.1],
+            }
+        };
+        Ok(Self {
+            big_endian,
+            headers: [header(0), header(INDEX_HEADER_SIZE)],
+            checkpoint: CheckpointInfo {
+                backfill: u32_at(96),
+                read_marks: [100, 104, 108, 112, 116].map(u32_at),
+                locks: value[120..128].try_into()?,
+                backfill_attempted: u32_at(128),
+                not_used: u32_at(132),
+            },
+        })
+    }
+
+    /// 两份头是否相同。不同时说明写入者正在修改 wal-index，或者文件已经损坏。
+    pub fn consistent(&self) -> bool {
+        let [a, b] = &self.headers;
+        (a.change, a.max_frame, a.db_size, a.salt, a.checksum)
+            == (b.change, b.max_frame, b.db_size, b.salt, b.checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 小端机器上写入的 -shm 文件的开头
+    fn shm(max_frame: u32) -> Vec<u8> {
+        let mut header = vec![];
+        for v in [INDEX_VERSION, 0, 7] {
+            header.extend(v.to_le_bytes());
+        }
+        header.extend([1, 0]);
+        header.extend(4096u16.to_le_bytes());
+        for v in [max_frame, 3, 11, 12, 0xaa, 0xbb] {
+            header.extend(v.to_le_bytes());
+        }
+        let sum = wal_checksum(false, (0, 0), &header);
+        header.extend(sum.0.to_le_bytes());
+        header.extend(sum.1.to_le_bytes());
+        let mut bytes = [header.clone(), header].concat();
+        for v in [2, 0, 2, u32::MAX, u32::MAX, u32::MAX] {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend([0; 8]);
+        bytes.extend([2u32, 0].map(u32::to_le_bytes).concat());
+        bytes.resize(32768, 0);
+        bytes
+    }
+
+    #[test]
+    fn parse_wal_index() {
+        let mut bytes = shm(2);
+        let index = WalIndex::parse(&bytes).unwrap();
+        assert!(!index.big_endian);
+        assert!(index.consistent());
+        let header = &index.headers[0];
+        assert!(header.valid);
+        assert_eq!(
+            (header.max_frame, header.db_size, header.real_page_size()),
+            (2, 3, 4096)
+        );
+        assert_eq!(index.checkpoint.backfill, 2);
+        assert_eq!(index.checkpoint.read_marks[1], 2);
+
+        // 写入者只更新了第一份头
+        bytes[16] = 3;
+        let index = WalIndex::parse(&bytes).unwrap();
+        assert!(!index.headers[0].valid && index.headers[1].valid);
+        assert!(!index.consistent());
+        assert!(WalIndex::parse(&bytes[..100]).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    rc::Rc,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+/// 可随机访问的数据库文件，Reader 只通过它按页读取数据
+pub trait PageSource: Debug {
+    /// 文件的总字节数
+    fn size(&self) -> u64;
+
+    /// 从 offset 开始读满 buf
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+/// 已经在内存中的数据库文件，例如 `Vec<u8>`、`Rc<[u8]>` 或 `&[u8]`
+#[derive(Debug)]
+pub struct MemorySource<B>(pub B);
+
+impl<B: AsRef<[u8]> + Debug> PageSource for MemorySource<B> {
+    fn size(&self) -> u64 {
+        self.0.as_ref().len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let bytes = self.0.as_ref();
+        let start = usize::try_from(offset).unwrap_or(usize::MAX);
+        let src = start
+            .checked_add(buf.len())
+            .and_then(|end| bytes.get(start..end))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        buf.copy_from_slice(src);
+        Ok(())
+    }
+}
+
+/// 通过 seek + read 按需读取的文件
+#[derive(Debug)]
+pub struct FileSource {
+    file: RefCell<File>,
+    size: u64,
+}
+
+impl FileSource {
+    pub fn new(file: File) -> io::Result<Self> {
+        let size = file.metadata()?.len();
+        Ok(Self {
+            file: RefCell::new(file),
+            size,
+        })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl PageSource for FileSource {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+}
+
+/// 内存映射的文件，由操作系统按需换入页面
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct MmapSource {
+    map: memmap2::Mmap,
+}
+
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+impl MmapSource {
+    /// # Safety
+    ///
+    /// 映射期间文件不能被其它进程截断或修改，否则读取到的内容未定义
+    pub unsafe fn new(file: &File) -> io::Result<Self> {
+        Ok(Self {
+            map: memmap2::Mmap::map(file)?,
+        })
+    }
+}
+
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+impl PageSource for MmapSource {
+    fn size(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        MemorySource(&self.map[..]).read_at(offset, buf)
+    }
+}
+
+/// 按页号索引的缓存，最多保留 capacity 项，超出时淘汰最久未使用的项
+#[derive(Debug)]
+pub struct Lru<T> {
+    capacity: usize,
+    items: HashMap<u32, T>,
+    /// 最近使用的页在队尾
+    order: VecDeque<u32>,
+}
+
+impl<T: Clone> Lru<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            items: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// 取出第 number 页的项，并把它变成最近使用的
+    pub fn get(&mut self, number: u32) -> Option<T> {
+        let item = self.items.get(&number)?.clone();
+        if let Some(i) = self.order.iter().position(|&n| n == number) {
+            self.order.remove(i);
+        }
+        self.order.push_back(number);
+        Some(item)
+    }
+
+    pub fn insert(&mut self, number: u32, item: T) {
+        if self.items.insert(number, item).is_some() {
+            self.order.retain(|&n| n != number);
+        }
+        while self.items.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(old) => self.items.remove(&old),
+                None => break,
+            };
+        }
+        self.order.push_back(number);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.order.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+/// 在 PageSource 之上按页缓存，最多保留 capacity 页，超出时淘汰最久未使用的页
+#[derive(Debug)]
+pub struct PageCache {
+    source: Box<dyn PageSource>,
+    page_size: usize,
+    pages: RefCell<Lru<Rc<[u8]>>>,
+    /// 从 PageSource 读取页的次数
+    reads: Cell<usize>,
+}
+
+impl PageCache {
+    pub fn new(source: Box<dyn PageSource>, page_size: usize, capacity: usize) -> Self {
+        Self {
+            source,
+            page_size,
+            pages: RefCell::new(Lru::new(capacity)),
+            reads: Cell::new(0),
+        }
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// 文件的总字节数，包括末尾不满一页的部分
+    pub fn size(&self) -> u64 {
+        self.source.size()
+    }
+
+    /// 文件中完整的页数
+    pub fn page_count(&self) -> u32 {
+        (self.source.size() / self.page_size as u64).min(u32::MAX as u64) as u32
+    }
+
+    /// 实际从 PageSource 读取过多少次页
+    pub fn reads(&self) -> usize {
+        self.reads.get()
+    }
+
+    /// 当前缓存中的页数
+    pub fn cached(&self) -> usize {
+        self.pages.borrow().len()
+    }
+
+    /// 按页号（从 1 开始）读取一页
+    pub fn page(&self, number: u32) -> Result<Rc<[u8]>> {
+        if number == 0 || number > self.page_count() {
+            bail!(
+                "Page {number} is out of range (the file has {} pages)",
+                self.page_count()
+            );
+        }
+        if let Some(page) = self.pages.borrow_mut().get(number) {
+            return Ok(page);
+        }
+
+        let mut buf = vec![0; self.page_size];
+        let offset = (number as u64 - 1) * self.page_size as u64;
+        self.source
+            .read_at(offset, &mut buf)
+            .map_err(|e| anyhow!("Failed to read page {number}: {e}"))?;
+        self.reads.set(self.reads.get() + 1);
+        let page: Rc<[u8]> = buf.into();
+        self.pages.borrow_mut().insert(number, page.clone());
+        Ok(page)
+    }
+
+    /// 直接从 PageSource 读取任意范围，不经过缓存
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        self.source
+            .read_at(offset, buf)
+            .map_err(|e| anyhow!("Failed to read {} bytes at {offset}: {e}", buf.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::{Page, Reader},
+        ui::viewer::SIMPLE_DB,
+    };
+
+    #[test]
+    fn lru_cache_evicts_least_recently_used() {
+        let bytes: Vec<u8> = (0..8u8).flat_map(|i| [i; 512]).collect();
+        let cache = PageCache::new(Box::new(MemorySource(bytes)), 512, 2);
+        assert_eq!(cache.page_count(), 8);
+        assert_eq!(cache.page(1).unwrap()[0], 0);
+        assert_eq!(cache.page(2).unwrap()[0], 1);
+        // 命中缓存，并把第 1 页变成最近使用的
+        cache.page(1).unwrap();
+        assert_eq!(cache.reads(), 2);
+        // 淘汰第 2 页
+        assert_eq!(cache.page(3).unwrap()[511], 2);
+        assert_eq!(cache.cached(), 2);
+        cache.page(1).unwrap();
+        assert_eq!(cache.reads(), 3);
+        cache.page(2).unwrap();
+        assert_eq!(cache.reads(), 4);
+
+        assert!(cache.page(0).is_err());
+        assert!(cache.page(9).is_err());
+    }
+
+    #[test]
+    fn file_source_reads_at_offset() {
+        let path = std::env::temp_dir().join(format!("page-source-{}", std::process::id()));
+        std::fs::write(&path, (0..=255u8).collect::<Vec<_>>()).unwrap();
+        let source = FileSource::open(&path).unwrap();
+        let mut buf = [0; 4];
+        source.read_at(100, &mut buf).unwrap();
+        assert_eq!(buf, [100, 101, 102, 103]);
+        assert_eq!(source.size(), 256);
+        assert!(source.read_at(254, &mut buf).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// 把 SIMPLE_DB 写入临时文件，返回它的路径
+    fn simple_db_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        std::fs::write(&path, SIMPLE_DB).unwrap();
+        path
+    }
+
+    /// 从文件中读到的页与内存中的相同
+    fn check_reader(reader: &Reader) {
+        let page_size = reader.header.real_page_size();
+        assert_eq!(reader.page_count(), 2);
+        assert_eq!(reader.schema[1].name, "simple");
+        assert_eq!(
+            *reader.cache().page(2).unwrap(),
+            SIMPLE_DB[page_size..2 * page_size]
+        );
+        let Some(Page::BTree(page)) = reader.page(2).as_deref().cloned() else {
+            panic!("page 2 should be a b-tree page");
+        };
+        assert_eq!(page.cells.len(), 4);
+        reader.load_all();
+        assert_eq!(reader.diagnostics(), vec![]);
+    }
+
+    #[test]
+    fn read_database_file() {
+        let path = simple_db_file("file-source");
+        let reader = Reader::open(FileSource::open(&path).unwrap(), 2).unwrap();
+        check_reader(&reader);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+    #[test]
+    fn read_mapped_database_file() {
+        let path = simple_db_file("mmap-source");
+        let file = File::open(&path).unwrap();
+        // 测试期间没有其它进程修改这个文件
+        let source = unsafe { MmapSource::new(&file) }.unwrap();
+        assert_eq!(source.size(), SIMPLE_DB.len() as u64);
+        let reader = Reader::open(source, 2).unwrap();
+        check_reader(&reader);
+        drop(reader);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+/// 读取一个 SQLite varint（大端，1~9 字节）。
+/// 前 8 个字节每个贡献低 7 位，高位为 1 表示后面还有字节；
+/// 第 9 个字节的 8 位全部参与。
+/// 返回 (值, 占用的字节数)，缓冲区不足时返回 None。
+pub fn read_varint(bytes: &[u8]) -> Option<(i64, usize)> {
+    let mut value: u64 = 0;
+    for i in 0..9 {
+        let b = *bytes.get(i)?;
+        if i == 8 {
+            value = (value << 8) | b as u64;
+            return Some((value as i64, 9));
+        }
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            return Some((value as i64, i + 1));
+        }
+    }
+    unreachable!()
+}
+
+/// 将整数编码为最短的 SQLite varint。
+pub fn encode_varint(value: i64) -> Vec<u8> {
+    let v = value as u64;
+    if v & 0xff00_0000_0000_0000 != 0 {
+        // 需要 9 个字节：最后一个字节保存完整的低 8 位
+        let mut out = vec![0u8; 9];
+        out[8] = v as u8;
+        let mut rest = v >> 8;
+        for i in (0..8).rev() {
+            out[i] = (rest & 0x7f) as u8 | 0x80;
+            rest >>= 7;
+        }
+        return out;
+    }
+    let mut out = Vec::with_capacity(9);
+    let mut rest = v;
+    loop {
+        out.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    out[0] &= 0x7f;
+    out.reverse();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip() {
+        for v in [
+            0,
+            1,
+            127,
+            128,
+            240,
+            2287,
+            16383,
+            16384,
+            1 << 40,
+            -1,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            let bytes = encode_varint(v);
+            assert_eq!(read_varint(&bytes), Some((v, bytes.len())), "value {v}");
+        }
+        assert_eq!(encode_varint(-1).len(), 9);
+        assert_eq!(read_varint(&[0x81]), None);
+    }
+}
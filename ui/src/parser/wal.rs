@@ -0,0 +1,313 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+use super::Bytes;
+use crate::slc;
+
+/// WAL 头的字节数
+pub const WAL_HEADER_SIZE: usize = 32;
+
+/// 每一帧在页内容之前的帧头的字节数
+pub const FRAME_HEADER_SIZE: usize = 24;
+
+/// 校验和按小端计算的文件的 magic，最低位为 1 时按大端计算
+pub const WAL_MAGIC: u32 = 0x377f0682;
+
+/// apply 写回后的数据库最多的字节数。损坏的 WAL 可能声称提交了 2^32 页的数据库
+pub const MAX_APPLIED_SIZE: usize = 1 << 30;
+
+/// WAL 文件开头的 32 字节
+#[derive(Debug, Clone, Serialize)]
+pub struct WalHeader {
+    /// 0x377f0682 或 0x377f0683，最低位表示校验和的字节序
+    /// offset: 0, size: 4
+    pub magic: u32,
+    /// 文件格式版本，目前为 3007000
+    /// offset: 4, size: 4
+    pub version: u32,
+    /// 数据库的页大小，65536 直接保存
+    /// offset: 8, size: 4
+    pub page_size: u32,
+    /// 检查点序号，每次检查点后重置 WAL 时递增
+    /// offset: 12, size: 4
+    pub checkpoint_seq: u32,
+    /// 每次检查点后重新生成的随机数，帧头中的 salt 必须与之相同
+    /// offset: 16, size: 4
+    pub salt1: u32,
+    /// offset: 20, size: 4
+    pub salt2: u32,
+    /// 前 24 字节的校验和
+    /// offset: 24, size: 4
+    pub checksum1: u32,
+    /// offset: 28, size: 4
+    pub checksum2: u32,
+    /// 校验和是否与前 24 字节一致
+    pub valid: bool,
+}
+
+impl WalHeader {
+    /// 校验和是否按大端读取整数
+    pub fn big_endian_checksum(&self) -> bool {
+        self.magic & 1 == 1
+    }
+}
+
+/// WAL 中的一帧：帧头和一页新的内容
+#[derive(Debug, Clone, Serialize)]
+pub struct WalFrame {
+    /// 帧序号，从 1 开始
+    pub index: usize,
+    /// 帧头在 WAL 文件中的偏移
+    pub offset: usize,
+    /// 这一帧保存的页的页号
+    /// offset: 0, size: 4
+    pub page_number: u32,
+    /// 提交帧中是事务提交后数据库的页数，其它帧为 0
+    /// offset: 4, size: 4
+    pub db_size: u32,
+    /// 与 WAL 头相同时帧才有效
+    /// offset: 8, size: 4
+    pub salt1: u32,
+    /// offset: 12, size: 4
+    pub salt2: u32,
+    /// 从 WAL 头开始累积到这一帧的校验和
+    /// offset: 16, size: 4
+    pub checksum1: u32,
+    /// offset: 20, size: 4
+    pub checksum2: u32,
+    /// salt 与 WAL 头相同，校验和正确，且之前的帧都有效
+    pub valid: bool,
+    /// 属于已提交的事务：有效，并且它或它之后有有效的提交帧
+    pub committed: bool,
+    /// 页的新内容
+    pub content: Bytes,
+}
+
+impl WalFrame {
+    pub fn is_commit(&self) -> bool {
+        self.db_size != 0
+    }
+}
+
+/// 解析后的 WAL 文件。最后一个有效帧之后的内容是以前的事务留下的，SQLite 会忽略它们。
+#[derive(Debug, Clone, Serialize)]
+pub struct Wal {
+    pub header: Rc<WalHeader>,
+    pub frames: Vec<Rc<WalFrame>>,
+}
+
+/// SQLite 的 WAL 校验和：按 8 字节一组累加，data 的长度是 8 的倍数
+pub fn checksum(big_endian: bool, init: (u32, u32), data: &[u8]) -> (u32, u32) {
+    let read = |b: &[u8]| {
+        let b = b.try_into().expect("4 bytes");
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    let (mut s1, mut s2) = init;
+    for chunk in data.chunks_exact(8) {
+        s1 = s1.wrapping_add(read(&chunk[..4])).wrapping_add(s2);
+        s2 = s2.wrapping_add(read(&chunk[4..])).wrapping_add(s1);
+    }
+    (s1, s2)
+}
+
+impl Wal {
+    /// 解析 WAL 头和所有完整的帧。只有 WAL 头无法使用时才返回错误。
+    pub fn parse(bytes: Rc<[u8]>) -> Result<Self> {
+        let value = &bytes[..];
+        if value.len() < WAL_HEADER_SIZE {
+            bail!(
+                "The file is {} bytes, too short for a WAL header",
+                value.len()
+            );
+        }
+        let magic = slc!(value, 0, 4, u32);
+        if magic & !1 != WAL_MAGIC {
+            bail!("Bad WAL magic number {magic:#010x}");
+        }
+        let page_size = slc!(value, 8, 4, u32);
+        if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+            bail!("Invalid WAL page size {page_size}");
+        }
+        let mut header = WalHeader {
+            magic,
+            version: slc!(value, 4, 4, u32),
+            page_size,
+            checkpoint_seq: slc!(value, 12, 4, u32),
+            salt1: slc!(value, 16, 4, u32),
+            salt2: slc!(value, 20, 4, u32),
+            checksum1: slc!(value, 24, 4, u32),
+            checksum2: slc!(value, 28, 4, u32),
+            valid: false,
+        };
+        let big_endian = header.big_endian_checksum();
+        let mut sum = checksum(big_endian, (0, 0), &value[..24]);
+        header.valid = sum == (header.checksum1, header.checksum2);
+
+        let frame_size = FRAME_HEADER_SIZE + page_size as usize;
+        let mut valid = header.valid;
+        let mut frames = vec![];
+        let mut offset = WAL_HEADER_SIZE;
+        while offset + frame_size <= value.len() {
+            let content_start = offset + FRAME_HEADER_SIZE;
+            let mut frame = WalFrame {
+                index: frames.len() + 1,
+                offset,
+                page_number: slc!(value, offset, 4, u32),
+                db_size: slc!(value, offset + 4, 4, u32),
+                salt1: slc!(value, offset + 8, 4, u32),
+                salt2: slc!(value, offset + 12, 4, u32),
+                checksum1: slc!(value, offset + 16, 4, u32),
+                checksum2: slc!(value, offset + 20, 4, u32),
+                valid: false,
+                committed: false,
+                content: Bytes::new(bytes.clone(), content_start..offset + frame_size),
+            };
+            if valid {
+                sum = checksum(big_endian, sum, &value[offset..offset + 8]);
+                sum = checksum(big_endian, sum, &frame.content);
+                valid = frame.page_number != 0
+                    && (frame.salt1, frame.salt2) == (header.salt1, header.salt2)
+                    && sum == (frame.checksum1, frame.checksum2);
+            }
+            frame.valid = valid;
+            frames.push(frame);
+            offset += frame_size;
+        }
+
+        // 最后一个有效的提交帧之前的有效帧都已提交
+        if let Some(last) = frames.iter().rposition(|f| f.valid && f.is_commit()) {
+            for frame in &mut frames[..=last] {
+                frame.committed = true;
+            }
+        }
+        Ok(Self {
+            header: Rc::new(header),
+            frames: frames.into_iter().map(Rc::new).collect(),
+        })
+    }
+
+    /// 最后一个已提交的事务之后数据库的页数，没有已提交的事务时为 None
+    pub fn db_size(&self) -> Option<u32> {
+        self.frames
+            .iter()
+            .rev()
+            .find(|f| f.committed && f.is_commit())
+            .map(|f| f.db_size)
+    }
+
+    /// 每一页最新的已提交帧，按页号排列
+    pub fn latest_frames(&self) -> BTreeMap<u32, Rc<WalFrame>> {
+        self.frames
+            .iter()
+            .filter(|f| f.committed)
+            .map(|f| (f.page_number, f.clone()))
+            .collect()
+    }
+
+    /// 检查点之后的数据库：已提交的帧写回对应的页，文件大小取最后一个事务提交时的页数
+    pub fn apply(&self, db: &[u8]) -> Result<Vec<u8>> {
+        let Some(db_size) = self.db_size() else {
+            return Ok(db.to_vec());
+        };
+        let page_size = self.header.page_size as usize;
+        if db.len() >= 100 {
+            let db_page_size = match u16::from_be_bytes([db[16], db[17]]) {
+                1 => 65536,
+                n => n as usize,
+            };
+            if db_page_size != page_size {
+                bail!(
+                    "The WAL page size {page_size} doesn't match the database page size {db_page_size}"
+                );
+            }
+        }
+        // 数据库变大时新的页都写在 WAL 中，所以页数不会超过两者中较大的一个
+        let max_page = self.latest_frames().keys().last().copied().unwrap_or(0);
+        let limit = (db.len() / page_size).max(max_page as usize);
+        if db_size as usize > limit {
+            bail!("The WAL commits a database of {db_size} pages, but only {limit} pages exist");
+        }
+        let size = (db_size as usize)
+            .checked_mul(page_size)
+            .filter(|&size| size <= MAX_APPLIED_SIZE.max(db.len()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "The WAL commits a database of {db_size} pages, larger than {} MiB",
+                    MAX_APPLIED_SIZE >> 20
+                )
+            })?;
+        let mut bytes = db.to_vec();
+        bytes.resize(size, 0);
+        for (number, frame) in self.latest_frames() {
+            let range = (number as usize - 1)
+                .checked_mul(page_size)
+                .and_then(|start| Some(start..start.checked_add(page_size)?));
+            if let Some(page) = range.and_then(|range| bytes.get_mut(range)) {
+                page.copy_from_slice(&frame.content);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::{Reader, RecordValue},
+        writer::{wal::build_wal, DatabaseBuilder},
+    };
+
+    fn db(rows: i64) -> Vec<u8> {
+        let rows = (1..=rows).map(|i| vec![RecordValue::Integer(i)]).collect();
+        DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a)", rows)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn apply_committed_frames() {
+        let old = db(3);
+        let new = db(200);
+        let pages: Vec<&[u8]> = new.chunks(512).collect();
+        // 第一个事务写入新数据库的所有页，第二个事务没有提交
+        let mut frames: Vec<(u32, &[u8], u32)> = pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i as u32 + 1, *p, 0))
+            .collect();
+        frames.last_mut().unwrap().2 = pages.len() as u32;
+        frames.push((1, &old[..512], 0));
+        let wal = Wal::parse(Rc::from(build_wal(512, &frames))).unwrap();
+
+        assert!(wal.header.valid);
+        assert!(wal.frames.iter().all(|f| f.valid));
+        assert!(!wal.frames.last().unwrap().committed);
+        assert_eq!(wal.db_size(), Some(pages.len() as u32));
+        let applied = wal.apply(&old).unwrap();
+        assert_eq!(applied, new);
+        assert_eq!(Reader::new(&applied).unwrap().diagnostics(), vec![]);
+    }
+
+    #[test]
+    fn stop_at_the_first_invalid_frame() {
+        let old = db(3);
+        let new = db(4);
+        let mut bytes = build_wal(512, &[(2, &new[512..1024], 2), (2, &old[512..1024], 2)]);
+        // 改坏第一帧的内容后，两帧都无效
+        bytes[WAL_HEADER_SIZE + FRAME_HEADER_SIZE + 100] ^= 1;
+        let wal = Wal::parse(Rc::from(bytes)).unwrap();
+        assert_eq!(wal.frames.len(), 2);
+        assert!(wal.frames.iter().all(|f| !f.valid && !f.committed));
+        assert_eq!(wal.apply(&old).unwrap(), old);
+
+        assert!(Wal::parse(Rc::from(&old[..])).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::parser::{Analysis, SpaceUsage};
+
+use super::{catalog, i18n::Text, state::AppState, Field, Parts};
+
+pub const ANALYSIS_LABEL: &str = "Analysis";
+
+impl Parts for Analysis {
+    fn label(&self) -> String {
+        ANALYSIS_LABEL.to_string()
+    }
+
+    fn desc(&self) -> Text {
+        catalog::analysis(
+            self.total_pages,
+            self.page_size,
+            self.all.pages(),
+            self.freelist_pages,
+            self.other_pages,
+        )
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        Rc::new([])
+    }
+}
+
+fn number(v: Option<f64>) -> String {
+    v.map_or("-".to_string(), |v| format!("{v:.2}"))
+}
+
+fn bytes(usage: &SpaceUsage, n: usize) -> String {
+    format!("{n} ({:.1}%)", usage.percent(n))
+}
+
+/// 报告中一行的取值方法
+type Metric = fn(&SpaceUsage) -> String;
+
+/// 报告中的每一行：名字和取值方法
+const ROWS: &[(&str, Metric)] = &[
+    ("Type", |u| {
+        if u.is_index { "index" } else { "table" }.to_string()
+    }),
+    ("Pages", |u| u.pages().to_string()),
+    ("Leaf pages", |u| u.leaf_pages.to_string()),
+    ("Interior pages", |u| u.interior_pages.to_string()),
+    ("Overflow pages", |u| u.overflow_pages.to_string()),
+    ("B-Tree depth", |u| u.depth.to_string()),
+    ("Average fan-out", |u| number(u.fan_out())),
+    ("Entries", |u| u.entries.to_string()),
+    ("Bytes of storage", |u| u.storage().to_string()),
+    ("Bytes of payload", |u| bytes(u, u.payload)),
+    ("Bytes of overhead", |u| bytes(u, u.overhead)),
+    ("Bytes unused", |u| bytes(u, u.unused)),
+    ("Fragmented bytes", |u| u.fragmented_bytes.to_string()),
+    ("Average payload per entry", |u| number(u.avg_payload())),
+    ("Average unused per entry", |u| number(u.avg_unused())),
+    ("Average cell size", |u| number(u.avg_cell_size())),
+    ("Maximum payload", |u| u.max_payload.to_string()),
+    ("Entries using overflow", |u| u.overflow_cells.to_string()),
+    ("Fragmentation", |u| {
+        u.fragmentation()
+            .map_or("-".to_string(), |f| format!("{f:.1}%"))
+    }),
+];
+
+/// 空间使用报告：先是整个数据库的汇总，然后每张表和每个索引各占一列
+pub fn AnalysisReport() -> Element {
+    let viewer = use_context::<AppState>().viewer;
+    let analysis = viewer.read().analysis();
+    let mut columns = vec![
+        analysis.all.clone(),
+        analysis.tables.clone(),
+        analysis.indexes.clone(),
+    ];
+    columns.extend(analysis.trees.iter().cloned());
+
+    rsx! {
+        div {
+            class: "p-4 overflow-auto text-xs",
+            table {
+                class: "table table-xs table-pin-cols",
+                thead {
+                    tr {
+                        th {}
+                        for usage in columns.iter() {
+                            td {
+                                class: "font-bold",
+                                "{usage.name}"
+                            }
+                        }
+                    }
+                }
+                tbody {
+                    for (name, value) in ROWS.iter() {
+                        tr {
+                            th {
+                                class: "whitespace-nowrap",
+                                "{name}"
+                            }
+                            for usage in columns.iter() {
+                                td {
+                                    class: "whitespace-nowrap",
+                                    "{value(usage)}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,701 @@
+use crate::parser::{record::serial_type_size, schema::ColumnInfo, PageType};
+
+use super::i18n::{catalog, Text};
+
+// 数据库头
+
+catalog! {
+    db_header() {
+        en: "The first 100 bytes of the database file comprise the database file header. All multibyte fields in the database file header are stored with the most significant byte first (big-endian).",
+        zh: "数据库文件的前 100 字节是数据库头。数据库头中的多字节字段都按最高有效字节在前（大端）保存。",
+    }
+    magic() {
+        en: "Magic header string, which corresponds to the UTF-8 string: 'SQLite format 3\\000'. Every valid SQLite database file begins with these 16 bytes (in hex): 53 51 4c 69 74 65 20 66 6f 72 6d 61 74 20 33 00.",
+        zh: "魔数字符串，即 UTF-8 字符串 'SQLite format 3\\000'。每个有效的 SQLite 数据库文件都以这 16 个字节开头（十六进制）：53 51 4c 69 74 65 20 66 6f 72 6d 61 74 20 33 00。",
+    }
+    page_size() {
+        en: "Page size of the database, interpreted as a big-endian integer and must be a power of two between 512 and 32768, inclusive. Starting from version 3.7.1 page size of 65536 bytes is supported, but since it won't fit in a two-byte integer, big-endian magic number 1 is used to represent it: 0x00 0x01.",
+        zh: "数据库的页大小，按大端整数解释，必须是 512 到 32768（含）之间的 2 的幂。从 3.7.1 版开始支持 65536 字节的页，但它放不进两个字节，所以用大端的 1 表示：0x00 0x01。",
+    }
+    write_version() {
+        en: "File format write version: 1 for legacy rollback journal, 2 for WAL.",
+        zh: "文件格式的写版本：1 表示传统的回滚日志，2 表示 WAL。",
+    }
+    read_version() {
+        en: "File format read version: 1 for legacy rollback journal, 2 for WAL.",
+        zh: "文件格式的读版本：1 表示传统的回滚日志，2 表示 WAL。",
+    }
+    reserved_space() {
+        en: "Bytes of unused \"reserved\" space at the end of each page, usually 0. Extensions such as encryption use these bytes; they never hold database content.",
+        zh: "每页末尾保留的字节数，通常为 0。加密等扩展会使用这些字节，它们不会用于保存数据库内容。",
+    }
+    max_payload_fraction() {
+        en: "Maximum embedded payload fraction: the largest share of a b-tree page a single cell may use. Must be 64.",
+        zh: "最大嵌入负载比例：一个单元最多可以占用 B-Tree 页的比例。必须为 64。",
+    }
+    min_payload_fraction() {
+        en: "Minimum embedded payload fraction: the payload an index or interior cell keeps locally before spilling to overflow pages. Must be 32.",
+        zh: "最小嵌入负载比例：索引单元或内部单元溢出到溢出页之前至少保存在本页的负载。必须为 32。",
+    }
+    leaf_payload_fraction() {
+        en: "Leaf payload fraction: the payload a table leaf cell keeps locally before spilling to overflow pages. Must be 32.",
+        zh: "叶子负载比例：表叶子单元溢出到溢出页之前至少保存在本页的负载。必须为 32。",
+    }
+    file_change_counter() {
+        en: "File change counter, incremented whenever the database file is changed in rollback mode, so that other processes know to flush their page cache. In WAL mode changes are detected through the wal-index instead.",
+        zh: "文件修改计数器。回滚日志模式下每次修改数据库文件时递增，其它进程据此知道要清空页缓存。WAL 模式下改为通过 wal-index 检测修改。",
+    }
+    db_size() {
+        en: "Size of the database file in pages, the \"in-header database size\". Only valid when the version-valid-for number matches the change counter.",
+        zh: "数据库文件的页数，即\"头中的数据库大小\"。只有 version-valid-for 与修改计数器相同时才有效。",
+    }
+    first_freelist_trunk() {
+        en: "Page number of the first freelist trunk page, or zero if the freelist is empty.",
+        zh: "第一个空闲列表主干页的页号，空闲列表为空时为 0。",
+    }
+    freelist_count() {
+        en: "Total number of freelist pages, trunk and leaf pages together.",
+        zh: "空闲列表中的总页数，包括主干页和叶子页。",
+    }
+    schema_cookie() {
+        en: "The schema cookie, incremented whenever the schema changes. Prepared statements check it to know when they must be recompiled.",
+        zh: "模式 cookie，每次模式改变时递增。预编译的语句据此判断是否需要重新编译。",
+    }
+    schema_format() {
+        en: "Schema format number. Supported values are 1, 2, 3 and 4; 4 is used by default since SQLite 3.3.0.",
+        zh: "模式格式编号。支持的值为 1、2、3 和 4，SQLite 3.3.0 起默认使用 4。",
+    }
+    default_cache_size() {
+        en: "Default page cache size suggested by PRAGMA default_cache_size.",
+        zh: "PRAGMA default_cache_size 建议的默认页缓存大小。",
+    }
+    largest_root_page() {
+        en: "Page number of the largest root b-tree page when in auto-vacuum or incremental-vacuum mode, or zero otherwise.",
+        zh: "自动清理（auto-vacuum）或增量清理（incremental-vacuum）模式下最大的根 B-Tree 页的页号，否则为 0。",
+    }
+    text_encoding() {
+        en: "Database text encoding: 1 means UTF-8, 2 means UTF-16le, 3 means UTF-16be.",
+        zh: "数据库的文本编码：1 表示 UTF-8，2 表示 UTF-16le，3 表示 UTF-16be。",
+    }
+    user_version() {
+        en: "User version, read and set by PRAGMA user_version and not used by SQLite itself.",
+        zh: "用户版本，由 PRAGMA user_version 读写，SQLite 本身不使用。",
+    }
+    incremental_vacuum() {
+        en: "Incremental-vacuum mode flag: non-zero for incremental vacuum, zero otherwise.",
+        zh: "增量清理模式标志：非 0 表示增量清理，0 表示不是。",
+    }
+    application_id() {
+        en: "Application ID set by PRAGMA application_id, identifying the application that owns the file.",
+        zh: "由 PRAGMA application_id 设置的应用程序 ID，标识文件属于哪个应用程序。",
+    }
+    expansion_reserved() {
+        en: "Reserved for expansion, must be zero.",
+        zh: "为将来扩展保留，必须为 0。",
+    }
+    version_valid_for() {
+        en: "The value of the change counter when the SQLite version number was stored, telling whether the in-header database size is valid.",
+        zh: "写入 SQLite 版本号时修改计数器的值，用来判断头中的数据库大小是否有效。",
+    }
+    sqlite_version() {
+        en: "Version number of the SQLite library that most recently modified the file, for example 3035005 for version 3.35.5.",
+        zh: "最近一次修改文件的 SQLite 库的版本号，例如 3.35.5 版表示为 3035005。",
+    }
+}
+
+// B-Tree 页
+
+/// 页类型的名字，用在句子中间
+pub fn page_type(page_type: PageType) -> Text {
+    match page_type {
+        PageType::InteriorIndex => Text::new("interior index", "索引内部"),
+        PageType::InteriorTable => Text::new("interior table", "表内部"),
+        PageType::LeafIndex => Text::new("leaf index", "索引叶子"),
+        PageType::LeafTable => Text::new("leaf table", "表叶子"),
+    }
+}
+
+/// schema 中对象的种类
+fn object_kind(kind: &str) -> Text {
+    match kind {
+        "table" => Text::new("table", "表"),
+        "index" => Text::new("index", "索引"),
+        other => Text::new(other, other),
+    }
+}
+
+/// B-Tree 页说明的开头，owner 是页所属的 (种类, 名字)
+pub fn btree_page(page_type: PageType, owner: Option<(&str, &str)>) -> Text {
+    let t = self::page_type(page_type);
+    match owner {
+        Some((kind, name)) => {
+            let kind = object_kind(kind);
+            Text::new(
+                format!("A {} b-tree page of {} `{name}`. ", t.en, kind.en),
+                format!("{}`{name}` 的一个{} B-Tree 页。", kind.zh, t.zh),
+            )
+        }
+        None => Text::new(
+            format!("A {} b-tree page. ", t.en),
+            format!("一个{} B-Tree 页。", t.zh),
+        ),
+    }
+}
+
+catalog! {
+    leaf_table_page() {
+        en: "Table leaf pages hold the rows: every cell stores a rowid and a record with the column values.",
+        zh: "表叶子页保存表的行：每个单元保存一个 rowid 和一个包含各列值的 record。",
+    }
+    interior_table_page() {
+        en: "Interior table pages only hold keys: every cell stores a left child page number and the largest rowid in that subtree.",
+        zh: "表内部页只保存键：每个单元保存左子页的页号和那棵子树中最大的 rowid。",
+    }
+    leaf_index_page() {
+        en: "Index leaf pages hold index records: the indexed columns followed by the key of the table row.",
+        zh: "索引叶子页保存索引 record：被索引的列，后面是表中那一行的键。",
+    }
+    interior_index_page() {
+        en: "Interior index pages hold a left child page number and an index record separating the subtrees.",
+        zh: "索引内部页保存左子页的页号和分隔子树的索引 record。",
+    }
+    page_layout() {
+        en: " The page header is followed by the cell pointer array; cells are stored in the cell content area at the end of the page.",
+        zh: "页头之后是单元指针数组，单元保存在页末尾的单元内容区中。",
+    }
+    record_columns(columns: Text) {
+        en: " Record columns: {columns}.",
+        zh: "Record 的列：{columns}。",
+    }
+    without_rowid_page(page_type: Text, name: &str) {
+        en: "A {page_type} b-tree page of the WITHOUT ROWID table `{name}`. A WITHOUT ROWID table has no rowid: it is stored as an index b-tree keyed on its PRIMARY KEY, so every cell holds a complete table row instead of an index entry.",
+        zh: "WITHOUT ROWID 表 `{name}` 的一个{page_type} B-Tree 页。WITHOUT ROWID 表没有 rowid：它保存为以 PRIMARY KEY 为键的索引 B-Tree，所以每个单元保存完整的一行，而不是索引条目。",
+    }
+    without_rowid_interior() {
+        en: " On interior pages the row also serves as the key separating the subtrees.",
+        zh: "在内部页中，这一行同时也是分隔子树的键。",
+    }
+    without_rowid_order(primary_key: &str, physical: &str, declared: &str) {
+        en: " The record stores the primary key columns ({primary_key}) first, followed by the remaining columns in declared order: the physical order is ({physical}) while the declared order is ({declared}).",
+        zh: "Record 先保存主键列（{primary_key}），然后按声明顺序保存其余的列：物理顺序是（{physical}），声明顺序是（{declared}）。",
+    }
+    overflow_page() {
+        en: "An overflow page. When a payload is too large to fit on a b-tree page, the rest of it is stored in a linked list of overflow pages. The first 4 bytes hold the page number of the next overflow page (0 for the last one), the remaining bytes hold payload content.",
+        zh: "溢出页。负载太大、放不进 B-Tree 页时，剩下的部分保存在溢出页组成的链表中。前 4 个字节是下一个溢出页的页号（最后一页为 0），其余字节是负载内容。",
+    }
+    freelist_trunk_page() {
+        en: "A freelist trunk page. Unused pages are tracked by the freelist: each trunk page holds the number of the next trunk page, the number of leaf pointers that follow, and the page numbers of freelist leaf pages.",
+        zh: "空闲列表主干页。空闲列表记录没有使用的页：每个主干页保存下一个主干页的页号、之后的叶子指针数和空闲列表叶子页的页号。",
+    }
+    freelist_leaf_page() {
+        en: "A freelist leaf page. It is not in use and its content is meaningless; it will be reused the next time a page is needed.",
+        zh: "空闲列表叶子页。它没有被使用，内容没有意义，下次需要新页时会被重新使用。",
+    }
+    unknown_page() {
+        en: "A page that is not referenced by any b-tree, overflow chain or the freelist, such as the lock-byte page or a pointer map page.",
+        zh: "没有被任何 B-Tree、溢出链或空闲列表引用的页，例如 lock-byte 页或 pointer map 页。",
+    }
+}
+
+/// record 中一列的说明，例如 a INTEGER COLLATE NOCASE DESC
+pub fn column_summary(column: &ColumnInfo) -> Text {
+    let mut s = format!("{} {}", column.name, column.affinity);
+    if let Some(collation) = &column.collation {
+        s += &format!(" COLLATE {collation}");
+    }
+    if column.desc {
+        s += " DESC";
+    }
+    let mut text = Text::new(s.clone(), s);
+    if column.rowid_alias {
+        text += Text::new(" (alias of rowid)", "（rowid 的别名）");
+    }
+    text
+}
+
+/// 按列名和亲和性描述 record 的第 j 列，没有 schema 信息时按位置描述
+pub fn column_label(columns: &[ColumnInfo], j: usize) -> Text {
+    match columns.get(j) {
+        Some(c) => Text::new(
+            format!("column `{}` ({} affinity)", c.name, c.affinity),
+            format!("列 `{}`（{} 亲和性）", c.name, c.affinity),
+        ),
+        None => Text::new(format!("column {j}"), format!("第 {j} 列")),
+    }
+}
+
+/// 串行类型表示的值的类型和大小
+pub fn serial_type(serial_type: i64) -> Text {
+    let size = serial_type_size(serial_type);
+    match serial_type {
+        0 => Text::new("NULL", "NULL"),
+        1..=6 => Text::new(
+            format!("{size}-byte big-endian signed integer"),
+            format!("{size} 字节的大端有符号整数"),
+        ),
+        7 => Text::new(
+            "IEEE 754-2008 64-bit floating point number",
+            "IEEE 754-2008 64 位浮点数",
+        ),
+        8 => Text::new("integer 0", "整数 0"),
+        9 => Text::new("integer 1", "整数 1"),
+        10 | 11 => Text::new("reserved for internal use", "保留给内部使用"),
+        n if n % 2 == 0 => Text::new(
+            format!("BLOB of {size} bytes"),
+            format!("{size} 字节的 BLOB"),
+        ),
+        _ => Text::new(
+            format!("TEXT of {size} bytes"),
+            format!("{size} 字节的 TEXT"),
+        ),
+    }
+}
+
+// 页头、单元和 record 的字段
+
+catalog! {
+    page_type_field() {
+        en: "B-Tree page type: 2 for an interior index page, 5 for an interior table page, 10 for a leaf index page, 13 for a leaf table page.",
+        zh: "B-Tree 页的类型：2 为索引内部页，5 为表内部页，10 为索引叶子页，13 为表叶子页。",
+    }
+    first_freeblock() {
+        en: "Start of the first freeblock on the page, or zero if there are no freeblocks.",
+        zh: "页中第一个空闲块的起始偏移，没有空闲块时为 0。",
+    }
+    cell_count() {
+        en: "Number of cells on the page.",
+        zh: "页中的单元数。",
+    }
+    cell_content_start() {
+        en: "Start of the cell content area. A zero value is interpreted as 65536.",
+        zh: "单元内容区的起始偏移。0 表示 65536。",
+    }
+    fragmented_free_bytes() {
+        en: "Number of fragmented free bytes within the cell content area.",
+        zh: "单元内容区中碎片空闲字节的数量。",
+    }
+    right_most_pointer() {
+        en: "Right-most pointer: page number of the child holding all keys larger than those of the last cell.",
+        zh: "最右指针：保存所有比最后一个单元更大的键的子页的页号。",
+    }
+    cell_pointer(i: usize) {
+        en: "Cell pointer {i}: offset of cell {i} from the start of the page.",
+        zh: "单元指针 {i}：单元 {i} 相对页起始的偏移。",
+    }
+    left_child(i: usize) {
+        en: "Cell {i}: page number of the left child. Every key in that subtree is less than or equal to the key of this cell.",
+        zh: "单元 {i}：左子页的页号。那棵子树中的每个键都小于或等于这个单元的键。",
+    }
+    payload_size(i: usize) {
+        en: "Cell {i}: total number of bytes of payload, including any overflow.",
+        zh: "单元 {i}：负载的总字节数，包括溢出的部分。",
+    }
+    rowid(i: usize) {
+        en: "Cell {i}: rowid, the integer key of the table row.",
+        zh: "单元 {i}：rowid，表中这一行的整数键。",
+    }
+    invalid_payload(i: usize) {
+        en: "Cell {i}: payload, which is not a valid record.",
+        zh: "单元 {i}：负载，它不是有效的 record。",
+    }
+    first_overflow(i: usize) {
+        en: "Cell {i}: page number of the first overflow page holding the rest of the payload.",
+        zh: "单元 {i}：保存负载剩余部分的第一个溢出页的页号。",
+    }
+    record_header_size(i: usize) {
+        en: "Cell {i}: size of the record header in bytes, including this varint.",
+        zh: "单元 {i}：record 头的字节数，包括这个 varint 本身。",
+    }
+    declared_row(row: &str) {
+        en: " The row in declared column order: {row}.",
+        zh: "按声明的列顺序，这一行是：{row}。",
+    }
+    serial_type_field(i: usize, column: Text, serial_type: Text) {
+        en: "Cell {i}: serial type of {column}: {serial_type}.",
+        zh: "单元 {i}：{column}的串行类型：{serial_type}。",
+    }
+    column_value(i: usize, column: Text) {
+        en: "Cell {i}: value of {column}.",
+        zh: "单元 {i}：{column}的值。",
+    }
+    declared_column(n: usize) {
+        en: " It is declared as column {n} of the table",
+        zh: "它在表中声明为第 {n} 列，",
+    }
+    primary_key_first() {
+        en: ", and stored first because it is part of the PRIMARY KEY.",
+        zh: "因为属于 PRIMARY KEY 而保存在最前面。",
+    }
+    after_primary_key() {
+        en: ", and stored after the PRIMARY KEY columns.",
+        zh: "保存在 PRIMARY KEY 的列之后。",
+    }
+    rowid_alias(rowid: i64) {
+        en: " This INTEGER PRIMARY KEY column is an alias of the rowid, so it is stored as NULL and its value is {rowid}.",
+        zh: "这个 INTEGER PRIMARY KEY 列是 rowid 的别名，所以保存为 NULL，它的值是 {rowid}。",
+    }
+    overflow_next() {
+        en: "Page number of the next overflow page in the chain, or zero for the last page.",
+        zh: "链中下一个溢出页的页号，最后一页为 0。",
+    }
+    overflow_content() {
+        en: "Payload content continued from the previous page of the chain.",
+        zh: "接着链中上一页的负载内容。",
+    }
+    trunk_next() {
+        en: "Page number of the next freelist trunk page, or zero for the last trunk page.",
+        zh: "下一个空闲列表主干页的页号，最后一个主干页为 0。",
+    }
+    trunk_leaf_count() {
+        en: "Number of leaf page pointers stored on this trunk page.",
+        zh: "这个主干页中保存的叶子页指针数。",
+    }
+    trunk_leaf(i: usize) {
+        en: "Page number of freelist leaf {i}.",
+        zh: "空闲列表叶子页 {i} 的页号。",
+    }
+}
+
+// 侧栏中的子部分
+
+catalog! {
+    cell(i: usize, page: u32) {
+        en: "Cell {i} of page {page}. ",
+        zh: "第 {page} 页的单元 {i}。",
+    }
+    leaf_table_cell() {
+        en: "It holds the rowid and the record of one table row.",
+        zh: "它保存表中一行的 rowid 和 record。",
+    }
+    interior_table_cell() {
+        en: "It holds a left child page number and the largest rowid in that subtree.",
+        zh: "它保存左子页的页号和那棵子树中最大的 rowid。",
+    }
+    leaf_index_cell() {
+        en: "It holds one index record.",
+        zh: "它保存一条索引 record。",
+    }
+    interior_index_cell() {
+        en: "It holds a left child page number and an index record separating the subtrees.",
+        zh: "它保存左子页的页号和分隔子树的索引 record。",
+    }
+    cell_overflows() {
+        en: " The payload doesn't fit on the page: the rest of it is stored in a chain of overflow pages.",
+        zh: "负载放不进这一页，剩下的部分保存在溢出页链中。",
+    }
+    record(cell: usize) {
+        en: "The record in the payload of cell {cell}: a header with its own size and the serial type of every column, followed by the column values.",
+        zh: "单元 {cell} 的负载中的 record：头中是头的大小和每一列的串行类型，后面是各列的值。",
+    }
+    page_range(first: usize, last: usize) {
+        en: "Pages {first} to {last} of the database. Pages are grouped by number so that a large file stays navigable; expand the group to list them.",
+        zh: "数据库的第 {first} 到 {last} 页。页按页号分组，大文件也能方便地浏览；展开分组列出其中的页。",
+    }
+}
+
+// 空间分析和问题列表
+
+catalog! {
+    analysis(total: usize, page_size: usize, btree: usize, freelist: usize, other: usize) {
+        en: "Space usage of every table and index, computed the same way as sqlite3_analyzer. \
+            The database has {total} page(s) of {page_size} bytes: {btree} in tables and indexes, {freelist} on the freelist and {other} not used by anything. \
+            Payload is the content of the rows and index entries (including the part stored on overflow pages), \
+            overhead is the space taken by page headers, cell pointers, cell headers and overflow pointers, \
+            and unused is the free space inside pages. \
+            Fragmentation is the percentage of pages that do not directly follow the previous page when the b-tree is walked in order.",
+        zh: "每个表和索引的空间占用，计算方法与 sqlite3_analyzer 相同。\
+            数据库有 {total} 页，每页 {page_size} 字节：{btree} 页属于表和索引，{freelist} 页在空闲列表中，{other} 页没有被使用。\
+            负载（Payload）是行和索引条目的内容（包括保存在溢出页中的部分），\
+            开销（Overhead）是页头、单元指针、单元头和溢出指针占用的空间，\
+            未使用（Unused）是页内的空闲空间。\
+            碎片率（Fragmentation）是按顺序遍历 B-Tree 时，不紧跟在上一页之后的页所占的百分比。",
+    }
+    no_problems() {
+        en: "No problems were found while parsing the database.",
+        zh: "解析数据库时没有发现问题。",
+    }
+    problems(errors: usize, warnings: usize, notes: usize) {
+        en: "Problems found while parsing the database: {errors} error(s), {warnings} warning(s) and {notes} note(s). \
+            Parsing continues past broken structures, so the rest of the file can still be explored. \
+            Click a problem to jump to the page it was found on.",
+        zh: "解析数据库时发现的问题：{errors} 个错误、{warnings} 个警告和 {notes} 条提示。\
+            遇到损坏的结构时解析会跳过它继续进行，所以文件的其余部分仍然可以浏览。\
+            点击一个问题跳到发现它的页。",
+    }
+}
+
+// WAL 文件
+
+catalog! {
+    wal_header() {
+        en: "The write-ahead log starts with a 32-byte header. Instead of changing the database file, transactions append frames holding new page content to the WAL; a checkpoint later copies them back. ",
+        zh: "预写日志（WAL）以 32 字节的头开始。事务不直接修改数据库文件，而是把保存新页内容的帧追加到 WAL 中，之后由检查点把它们写回数据库。",
+    }
+    wal_checksum_valid() {
+        en: "The checksum is valid.",
+        zh: "校验和正确。",
+    }
+    wal_checksum_invalid() {
+        en: "The checksum doesn't match, so SQLite ignores the whole WAL file.",
+        zh: "校验和不匹配，SQLite 会忽略整个 WAL 文件。",
+    }
+    wal_magic() {
+        en: "Magic number, 0x377f0682 or 0x377f0683. The least significant bit tells whether checksums are computed on big-endian (1) or little-endian (0) integers.",
+        zh: "魔数，0x377f0682 或 0x377f0683。最低位表示校验和按大端（1）还是小端（0）整数计算。",
+    }
+    wal_version() {
+        en: "File format version, currently 3007000.",
+        zh: "文件格式版本，目前为 3007000。",
+    }
+    wal_page_size() {
+        en: "Database page size.",
+        zh: "数据库的页大小。",
+    }
+    checkpoint_seq() {
+        en: "Checkpoint sequence number, incremented every time the WAL is reset after a checkpoint.",
+        zh: "检查点序号，每次检查点之后重置 WAL 时递增。",
+    }
+    wal_salt1() {
+        en: "Salt-1, a random integer incremented with each checkpoint. Frames are only valid when they carry the same salts.",
+        zh: "Salt-1，每次检查点时递增的随机整数。只有带着相同 salt 的帧才有效。",
+    }
+    wal_salt2() {
+        en: "Salt-2, a different random number for each checkpoint.",
+        zh: "Salt-2，每次检查点都不同的随机数。",
+    }
+    wal_header_checksum1() {
+        en: "Checksum-1, the first part of the checksum of the first 24 bytes.",
+        zh: "Checksum-1，前 24 字节的校验和的第一部分。",
+    }
+    wal_header_checksum2() {
+        en: "Checksum-2, the second part of the checksum of the first 24 bytes.",
+        zh: "Checksum-2，前 24 字节的校验和的第二部分。",
+    }
+    wal_frame(page: u32) {
+        en: "A WAL frame: a 24-byte frame header followed by the new content of page {page}. ",
+        zh: "WAL 帧：24 字节的帧头，后面是第 {page} 页的新内容。",
+    }
+    frame_committed() {
+        en: "It belongs to a committed transaction, so readers see this version of the page unless a later frame replaces it.",
+        zh: "它属于已提交的事务，除非之后的帧替换了它，读者看到的是这一版本的页。",
+    }
+    frame_uncommitted() {
+        en: "No commit frame follows it, so the transaction was never committed and SQLite ignores it.",
+        zh: "它之后没有提交帧，事务从未提交，SQLite 会忽略它。",
+    }
+    frame_invalid() {
+        en: "Its salts or checksum don't match, so SQLite ignores it and every frame after it.",
+        zh: "它的 salt 或校验和不匹配，SQLite 会忽略它和它之后的所有帧。",
+    }
+    frame_page_number() {
+        en: "Page number of the page stored in this frame.",
+        zh: "这一帧中保存的页的页号。",
+    }
+    frame_db_size() {
+        en: "For commit frames, the size of the database in pages after the commit. For all other frames, zero.",
+        zh: "对于提交帧，是提交后数据库的页数；其它帧为 0。",
+    }
+    frame_salt1() {
+        en: "Salt-1 copied from the WAL header.",
+        zh: "从 WAL 头复制的 Salt-1。",
+    }
+    frame_salt2() {
+        en: "Salt-2 copied from the WAL header.",
+        zh: "从 WAL 头复制的 Salt-2。",
+    }
+    frame_checksum1() {
+        en: "Checksum-1, the cumulative checksum of the WAL header and all frames up to and including this one.",
+        zh: "Checksum-1，WAL 头和直到这一帧（含）的所有帧的累积校验和。",
+    }
+    frame_checksum2() {
+        en: "Checksum-2, the second part of the cumulative checksum.",
+        zh: "Checksum-2，累积校验和的第二部分。",
+    }
+    frame_content() {
+        en: "The new content of the page.",
+        zh: "页的新内容。",
+    }
+}
+
+// -shm 文件中的 wal-index
+
+catalog! {
+    wal_index(order: Text) {
+        en: "The shared-memory file holds the wal-index, which lets readers find the latest frame for a page without scanning the WAL. It starts with two copies of a 48-byte header, followed by checkpoint information and hash tables. Integers are stored in the byte order of the machine that wrote it, {order} here; the Hex column shows them big-endian. ",
+        zh: "共享内存文件保存 wal-index，读者不必扫描 WAL 就能找到一页最新的帧。它以 48 字节的头的两份拷贝开始，后面是检查点信息和哈希表。整数按写入它的机器的字节序保存，这里是{order}；Hex 列按大端显示它们。",
+    }
+    index_consistent() {
+        en: "Both copies agree.",
+        zh: "两份拷贝一致。",
+    }
+    index_inconsistent() {
+        en: "The two copies differ: a writer was updating the index, or it is damaged.",
+        zh: "两份拷贝不同：写入者正在更新索引，或者它已经损坏。",
+    }
+    index_version() {
+        en: "The wal-index format version, 3007000.",
+        zh: "wal-index 格式的版本，3007000。",
+    }
+    index_change() {
+        en: "Counter incremented on every transaction.",
+        zh: "每个事务递增的计数器。",
+    }
+    index_is_init() {
+        en: "1 when the header has been initialized.",
+        zh: "头已经初始化时为 1。",
+    }
+    index_big_end_checksum() {
+        en: "1 when the WAL checksums are big-endian.",
+        zh: "WAL 的校验和按大端计算时为 1。",
+    }
+    index_page_size() {
+        en: "Database page size, with 65536 stored as 1.",
+        zh: "数据库的页大小，65536 保存为 1。",
+    }
+    index_max_frame() {
+        en: "Index of the last valid commit frame in the WAL.",
+        zh: "WAL 中最后一个有效提交帧的序号。",
+    }
+    index_db_size() {
+        en: "Size of the database in pages.",
+        zh: "数据库的页数。",
+    }
+    index_frame_checksum1() {
+        en: "Checksum-1 of the last frame in the WAL.",
+        zh: "WAL 中最后一帧的 Checksum-1。",
+    }
+    index_frame_checksum2() {
+        en: "Checksum-2 of the last frame in the WAL.",
+        zh: "WAL 中最后一帧的 Checksum-2。",
+    }
+    index_checksum1() {
+        en: "Checksum-1 of the first 40 bytes of this header.",
+        zh: "这份头前 40 字节的 Checksum-1。",
+    }
+    index_checksum2() {
+        en: "Checksum-2 of the first 40 bytes of this header.",
+        zh: "这份头前 40 字节的 Checksum-2。",
+    }
+    backfill() {
+        en: "Number of WAL frames that have been copied back into the database.",
+        zh: "已经写回数据库的 WAL 帧数。",
+    }
+    read_mark(i: usize) {
+        en: "Read mark {i}: the last frame visible to readers holding this lock, 0xffffffff when unused.",
+        zh: "读标记 {i}：持有这把锁的读者能看到的最后一帧，没有使用时为 0xffffffff。",
+    }
+    lock_bytes() {
+        en: "Bytes used for file locks, their content is meaningless.",
+        zh: "用于文件锁的字节，内容没有意义。",
+    }
+    backfill_attempted() {
+        en: "Number of frames a checkpoint has attempted to copy back.",
+        zh: "检查点尝试写回的帧数。",
+    }
+}
+
+/// wal-index 中整数的字节序
+pub fn byte_order(big_endian: bool) -> Text {
+    if big_endian {
+        Text::new("big-endian", "大端")
+    } else {
+        Text::new("little-endian", "小端")
+    }
+}
+
+// 回滚日志
+
+catalog! {
+    journal_header() {
+        en: "A rollback journal header. Before changing a page, SQLite copies its original content to the journal; if the transaction doesn't finish, the next connection plays the journal back to restore the database. A new header starts each segment and fills a whole sector.",
+        zh: "回滚日志头。修改一页之前，SQLite 先把它的原始内容复制到日志中；如果事务没有完成，下一个连接会回放日志来恢复数据库。每一段以新的头开始，头占满一个扇区。",
+    }
+    journal_magic() {
+        en: "Magic string 0xd9d505f920a163d7. It is zeroed when the transaction commits.",
+        zh: "魔数 0xd9d505f920a163d7。事务提交时被清零。",
+    }
+    journal_page_count() {
+        en: "Number of page records in this segment, or 0xffffffff to read records up to the end of the file.",
+        zh: "这一段中的页记录数，0xffffffff 表示一直读到文件末尾。",
+    }
+    journal_nonce() {
+        en: "Random nonce used as the initial value of record checksums.",
+        zh: "作为记录校验和初值的随机数。",
+    }
+    journal_initial_size() {
+        en: "Size of the database in pages before the transaction. A rollback truncates the file to this size.",
+        zh: "事务开始前数据库的页数。回滚时把文件截断到这个大小。",
+    }
+    journal_sector_size() {
+        en: "Sector size of the disk; each header fills one sector.",
+        zh: "磁盘的扇区大小，每个头占满一个扇区。",
+    }
+    journal_page_size() {
+        en: "Database page size.",
+        zh: "数据库的页大小。",
+    }
+    journal_record(page: u32) {
+        en: "The original content of page {page} before the transaction changed it. A record with a bad checksum ends the playback of the journal.",
+        zh: "事务修改之前第 {page} 页的原始内容。校验和错误的记录会结束日志的回放。",
+    }
+    record_page_number() {
+        en: "Page number of the saved page.",
+        zh: "保存的页的页号。",
+    }
+    record_content() {
+        en: "Original content of the page.",
+        zh: "页的原始内容。",
+    }
+    record_checksum() {
+        en: "Checksum: the nonce plus every 200th byte of the page, counting back from the end.",
+        zh: "校验和：nonce 加上页中从末尾往前每隔 200 字节取的一个字节。",
+    }
+}
+
+// WAL 帧或日志记录中的页
+
+catalog! {
+    wal_page_image(page: u32) {
+        en: "A copy of page {page}: the new version of the page, which replaces it in the database at the next checkpoint. A b-tree page starts with its page header, after the database header on page 1.",
+        zh: "第 {page} 页的副本：页的新版本，下次检查点时替换数据库中的这一页。B-Tree 页以页头开始，第 1 页的页头在数据库头之后。",
+    }
+    journal_page_image(page: u32) {
+        en: "A copy of page {page}: the version of the page before the transaction, which a rollback writes back. A b-tree page starts with its page header, after the database header on page 1.",
+        zh: "第 {page} 页的副本：事务之前的版本，回滚时写回数据库。B-Tree 页以页头开始，第 1 页的页头在数据库头之后。",
+    }
+    page_content() {
+        en: "The content of the page.",
+        zh: "页的内容。",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::ui::{viewer::Viewer, Parts};
+
+    /// 中文说明中至少有一个汉字
+    fn translated(text: &Text) -> bool {
+        !text.en.is_empty()
+            && text
+                .zh
+                .chars()
+                .any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+    }
+
+    #[test]
+    fn every_description_has_both_languages() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        let mut parts: Vec<Rc<dyn Parts>> = viewer.tree.clone();
+        while let Some(part) = parts.pop() {
+            assert!(translated(&part.desc()), "{}", part.label());
+            for field in part.fields().iter() {
+                assert!(translated(&field.desc), "{}", field.desc.en);
+            }
+            parts.extend(part.children());
+        }
+        assert_eq!(serial_type(13).zh.as_ref(), "0 字节的 TEXT");
+    }
+}
@@ -0,0 +1,475 @@
+use std::rc::Rc;
+
+use crate::parser::{
+    journal::{Journal, JournalHeader, JournalRecord},
+    page::PageHeader,
+    shm::{WalIndex, WalIndexHeader},
+    Bytes, Wal, WalFrame, WalHeader,
+};
+
+use super::{
+    catalog,
+    i18n::Text,
+    page::page_header_fields,
+    session::{FileKind, Session},
+    viewer::CachedPart,
+    Field, Parts, Value,
+};
+
+/// 与数据库一起打开的伴随文件，在侧边栏中与主文件分组显示。
+/// 字段的偏移是在伴随文件中的偏移。
+#[derive(Debug)]
+pub struct CompanionFile {
+    pub name: String,
+    pub kind: FileKind,
+    pub parts: Vec<Rc<dyn Parts>>,
+    /// 文件无法解析或没有有效内容时的说明
+    pub note: Option<String>,
+}
+
+impl CompanionFile {
+    fn new(kind: FileKind, name: String, bytes: &Rc<[u8]>) -> Self {
+        let parsed = match kind {
+            FileKind::Wal => Wal::parse(bytes.clone()).map(|wal| {
+                let note = wal
+                    .db_size()
+                    .is_none()
+                    .then(|| "No committed transactions".to_string());
+                let mut parts = vec![wal.header.clone() as Rc<dyn Parts>];
+                parts.extend(wal.frames.iter().map(|f| f.clone() as Rc<dyn Parts>));
+                (parts, note)
+            }),
+            FileKind::Shm => {
+                WalIndex::parse(bytes).map(|index| (vec![Rc::new(index) as Rc<dyn Parts>], None))
+            }
+            FileKind::Journal => Journal::parse(bytes.clone()).map(|journal| {
+                let note = (!journal.is_hot())
+                    .then(|| "The header is zeroed, nothing to roll back".to_string());
+                let mut parts: Vec<Rc<dyn Parts>> = vec![];
+                for header in &journal.headers {
+                    parts.push(header.clone());
+                    parts.extend(
+                        journal
+                            .records
+                            .iter()
+                            .filter(|r| r.segment == header.index)
+                            .map(|r| r.clone() as Rc<dyn Parts>),
+                    );
+                }
+                (parts, note)
+            }),
+            FileKind::Main => unreachable!("the main file is not a companion"),
+        };
+        let (parts, note) = parsed.unwrap_or_else(|e| (vec![], Some(e.to_string())));
+        Self {
+            name,
+            kind,
+            parts: parts
+                .into_iter()
+                .map(|p| Rc::new(CachedPart::new(p)) as Rc<dyn Parts>)
+                .collect(),
+            note,
+        }
+    }
+}
+
+/// 解析会话中的所有伴随文件
+pub fn companion_files(session: &Session) -> Vec<CompanionFile> {
+    session
+        .companions()
+        .map(|(kind, name, bytes)| CompanionFile::new(kind, name, bytes))
+        .collect()
+}
+
+impl Parts for WalHeader {
+    fn label(&self) -> String {
+        "WAL Header".to_string()
+    }
+
+    fn desc(&self) -> Text {
+        catalog::wal_header()
+            + if self.valid {
+                catalog::wal_checksum_valid()
+            } else {
+                catalog::wal_checksum_invalid()
+            }
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        Rc::new([
+            Field::new(catalog::wal_magic(), 0, 4, Value::U32(self.magic)),
+            Field::new(catalog::wal_version(), 4, 4, Value::U32(self.version)),
+            Field::new(catalog::wal_page_size(), 8, 4, Value::U32(self.page_size)),
+            Field::new(
+                catalog::checkpoint_seq(),
+                12,
+                4,
+                Value::U32(self.checkpoint_seq),
+            ),
+            Field::new(catalog::wal_salt1(), 16, 4, Value::U32(self.salt1)),
+            Field::new(catalog::wal_salt2(), 20, 4, Value::U32(self.salt2)),
+            Field::new(
+                catalog::wal_header_checksum1(),
+                24,
+                4,
+                Value::U32(self.checksum1),
+            ),
+            Field::new(
+                catalog::wal_header_checksum2(),
+                28,
+                4,
+                Value::U32(self.checksum2),
+            ),
+        ])
+    }
+}
+
+impl Parts for WalFrame {
+    fn label(&self) -> String {
+        let state = match (self.committed, self.valid) {
+            (true, _) if self.is_commit() => ", commit",
+            (true, _) => "",
+            (false, true) => ", uncommitted",
+            (false, false) => ", invalid",
+        };
+        format!("Frame {} (Page {}{state})", self.index, self.page_number)
+    }
+
+    fn desc(&self) -> Text {
+        catalog::wal_frame(self.page_number)
+            + if self.committed {
+                catalog::frame_committed()
+            } else if self.valid {
+                catalog::frame_uncommitted()
+            } else {
+                catalog::frame_invalid()
+            }
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let o = self.offset;
+        Rc::new([
+            Field::new(
+                catalog::frame_page_number(),
+                o,
+                4,
+                Value::U32(self.page_number),
+            ),
+            Field::new(catalog::frame_db_size(), o + 4, 4, Value::U32(self.db_size)),
+            Field::new(catalog::frame_salt1(), o + 8, 4, Value::U32(self.salt1)),
+            Field::new(catalog::frame_salt2(), o + 12, 4, Value::U32(self.salt2)),
+            Field::new(
+                catalog::frame_checksum1(),
+                o + 16,
+                4,
+                Value::U32(self.checksum1),
+            ),
+            Field::new(
+                catalog::frame_checksum2(),
+                o + 20,
+                4,
+                Value::U32(self.checksum2),
+            ),
+            Field::new(
+                catalog::frame_content(),
+                o + 24,
+                self.content.len(),
+                Value::Array(self.content.clone()),
+            ),
+        ])
+    }
+
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        let image = PageImage {
+            page_number: self.page_number,
+            offset: self.offset + 24,
+            content: self.content.clone(),
+            desc: catalog::wal_page_image(self.page_number),
+        };
+        vec![Rc::new(CachedPart::new(Rc::new(image)))]
+    }
+
+    fn has_children(&self) -> bool {
+        true
+    }
+}
+
+fn index_header_fields(h: &WalIndexHeader) -> Vec<Field> {
+    let o = h.offset;
+    vec![
+        Field::new(catalog::index_version(), o, 4, Value::U32(h.version)),
+        Field::new(catalog::index_change(), o + 8, 4, Value::U32(h.change)),
+        Field::new(catalog::index_is_init(), o + 12, 1, Value::U8(h.is_init)),
+        Field::new(
+            catalog::index_big_end_checksum(),
+            o + 13,
+            1,
+            Value::U8(h.big_end_checksum),
+        ),
+        Field::new(
+            catalog::index_page_size(),
+            o + 14,
+            2,
+            Value::U16(h.page_size),
+        ),
+        Field::new(
+            catalog::index_max_frame(),
+            o + 16,
+            4,
+            Value::U32(h.max_frame),
+        ),
+        Field::new(catalog::index_db_size(), o + 20, 4, Value::U32(h.db_size)),
+        Field::new(
+            catalog::index_frame_checksum1(),
+            o + 24,
+            4,
+            Value::U32(h.frame_checksum[0]),
+        ),
+        Field::new(
+            catalog::index_frame_checksum2(),
+            o + 28,
+            4,
+            Value::U32(h.frame_checksum[1]),
+        ),
+        Field::new(catalog::frame_salt1(), o + 32, 4, Value::U32(h.salt[0])),
+        Field::new(catalog::frame_salt2(), o + 36, 4, Value::U32(h.salt[1])),
+        Field::new(
+            catalog::index_checksum1(),
+            o + 40,
+            4,
+            Value::U32(h.checksum[0]),
+        ),
+        Field::new(
+            catalog::index_checksum2(),
+            o + 44,
+            4,
+            Value::U32(h.checksum[1]),
+        ),
+    ]
+}
+
+impl Parts for WalIndex {
+    fn label(&self) -> String {
+        "WAL Index Header".to_string()
+    }
+
+    fn desc(&self) -> Text {
+        let consistent = if self.consistent() {
+            catalog::index_consistent()
+        } else {
+            catalog::index_inconsistent()
+        };
+        let order = catalog::byte_order(self.big_endian);
+        catalog::wal_index(order) + consistent
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let c = &self.checkpoint;
+        let mut fields = index_header_fields(&self.headers[0]);
+        fields.extend(index_header_fields(&self.headers[1]));
+        fields.push(Field::new(
+            catalog::backfill(),
+            96,
+            4,
+            Value::U32(c.backfill),
+        ));
+        for (i, mark) in c.read_marks.iter().enumerate() {
+            fields.push(Field::new(
+                catalog::read_mark(i),
+                100 + i * 4,
+                4,
+                Value::U32(*mark),
+            ));
+        }
+        fields.push(Field::new(
+            catalog::lock_bytes(),
+            120,
+            8,
+            Value::Array(c.locks[..].into()),
+        ));
+        fields.push(Field::new(
+            catalog::backfill_attempted(),
+            128,
+            4,
+            Value::U32(c.backfill_attempted),
+        ));
+        fields.into()
+    }
+}
+
+impl Parts for JournalHeader {
+    fn label(&self) -> String {
+        format!("Journal Header {}", self.index)
+    }
+
+    fn desc(&self) -> Text {
+        catalog::journal_header()
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let o = self.offset;
+        Rc::new([
+            Field::new(
+                catalog::journal_magic(),
+                o,
+                8,
+                Value::Array(self.magic[..].into()),
+            ),
+            Field::new(
+                catalog::journal_page_count(),
+                o + 8,
+                4,
+                Value::U32(self.page_count),
+            ),
+            Field::new(catalog::journal_nonce(), o + 12, 4, Value::U32(self.nonce)),
+            Field::new(
+                catalog::journal_initial_size(),
+                o + 16,
+                4,
+                Value::U32(self.initial_size),
+            ),
+            Field::new(
+                catalog::journal_sector_size(),
+                o + 20,
+                4,
+                Value::U32(self.sector_size),
+            ),
+            Field::new(
+                catalog::journal_page_size(),
+                o + 24,
+                4,
+                Value::U32(self.page_size),
+            ),
+        ])
+    }
+}
+
+impl Parts for JournalRecord {
+    fn label(&self) -> String {
+        let bad = if self.valid { "" } else { ", bad checksum" };
+        format!("Record {} (Page {}{bad})", self.index, self.page_number)
+    }
+
+    fn desc(&self) -> Text {
+        catalog::journal_record(self.page_number)
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let o = self.offset;
+        let size = self.content.len();
+        Rc::new([
+            Field::new(
+                catalog::record_page_number(),
+                o,
+                4,
+                Value::U32(self.page_number),
+            ),
+            Field::new(
+                catalog::record_content(),
+                o + 4,
+                size,
+                Value::Array(self.content.clone()),
+            ),
+            Field::new(
+                catalog::record_checksum(),
+                o + 4 + size,
+                4,
+                Value::U32(self.checksum),
+            ),
+        ])
+    }
+
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        let image = PageImage {
+            page_number: self.page_number,
+            offset: self.offset + 4,
+            content: self.content.clone(),
+            desc: catalog::journal_page_image(self.page_number),
+        };
+        vec![Rc::new(CachedPart::new(Rc::new(image)))]
+    }
+
+    fn has_children(&self) -> bool {
+        true
+    }
+}
+
+/// WAL 帧或日志记录中保存的一页
+#[derive(Debug)]
+struct PageImage {
+    page_number: u32,
+    /// 页内容在伴随文件中的偏移
+    offset: usize,
+    content: Bytes,
+    desc: Text,
+}
+
+impl Parts for PageImage {
+    fn label(&self) -> String {
+        format!("Page {} image", self.page_number)
+    }
+
+    fn desc(&self) -> Text {
+        self.desc.clone()
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let h = if self.page_number == 1 { 100 } else { 0 };
+        let mut fields = match self.content.get(h..).map(PageHeader::parse) {
+            Some(Ok(header)) => page_header_fields(self.offset + h, &header),
+            _ => vec![],
+        };
+        fields.push(Field::new(
+            catalog::page_content(),
+            self.offset,
+            self.content.len(),
+            Value::Array(self.content.clone()),
+        ));
+        fields.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::RecordValue,
+        writer::{wal::build_wal, DatabaseBuilder},
+    };
+
+    #[test]
+    fn group_wal_frames() {
+        let db = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a)", vec![vec![RecordValue::Integer(1)]])
+            .build()
+            .unwrap();
+        let wal = build_wal(
+            512,
+            &[(2, &db[512..], 0), (1, &db[..512], 2), (2, &db[512..], 0)],
+        );
+        let mut session = Session::new("t.db".to_string(), Rc::from(db));
+        session.wal = Some(Rc::from(wal));
+        session.shm = Some(Rc::from(&b"junk"[..]));
+
+        let files = companion_files(&session);
+        assert_eq!(files.len(), 2);
+        let labels: Vec<_> = files[0].parts.iter().map(|p| p.label()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "WAL Header",
+                "Frame 1 (Page 2)",
+                "Frame 2 (Page 1, commit)",
+                "Frame 3 (Page 2, uncommitted)"
+            ]
+        );
+        assert_eq!(files[0].parts[1].fields()[0].offset, 32);
+        // 帧中保存的页，页头在帧头之后
+        let image = &files[0].parts[1].children()[0];
+        assert_eq!(image.label(), "Page 2 image");
+        assert_eq!(image.fields()[0].offset, 32 + 24);
+        assert_eq!(image.location(), None);
+        assert_eq!(files[1].name, "t.db-shm");
+        assert!(files[1].parts.is_empty() && files[1].note.is_some());
+    }
+}
@@ -0,0 +1,113 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+
+use crate::parser::{Diagnostic, Severity};
+
+use super::{catalog, i18n::Text, state::AppState, Field, Parts};
+
+pub const DIAGNOSTICS_LABEL: &str = "Diagnostics";
+
+impl Parts for Vec<Diagnostic> {
+    fn label(&self) -> String {
+        DIAGNOSTICS_LABEL.to_string()
+    }
+
+    fn desc(&self) -> Text {
+        let count = |s: Severity| self.iter().filter(|d| d.severity == s).count();
+        if self.is_empty() {
+            catalog::no_problems()
+        } else {
+            catalog::problems(
+                count(Severity::Error),
+                count(Severity::Warning),
+                count(Severity::Info),
+            )
+        }
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        Rc::new([])
+    }
+}
+
+/// 选中问题所在的页，以及页中包含该偏移的字段
+fn go_to(mut state: AppState, diagnostic: &Diagnostic) {
+    let viewer = state.viewer.read();
+    let part = match diagnostic.page {
+        Some(page) => viewer.page_part(page),
+        None => Some(viewer.first_part()),
+    };
+    let Some(part) = part else {
+        return;
+    };
+    let field = part
+        .fields()
+        .iter()
+        .find(|f| (f.offset..f.offset + f.size.max(1)).contains(&diagnostic.offset))
+        .cloned();
+    drop(viewer);
+    *state.selected_part.write() = part;
+    *state.selected_field.write() = field;
+}
+
+/// 解析中发现的问题列表
+pub fn DiagnosticsList() -> Element {
+    let state = use_context::<AppState>();
+    let diagnostics = state.viewer.read().diagnostics();
+    let edit = state.edit.read().clone();
+
+    rsx! {
+        div {
+            class: "p-4 overflow-auto text-xs",
+            table {
+                class: "table table-xs",
+                thead {
+                    tr {
+                        th { "Severity" }
+                        th { "Structure" }
+                        th { "Page" }
+                        th { "Offset" }
+                        th { "Problem" }
+                        th {}
+                    }
+                }
+                tbody {
+                    for diagnostic in diagnostics.iter() {
+                        tr {
+                            class: "hover cursor-pointer",
+                            onclick: {
+                                let diagnostic = diagnostic.clone();
+                                move |_| go_to(state, &diagnostic)
+                            },
+                            td {
+                                class: match diagnostic.severity {
+                                    Severity::Error => "text-error",
+                                    Severity::Warning => "text-warning",
+                                    Severity::Info => "text-info",
+                                },
+                                "{diagnostic.severity}"
+                            }
+                            td { "{diagnostic.structure}" }
+                            td {
+                                if let Some(page) = diagnostic.page {
+                                    "{page}"
+                                }
+                            }
+                            td { "{diagnostic.offset}" }
+                            td { "{diagnostic.message}" }
+                            // 修改文件后新出现的问题
+                            td {
+                                if edit.is_new(diagnostic) {
+                                    span { class: "badge badge-xs badge-error", "new" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
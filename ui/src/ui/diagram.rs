@@ -0,0 +1,486 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+
+use crate::parser::{BTreePage, Page, PageType, Reader, RecordValue};
+
+use super::state::AppState;
+
+/// 图中最多画出的 B-Tree 页，更多的子页只显示数量
+const LIMIT: usize = 1000;
+/// 每条溢出链最多画出的页
+const CHAIN_LIMIT: usize = 8;
+
+const NODE_W: f64 = 136.0;
+const NODE_H: f64 = 52.0;
+const CHAIN_W: f64 = 30.0;
+const CHAIN_H: f64 = 16.0;
+const GAP: f64 = 16.0;
+const LEVEL_GAP: f64 = 48.0;
+
+/// B-Tree 中的一页
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub page: u32,
+    /// 不是 B-Tree 页时为 None，例如子页指针指向了损坏的页
+    pub page_type: Option<PageType>,
+    pub cells: usize,
+    /// 第一个和最后一个单元的键
+    pub keys: Option<(String, String)>,
+    /// 单元的溢出链，按单元的顺序
+    pub overflow: Vec<Vec<u32>>,
+    pub children: Vec<Node>,
+    /// 超出 LIMIT 没有画出的子页数
+    pub hidden: usize,
+}
+
+/// 单元的键：表页中是 rowid，索引页中是 record 的第一列
+fn key(page: &BTreePage, i: usize) -> Option<String> {
+    let cell = page.cells.get(i)?;
+    if let Some((rowid, _)) = cell.rowid {
+        return Some(rowid.to_string());
+    }
+    let value = &cell
+        .payload
+        .as_ref()?
+        .record
+        .as_ref()?
+        .columns
+        .first()?
+        .value;
+    let mut text = match value {
+        RecordValue::Text(s) => format!("{s:?}"),
+        v => v.to_string(),
+    };
+    if text.chars().count() > 12 {
+        text = text.chars().take(12).collect::<String>() + "…";
+    }
+    Some(text)
+}
+
+/// 从 root 开始读出 B-Tree 的形状。已经出现过的页不再展开，避免损坏的文件中的环
+pub fn build(reader: &Reader, root: u32) -> Option<Node> {
+    let mut seen = HashSet::new();
+    let mut budget = LIMIT;
+    build_node(reader, root, &mut seen, &mut budget)
+}
+
+fn build_node(
+    reader: &Reader,
+    number: u32,
+    seen: &mut HashSet<u32>,
+    budget: &mut usize,
+) -> Option<Node> {
+    if *budget == 0 || !seen.insert(number) {
+        return None;
+    }
+    *budget -= 1;
+    let page = reader.page(number)?;
+    let Page::BTree(page) = &*page else {
+        return Some(Node {
+            page: number,
+            page_type: None,
+            cells: 0,
+            keys: None,
+            overflow: vec![],
+            children: vec![],
+            hidden: 0,
+        });
+    };
+    let keys = key(page, 0).zip(key(page, page.cells.len().saturating_sub(1)));
+    let overflow = page
+        .cells
+        .iter()
+        .filter_map(|c| c.payload.as_ref())
+        .filter(|p| !p.overflow.is_empty())
+        .map(|p| p.overflow.iter().map(|&(n, _)| n).collect())
+        .collect();
+    let pointers: Vec<u32> = page
+        .cells
+        .iter()
+        .filter_map(|c| c.left_child)
+        .chain(page.header.right_most_pointer)
+        .collect();
+    let mut children = vec![];
+    let mut hidden = 0;
+    for child in pointers {
+        match build_node(reader, child, seen, budget) {
+            Some(node) => children.push(node),
+            None if *budget == 0 => hidden += 1,
+            None => {}
+        }
+    }
+    Some(Node {
+        page: number,
+        page_type: Some(page.header.page_type),
+        cells: page.cells.len(),
+        keys,
+        overflow,
+        children,
+        hidden,
+    })
+}
+
+/// 画在图中的一页
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placed {
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    pub lines: Vec<String>,
+    /// 溢出页比 B-Tree 页小，只显示页号
+    pub overflow: bool,
+    pub leaf: bool,
+}
+
+/// 布局后的整棵树：页、子页指针和溢出链中的箭头
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub boxes: Vec<Placed>,
+    pub edges: Vec<(f64, f64, f64, f64)>,
+}
+
+impl Node {
+    /// 页框和右边的溢出链一起占用的宽度
+    fn own_width(&self) -> f64 {
+        let longest = self
+            .overflow
+            .iter()
+            .map(|c| c.len().min(CHAIN_LIMIT + 1))
+            .max()
+            .unwrap_or(0);
+        if longest == 0 {
+            NODE_W
+        } else {
+            NODE_W + GAP + longest as f64 * CHAIN_W
+        }
+    }
+
+    fn own_height(&self) -> f64 {
+        NODE_H.max(self.overflow.len() as f64 * (CHAIN_H + 4.0))
+    }
+
+    /// 以这一页为根的子树占用的宽度
+    fn width(&self) -> f64 {
+        let children: f64 = self.children.iter().map(Node::width).sum::<f64>()
+            + GAP * self.children.len().saturating_sub(1) as f64;
+        self.own_width().max(children)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let kind = self
+            .page_type
+            .map_or("Not a b-tree page".to_string(), |t| t.to_string());
+        let mut lines = vec![
+            format!("Page {}", self.page),
+            format!("{kind} · {} cell(s)", self.cells),
+        ];
+        match &self.keys {
+            Some((first, last)) if first == last => lines.push(format!("key {first}")),
+            Some((first, last)) => lines.push(format!("keys {first} … {last}")),
+            None => {}
+        }
+        if self.hidden > 0 {
+            lines.push(format!("+{} child page(s) not drawn", self.hidden));
+        }
+        lines
+    }
+}
+
+/// 每一层的高度：这一层中最高的页框或溢出链
+fn level_heights(node: &Node, depth: usize, heights: &mut Vec<f64>) {
+    if heights.len() <= depth {
+        heights.push(0.0);
+    }
+    heights[depth] = heights[depth].max(node.own_height());
+    for child in &node.children {
+        level_heights(child, depth + 1, heights);
+    }
+}
+
+/// 子树占用从 left 开始的一段，页框在子树的中间，子页在下一层从左到右排列
+fn place(node: &Node, left: f64, depth: usize, tops: &[f64], layout: &mut Layout) -> (f64, f64) {
+    let width = node.width();
+    let own = node.own_width();
+    let x = left + (width - own) / 2.0;
+    let y = tops[depth];
+    layout.boxes.push(Placed {
+        page: node.page,
+        x,
+        y,
+        w: NODE_W,
+        h: NODE_H,
+        lines: node.lines(),
+        overflow: false,
+        leaf: node.children.is_empty(),
+    });
+    // 溢出链画在页框右边，每条链一行
+    for (row, chain) in node.overflow.iter().enumerate() {
+        let cy = y + row as f64 * (CHAIN_H + 4.0);
+        let mut from = (x + NODE_W, cy + CHAIN_H / 2.0);
+        for (i, &page) in chain.iter().take(CHAIN_LIMIT).enumerate() {
+            let cx = x + NODE_W + GAP + i as f64 * CHAIN_W;
+            layout.edges.push((from.0, from.1, cx, cy + CHAIN_H / 2.0));
+            layout.boxes.push(Placed {
+                page,
+                x: cx,
+                y: cy,
+                w: CHAIN_W - 6.0,
+                h: CHAIN_H,
+                lines: vec![page.to_string()],
+                overflow: true,
+                leaf: false,
+            });
+            from = (cx + CHAIN_W - 6.0, cy + CHAIN_H / 2.0);
+        }
+        if chain.len() > CHAIN_LIMIT {
+            let cx = x + NODE_W + GAP + CHAIN_LIMIT as f64 * CHAIN_W;
+            layout.boxes.push(Placed {
+                page: chain[CHAIN_LIMIT],
+                x: cx,
+                y: cy,
+                w: CHAIN_W - 6.0,
+                h: CHAIN_H,
+                lines: vec![format!("+{}", chain.len() - CHAIN_LIMIT)],
+                overflow: true,
+                leaf: false,
+            });
+        }
+    }
+    let children: f64 = node.children.iter().map(Node::width).sum::<f64>()
+        + GAP * node.children.len().saturating_sub(1) as f64;
+    let mut child_left = left + (width - children) / 2.0;
+    let bottom = (x + NODE_W / 2.0, y + NODE_H);
+    for child in &node.children {
+        let top = place(child, child_left, depth + 1, tops, layout);
+        layout.edges.push((bottom.0, bottom.1, top.0, top.1));
+        child_left += child.width() + GAP;
+    }
+    (x + NODE_W / 2.0, y)
+}
+
+/// 计算每一页在图中的位置
+pub fn layout(root: &Node) -> Layout {
+    let mut heights = vec![];
+    level_heights(root, 0, &mut heights);
+    let mut tops = vec![GAP];
+    for h in &heights {
+        tops.push(tops.last().unwrap() + h + LEVEL_GAP);
+    }
+    let mut layout = Layout {
+        width: root.width() + 2.0 * GAP,
+        height: tops[heights.len()] - LEVEL_GAP + GAP,
+        ..Layout::default()
+    };
+    place(root, GAP, 0, &tops, &mut layout);
+    layout
+}
+
+/// 表或索引的 B-Tree 图：页是节点，子页指针是边，溢出链画在页的右边。
+/// 点击页选中它，可以缩放。
+pub fn BTreeDiagram() -> Element {
+    let state = use_context::<AppState>();
+    let mut selected_part = state.selected_part;
+    let mut selected_field = state.selected_field;
+    let mut chosen = use_signal(|| None::<u32>);
+    let mut zoom = use_signal(|| 1.0f64);
+    // 选中的页所属的树的根页
+    let owner_root = use_memo(move || {
+        let viewer = state.viewer.read();
+        let number = viewer.page_number(&state.selected_part.read())?;
+        match viewer.reader.page(number).as_deref() {
+            Some(Page::BTree(p)) => p.owner.as_ref().map(|o| o.root_page),
+            _ => None,
+        }
+    });
+    // 换了数据库，或者选中了某棵树中的页时，不再画之前从下拉菜单选的树。
+    // 选中溢出页等不属于树的页时保留
+    use_effect(move || {
+        state.viewer.read();
+        chosen.set(None);
+    });
+    use_effect(move || {
+        if owner_root.read().is_some() {
+            chosen.set(None);
+        }
+    });
+
+    // 没有选择时画选中的页所属的树
+    let root = use_memo(move || {
+        let viewer = state.viewer.read();
+        let mut roots = viewer
+            .reader
+            .schema
+            .iter()
+            .map(|e| e.root_page)
+            .filter(|&root| root > 0);
+        let owner_root = owner_root().filter(|root| roots.clone().any(|r| r == *root));
+        chosen().or(owner_root).or(roots.next())
+    });
+    // 只在换了数据库或者换了树时重新读页和布局，选中页和缩放不影响
+    let layout = use_memo(move || {
+        root()
+            .and_then(|root| build(&state.viewer.read().reader, root))
+            .map(|tree| layout(&tree))
+            .unwrap_or_default()
+    });
+
+    let viewer = state.viewer.read();
+    let trees: Vec<(String, u32)> = viewer
+        .reader
+        .schema
+        .iter()
+        .filter(|e| e.root_page > 0)
+        .map(|e| (format!("{} {}", e.kind, e.name), e.root_page))
+        .collect();
+    let selected_page = viewer.page_number(&selected_part.read());
+    let root = root();
+    let layout = layout.read();
+    let scale = zoom();
+
+    rsx! {
+        div {
+            class: "flex items-center gap-2 px-4 py-1 bg-secondary text-xs",
+            div {
+                class: "font-bold tracking-tighter",
+                "B-tree"
+            }
+            select {
+                class: "select select-xs select-bordered",
+                onchange: move |e| chosen.set(e.value().parse().ok()),
+                for (name, page) in trees {
+                    option {
+                        value: "{page}",
+                        selected: Some(page) == root,
+                        "{name} (root page {page})"
+                    }
+                }
+            }
+            button {
+                class: "btn btn-xs btn-ghost",
+                onclick: move |_| zoom.set((scale / 1.25).max(0.1)),
+                "−"
+            }
+            div { class: "w-10 text-center", "{scale * 100.0:.0}%" }
+            button {
+                class: "btn btn-xs btn-ghost",
+                onclick: move |_| zoom.set((scale * 1.25).min(4.0)),
+                "+"
+            }
+            button {
+                class: "btn btn-xs btn-ghost",
+                onclick: move |_| zoom.set(1.0),
+                "Reset"
+            }
+        }
+        div {
+            class: "overflow-auto max-h-[32rem] bg-base-100",
+            svg {
+                width: "{layout.width * scale}",
+                height: "{layout.height * scale}",
+                "viewBox": "0 0 {layout.width} {layout.height}",
+                "font-family": "sans-serif",
+                for (x1, y1, x2, y2) in layout.edges.iter().copied() {
+                    line {
+                        x1: "{x1}",
+                        y1: "{y1}",
+                        x2: "{x2}",
+                        y2: "{y2}",
+                        stroke: "#9ca3af",
+                        "stroke-width": "1",
+                    }
+                }
+                for b in layout.boxes.iter() {
+                    g {
+                        class: "cursor-pointer",
+                        onclick: {
+                            let page = b.page;
+                            move |_| {
+                                if let Some(part) = state.viewer.read().page_part(page) {
+                                    *selected_part.write() = part;
+                                    *selected_field.write() = None;
+                                }
+                            }
+                        },
+                        rect {
+                            x: "{b.x}",
+                            y: "{b.y}",
+                            width: "{b.w}",
+                            height: "{b.h}",
+                            rx: "4",
+                            fill: if b.overflow { "#fed7aa" } else if b.leaf { "#bbf7d0" } else { "#bae6fd" },
+                            stroke: if selected_page == Some(b.page) { "#f43f5e" } else { "#6b7280" },
+                            "stroke-width": if selected_page == Some(b.page) { "3" } else { "1" },
+                        }
+                        for (i, line) in b.lines.iter().enumerate() {
+                            text {
+                                x: "{b.x + 6.0}",
+                                y: "{b.y + 12.0 + 12.0 * i as f64}",
+                                "font-size": if b.overflow { "9" } else { "10" },
+                                "font-weight": if i == 0 { "bold" } else { "normal" },
+                                "{line}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::DatabaseBuilder;
+
+    #[test]
+    fn draw_a_table_tree() {
+        let rows = (1..=40)
+            .map(|i| {
+                let text = if i == 7 {
+                    "y".repeat(3000)
+                } else {
+                    "x".repeat(100)
+                };
+                vec![RecordValue::Integer(i), RecordValue::Text(text)]
+            })
+            .collect();
+        let bytes = DatabaseBuilder::new(1024)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+        let reader = Reader::new(&bytes).unwrap();
+        let root = reader.schema[1].root_page;
+        let tree = build(&reader, root).unwrap();
+        assert_eq!(tree.page_type, Some(PageType::InteriorTable));
+        assert!(tree.children.len() > 1);
+        assert!(tree
+            .children
+            .iter()
+            .all(|c| c.page_type == Some(PageType::LeafTable)));
+        // 第一个叶子页从 rowid 1 开始，长文本所在的页有溢出链
+        assert_eq!(tree.children[0].keys.as_ref().unwrap().0, "1");
+        assert!(tree.children.iter().any(|c| c.overflow.len() == 1));
+
+        let layout = layout(&tree);
+        let leaves: Vec<_> = layout.boxes.iter().filter(|b| b.leaf).collect();
+        assert_eq!(leaves.len(), tree.children.len());
+        // 同一层的页框不重叠，都在图中
+        for pair in leaves.windows(2) {
+            assert!(pair[0].x + pair[0].w < pair[1].x);
+        }
+        assert!(layout
+            .boxes
+            .iter()
+            .all(|b| b.x + b.w <= layout.width && b.y + b.h <= layout.height));
+        assert!(layout.boxes.iter().any(|b| b.overflow));
+        // 子页指针加上溢出链中的箭头
+        assert!(layout.edges.len() > tree.children.len());
+    }
+}
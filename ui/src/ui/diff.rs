@@ -0,0 +1,316 @@
+#![allow(non_snake_case)]
+
+use std::{collections::HashMap, rc::Rc};
+
+use dioxus::prelude::*;
+
+use super::{i18n::Lang, state::AppState, viewer::Viewer, Field, Parts};
+
+/// 一页或一个字段在两个数据库之间的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Changed,
+    /// 只在第二个数据库中
+    Added,
+    /// 只在第一个数据库中
+    Removed,
+}
+
+impl Change {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Same => "Same",
+            Self::Changed => "Changed",
+            Self::Added => "Added",
+            Self::Removed => "Removed",
+        }
+    }
+
+    fn colour(self) -> &'static str {
+        match self {
+            Self::Same => "bg-base-300",
+            Self::Changed => "bg-warning",
+            Self::Added => "bg-success",
+            Self::Removed => "bg-error",
+        }
+    }
+}
+
+/// 字段在两边的版本，只在一边出现的字段另一边为 None
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub before: Option<Field>,
+    pub after: Option<Field>,
+}
+
+impl FieldDiff {
+    pub fn change(&self) -> Change {
+        match (&self.before, &self.after) {
+            (Some(a), Some(b)) if a.value == b.value && a.to_bytes() == b.to_bytes() => {
+                Change::Same
+            }
+            (Some(_), Some(_)) => Change::Changed,
+            (None, _) => Change::Added,
+            (Some(_), None) => Change::Removed,
+        }
+    }
+}
+
+/// 按英文描述和同样描述中的次序配对两边的字段，例如两个数据库中第 3 个单元的 rowid。
+/// 返回不同的字段，按第一个数据库中的顺序排列，只在第二个数据库中的字段排在最后。
+pub fn diff_fields(before: &[Field], after: &[Field]) -> Vec<FieldDiff> {
+    let key = |fields: &[Field]| {
+        let mut seen: HashMap<Rc<str>, usize> = HashMap::new();
+        fields
+            .iter()
+            .map(|f| {
+                let n = seen.entry(f.desc.en.clone()).or_default();
+                *n += 1;
+                (f.desc.en.clone(), *n)
+            })
+            .collect::<Vec<_>>()
+    };
+    let before_keys = key(before);
+    let after_keys = key(after);
+    let after_index: HashMap<_, _> = after_keys.iter().zip(after).collect();
+    let mut diffs: Vec<FieldDiff> = before_keys
+        .iter()
+        .zip(before)
+        .map(|(k, f)| FieldDiff {
+            before: Some(f.clone()),
+            after: after_index.get(k).map(|&f| f.clone()),
+        })
+        .collect();
+    let before_index: HashMap<_, _> = before_keys.iter().zip(before).collect();
+    diffs.extend(
+        after_keys
+            .iter()
+            .zip(after)
+            .filter(|(k, _)| !before_index.contains_key(k))
+            .map(|(_, f)| FieldDiff {
+                before: None,
+                after: Some(f.clone()),
+            }),
+    );
+    diffs.retain(|d| d.change() != Change::Same);
+    diffs
+}
+
+/// 第 number 页在文件中的字节，直接按页大小切分，不解析页
+fn page_bytes(viewer: &Viewer, number: u32) -> Option<&[u8]> {
+    let page_size = viewer.reader.header.real_page_size();
+    let start = (number as usize).checked_sub(1)?.checked_mul(page_size)?;
+    viewer.bytes.get(start..start.checked_add(page_size)?)
+}
+
+/// 两个数据库中每一页的变化，下标 0 是第 1 页
+pub fn diff_pages(before: &Viewer, after: &Viewer) -> Vec<Change> {
+    let count = before.reader.page_count().max(after.reader.page_count());
+    (1..=count)
+        .map(|n| match (page_bytes(before, n), page_bytes(after, n)) {
+            (Some(a), Some(b)) if a == b => Change::Same,
+            (Some(_), Some(_)) => Change::Changed,
+            (None, Some(_)) => Change::Added,
+            (Some(_), None) => Change::Removed,
+            (None, None) => Change::Same,
+        })
+        .collect()
+}
+
+/// 选中的部分在另一个数据库中对应的部分：同一页，或者数据库头
+fn counterpart(viewer: &Viewer, other: &Viewer, part: &Rc<dyn Parts>) -> Option<Rc<dyn Parts>> {
+    if Rc::ptr_eq(part, &viewer.first_part()) {
+        return Some(other.first_part());
+    }
+    other.page_part(viewer.page_number(part)?)
+}
+
+/// 选择与当前数据库比较的数据库，选择 None 退出比较
+pub fn CompareSelect() -> Element {
+    let state = use_context::<AppState>();
+    let other_db = state.other_db;
+    let mut open_error = state.open_error;
+    rsx! {
+        div {
+            class: "join ml-2",
+            div {
+                class: "join-item btn btn-secondary tracking-tighter font-bold",
+                "Compare with"
+            }
+            select {
+                class: "join-item select select-secondary select-bordered font-bold tracking-tighter",
+                oninput: move |e| {
+                    let name = e.value();
+                    let name = (name != NONE).then_some(name.as_str());
+                    if let Err(e) = state.compare_with(name) {
+                        *open_error.write() = Some(format!("{e:#}"));
+                    }
+                },
+                option {
+                    selected: other_db.read().is_empty(),
+                    "{NONE}"
+                }
+                for name in state.db_names() {
+                    option {
+                        selected: name == *other_db.read(),
+                        "{name}",
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 下拉菜单中表示不比较的选项
+const NONE: &str = "None";
+
+/// 当前数据库与另一个数据库（例如迁移或 VACUUM 之前和之后）的比较：
+/// 数据库头中不同的字段、每一页的变化和选中页中不同的字段。点击页跳到这一页。
+pub fn CompareView() -> Element {
+    let state = use_context::<AppState>();
+    let lang = state.lang;
+    let mut selected_part = state.selected_part;
+    let mut selected_field = state.selected_field;
+    // 只在两个数据库之一变化时重新比较所有页，选择其它部分时不用重新比较
+    let pages = use_memo(move || {
+        let other = state.other.read();
+        Some(diff_pages(&state.viewer.read(), other.as_ref()?))
+    });
+    let other = state.other.read();
+    let other = other.as_ref()?;
+    let viewer = state.viewer.read();
+    let header = diff_fields(&viewer.first_part().fields(), &other.first_part().fields());
+    let pages = pages.read();
+    let pages = pages.as_deref().unwrap_or_default();
+    let count = |c: Change| pages.iter().filter(|&&p| p == c).count();
+    let summary = [Change::Changed, Change::Added, Change::Removed].map(|c| (c, count(c)));
+    let part = selected_part.read().clone();
+    let selected_page = viewer.page_number(&part);
+    let title = part.label();
+    let fields = counterpart(&viewer, other, &part)
+        .map(|theirs| diff_fields(&part.fields(), &theirs.fields()));
+
+    rsx! {
+        div {
+            class: "flex items-center gap-4 px-4 py-1 bg-secondary text-xs",
+            div {
+                class: "font-bold tracking-tighter",
+                "Compare {state.current_db} with {state.other_db}"
+            }
+            for (change, n) in summary {
+                div {
+                    class: "flex items-center gap-1",
+                    span { class: "inline-block w-3 h-3 {change.colour()}" }
+                    "{n} page(s) {change.name().to_lowercase()}"
+                }
+            }
+        }
+        div {
+            class: "flex gap-6 p-4 text-xs max-h-96 overflow-auto",
+            div {
+                class: "w-1/3",
+                div { class: "font-bold pb-1", "Pages" }
+                div {
+                    class: "flex flex-wrap gap-px",
+                    for (i, change) in pages.iter().copied().enumerate() {
+                        div {
+                            class: "w-4 h-4 cursor-pointer {change.colour()}",
+                            class: if selected_page == Some(i as u32 + 1) {"ring-2 ring-accent"},
+                            title: "Page {i + 1}: {change.name()}",
+                            onclick: move |_| {
+                                let viewer = state.viewer.read();
+                                let part = viewer
+                                    .page_part(i as u32 + 1)
+                                    .unwrap_or_else(|| viewer.first_part());
+                                *selected_part.write() = part;
+                                *selected_field.write() = None;
+                            },
+                        }
+                    }
+                }
+                div { class: "font-bold pt-4 pb-1", "Database header" }
+                if header.is_empty() {
+                    div { "The headers are identical" }
+                }
+                FieldDiffTable { diffs: header, lang: lang() }
+            }
+            div {
+                class: "w-2/3",
+                div { class: "font-bold pb-1", "{title}" }
+                match fields {
+                    None => rsx! { div { "Select the database header or a page to compare its fields" } },
+                    Some(fields) if fields.is_empty() => rsx! { div { "No fields differ" } },
+                    Some(fields) => rsx! { FieldDiffTable { diffs: fields, lang: lang() } },
+                }
+            }
+        }
+    }
+}
+
+/// 不同的字段：描述、两边的偏移和值
+#[component]
+fn FieldDiffTable(diffs: Vec<FieldDiff>, lang: Lang) -> Element {
+    let cell = |f: &Option<Field>| {
+        f.as_ref()
+            .map_or("—".to_string(), |f| format!("{} @ {}", f.value, f.offset))
+    };
+    rsx! {
+        table {
+            class: "table table-xs",
+            tbody {
+                for diff in diffs {
+                    tr {
+                        td {
+                            span { class: "inline-block w-2 h-2 mr-1 {diff.change().colour()}" }
+                            {diff.before.as_ref().or(diff.after.as_ref()).map(|f| f.desc.get(lang).to_string())}
+                        }
+                        td { class: "font-mono", "{cell(&diff.before)}" }
+                        td { class: "font-mono", "{cell(&diff.after)}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::RecordValue, writer::DatabaseBuilder};
+
+    fn database(rows: i64) -> Viewer {
+        let rows = (1..=rows)
+            .map(|i| vec![RecordValue::Integer(i), RecordValue::Text("x".repeat(300))])
+            .collect();
+        let bytes = DatabaseBuilder::new(1024)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+        Viewer::new(bytes.into()).unwrap()
+    }
+
+    #[test]
+    fn compare_two_databases() {
+        let before = database(2);
+        let after = database(20);
+        let pages = diff_pages(&before, &after);
+        assert_eq!(pages.len(), after.reader.page_count() as usize);
+        assert!(pages.contains(&Change::Added));
+        assert_eq!(diff_pages(&after, &after), vec![Change::Same; pages.len()]);
+
+        // 页数和修改计数器不同
+        let header = diff_fields(&before.first_part().fields(), &after.first_part().fields());
+        assert!(header.iter().all(|d| d.change() == Change::Changed));
+        assert!(header
+            .iter()
+            .any(|d| d.before.as_ref().unwrap().offset == 28));
+
+        // 第 2 页从叶子页变为内部页，原来的单元都有变化或消失
+        let page = |v: &Viewer| v.page_part(2).unwrap().fields();
+        let fields = diff_fields(&page(&before), &page(&after));
+        assert!(fields.iter().any(|d| d.change() == Change::Removed));
+        assert!(fields.iter().any(|d| d.change() == Change::Added));
+        assert!(diff_fields(&page(&before), &page(&before)).is_empty());
+    }
+}
@@ -0,0 +1,472 @@
+#![allow(non_snake_case)]
+
+use std::{ops::Range, rc::Rc};
+
+use dioxus::prelude::*;
+
+use crate::parser::Diagnostic;
+
+use super::{
+    export::download_bytes,
+    hexdump::{ascii, HexRow, ROW},
+    state::AppState,
+    viewer::{LazyPart, Viewer},
+    Field, Parts, Value,
+};
+
+/// 一次修改：从 offset 开始的字节由 old 改为 new
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+/// 对当前数据库文件的修改，以及可以撤销和重做的历史
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    /// 修改前的文件
+    pub original: Rc<[u8]>,
+    /// 修改后的文件内容，可能已经无法解析
+    pub bytes: Rc<[u8]>,
+    /// 修改后的文件无法解析时的错误，此时界面仍然显示最后一次能解析的版本
+    pub error: Option<String>,
+    /// 修改前的文件中的问题，用于标出修改后新出现的问题。第一次比较时才遍历修改前的文件
+    baseline: Rc<LazyPart<Vec<Diagnostic>>>,
+    undo: Vec<Patch>,
+    redo: Vec<Patch>,
+    /// 与修改前不同的字节数，每次修改时按修改的范围更新
+    changed_bytes: usize,
+}
+
+impl Edit {
+    pub fn new(viewer: &Viewer) -> Self {
+        Self {
+            original: viewer.bytes.clone(),
+            bytes: viewer.bytes.clone(),
+            error: None,
+            baseline: viewer.lazy_diagnostics(),
+            undo: vec![],
+            redo: vec![],
+            changed_bytes: 0,
+        }
+    }
+
+    pub fn modified(&self) -> bool {
+        self.changed_bytes > 0
+    }
+
+    /// 与修改前不同的字节数
+    pub fn changed_bytes(&self) -> usize {
+        self.changed_bytes
+    }
+
+    /// range 中是否有字节与修改前不同
+    pub fn changed(&self, range: Range<usize>) -> bool {
+        self.bytes.get(range.clone()) != self.original.get(range)
+    }
+
+    /// 修改前的文件中没有这个问题
+    pub fn is_new(&self, diagnostic: &Diagnostic) -> bool {
+        !self.baseline.get().contains(diagnostic)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// 当前生效的修改涉及的页，按页号排列
+    pub fn touched_pages(&self, page_size: usize) -> Vec<u32> {
+        let mut pages: Vec<u32> = self
+            .undo
+            .iter()
+            .flat_map(|p| {
+                let last = p.offset + p.new.len().max(1) - 1;
+                p.offset / page_size + 1..=last / page_size + 1
+            })
+            .map(|n| n as u32)
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+
+    /// 用 new 覆盖从 offset 开始的字节，越界时返回 None
+    pub fn patched(&self, offset: usize, new: &[u8]) -> Option<Rc<[u8]>> {
+        let end = offset.checked_add(new.len())?;
+        let mut bytes = self.bytes.to_vec();
+        bytes.get_mut(offset..end)?.copy_from_slice(new);
+        Some(bytes.into())
+    }
+
+    /// 写入 new 并更新与修改前不同的字节数，越界时不修改
+    fn write(&mut self, offset: usize, new: &[u8]) -> bool {
+        let Some(bytes) = self.patched(offset, new) else {
+            return false;
+        };
+        let range = offset..offset + new.len();
+        let differs = |bytes: &[u8]| {
+            bytes[range.clone()]
+                .iter()
+                .zip(&self.original[range.clone()])
+                .filter(|(a, b)| a != b)
+                .count()
+        };
+        self.changed_bytes = self.changed_bytes + differs(&bytes) - differs(&self.bytes);
+        self.bytes = bytes;
+        true
+    }
+
+    /// 记录一次新的修改，清空重做历史
+    pub fn apply(&mut self, offset: usize, new: &[u8]) -> bool {
+        let Some(old) = offset
+            .checked_add(new.len())
+            .and_then(|end| self.bytes.get(offset..end))
+            .map(<[u8]>::to_vec)
+        else {
+            return false;
+        };
+        self.write(offset, new);
+        self.undo.push(Patch {
+            offset,
+            old,
+            new: new.to_vec(),
+        });
+        self.redo.clear();
+        true
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(patch) = self.undo.pop() else {
+            return false;
+        };
+        self.write(patch.offset, &patch.old);
+        self.redo.push(patch);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(patch) = self.redo.pop() else {
+            return false;
+        };
+        self.write(patch.offset, &patch.new);
+        self.undo.push(patch);
+        true
+    }
+}
+
+/// 修改文件中的字节并重新解析
+pub fn patch(mut state: AppState, offset: usize, new: &[u8]) {
+    if state.edit.write().apply(offset, new) {
+        reload(state);
+    }
+}
+
+pub fn undo(mut state: AppState) {
+    if state.edit.write().undo() {
+        reload(state);
+    }
+}
+
+pub fn redo(mut state: AppState) {
+    if state.edit.write().redo() {
+        reload(state);
+    }
+}
+
+/// part 的子孙中 label 和位置都相同的部分
+fn find_descendant(
+    part: Rc<dyn Parts>,
+    label: &str,
+    location: Option<(u32, Range<usize>)>,
+) -> Option<Rc<dyn Parts>> {
+    part.children().into_iter().find_map(|child| {
+        if child.label() == label && child.location() == location {
+            return Some(child);
+        }
+        find_descendant(child, label, location.clone())
+    })
+}
+
+/// 重新解析修改后的文件。只遍历被修改的页所在的 B-Tree，其它页在用到时才解析，
+/// 所以修改前后都没有遍历的 B-Tree 中的问题不会被当作新问题
+fn reparse(edit: &Edit) -> anyhow::Result<Viewer> {
+    let viewer = Viewer::new(edit.bytes.clone())?;
+    for number in edit.touched_pages(viewer.reader.header.real_page_size()) {
+        viewer.reader.classify(number);
+    }
+    Ok(viewer)
+}
+
+/// 重新解析修改后的文件。解析成功时保留当前选中的部分和字段。
+fn reload(mut state: AppState) {
+    let companions = state.viewer.read().companions.clone();
+    let reparsed = reparse(&state.edit.read());
+    let error = match reparsed {
+        Ok(viewer) => {
+            let viewer = viewer.with_companions(companions);
+            let selected = state.selected_part.read().clone();
+            let label = selected.label();
+            let part = match selected.location() {
+                // 页中的子部分在同一页的子孙中查找，找不到时选中这一页
+                Some((number, _)) => viewer.page_part(number).map(|page| {
+                    find_descendant(page.clone(), &label, selected.location()).unwrap_or(page)
+                }),
+                None => viewer.parts.iter().find(|p| p.label() == label).cloned(),
+            }
+            .unwrap_or_else(|| viewer.first_part());
+            let field = state.selected_field.read().as_ref().and_then(|selected| {
+                part.fields()
+                    .iter()
+                    .find(|f| f.offset == selected.offset)
+                    .cloned()
+            });
+            *state.selected_part.write() = part;
+            *state.selected_field.write() = field;
+            *state.descent.write() = None;
+            *state.viewer.write() = viewer;
+            None
+        }
+        Err(e) => Some(format!("The patched file can't be parsed: {e}")),
+    };
+    state.edit.write().error = error;
+}
+
+/// 编辑一个字段的值，回车确认，Esc 取消
+#[component]
+pub fn FieldEditor(field: Field, editing: Signal<Option<usize>>) -> Element {
+    let state = use_context::<AppState>();
+    // 初始内容是 Field::encode 能解析回原值的写法
+    let mut input = use_signal(|| match &field.value {
+        Value::Text(text) => text.trim_end_matches('\0').to_string(),
+        Value::Array(_) => field.to_hex(),
+        value => value.to_string(),
+    });
+    let mut error = use_signal(|| None::<String>);
+    rsx! {
+        div {
+            input {
+                class: "input input-xs input-bordered w-32",
+                class: if error.read().is_some() {"input-error"},
+                autofocus: true,
+                value: "{input}",
+                oninput: move |e| {
+                    *input.write() = e.value();
+                    *error.write() = None;
+                },
+                onkeydown: move |e| match e.key() {
+                    Key::Enter => match field.encode(&input.read()) {
+                        Ok(bytes) => {
+                            patch(state, field.offset, &bytes);
+                            *editing.write() = None;
+                        }
+                        Err(e) => *error.write() = Some(e.to_string()),
+                    },
+                    Key::Escape => *editing.write() = None,
+                    _ => {}
+                },
+            }
+            if let Some(e) = error() {
+                div {
+                    class: "text-error max-w-32",
+                    "{e}"
+                }
+            }
+        }
+    }
+}
+
+/// 文件被修改后，显示修改的概况、撤销/重做和下载按钮
+pub fn EditStatus() -> Element {
+    let state = use_context::<AppState>();
+    let edit = state.edit.read().clone();
+    if !edit.modified() && !edit.can_undo() && !edit.can_redo() {
+        return None;
+    }
+    let changed = edit.changed_bytes();
+    // 只统计已经遍历过的结构中的问题，不为此遍历整个文件
+    let new_diagnostics = state
+        .viewer
+        .read()
+        .reader
+        .diagnostics()
+        .iter()
+        .filter(|d| edit.is_new(d))
+        .count();
+    rsx! {
+        div {
+            class: "flex items-center px-4 py-1 space-x-2 text-sm bg-warning",
+            div {
+                class: "flex-grow",
+                match &edit.error {
+                    Some(e) => rsx! { "{e}" },
+                    None => rsx! {
+                        "{changed} byte(s) changed, {new_diagnostics} new problem(s) found."
+                    },
+                }
+            }
+            button {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                disabled: !edit.can_undo(),
+                onclick: move |_| undo(state),
+                "Undo"
+            }
+            button {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                disabled: !edit.can_redo(),
+                onclick: move |_| redo(state),
+                "Redo"
+            }
+            button {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                onclick: move |_| {
+                    let name = format!("{}-modified.db", state.current_db.read());
+                    download_bytes(&name, &state.edit.read().bytes);
+                },
+                "Download modified file"
+            }
+        }
+    }
+}
+
+/// 以十六进制编辑选中部分的字节。点击一个字节后输入两位十六进制数字即可修改，
+/// 之后光标移到下一个字节，Esc 结束编辑。
+pub fn HexEditor() -> Element {
+    let state = use_context::<AppState>();
+    let mut cursor = use_signal(|| None::<usize>);
+    let mut input = use_signal(String::new);
+    let Some(range) = state.viewer.read().part_range(&state.selected_part.read()) else {
+        return rsx! {
+            div {
+                class: "p-4 text-xs",
+                "This part has no bytes of its own."
+            }
+        };
+    };
+    let edit = state.edit.read().clone();
+    let bytes = edit.bytes.clone();
+    let end = range.end.min(bytes.len());
+
+    rsx! {
+        div {
+            class: "p-4 font-mono text-xs overflow-auto",
+            for row in (range.start..end).step_by(ROW) {
+                HexRow {
+                    offset: row,
+                    ascii: rsx! {
+                        for i in row..(row + ROW).min(end) {
+                            "{ascii(bytes[i])}"
+                        }
+                    },
+                    for i in row..(row + ROW).min(end) {
+                        if cursor() == Some(i) {
+                            input {
+                                class: "w-5 bg-accent",
+                                autofocus: true,
+                                maxlength: 2,
+                                value: "{input}",
+                                oninput: move |e| {
+                                    let text = e.value();
+                                    if text.len() < 2 {
+                                        *input.write() = text;
+                                        return;
+                                    }
+                                    if let Ok(b) = u8::from_str_radix(&text, 16) {
+                                        patch(state, i, &[b]);
+                                    }
+                                    input.write().clear();
+                                    *cursor.write() = (i + 1 < end).then_some(i + 1);
+                                },
+                                onkeydown: move |e| {
+                                    if e.key() == Key::Escape {
+                                        *cursor.write() = None;
+                                    }
+                                },
+                            }
+                        } else {
+                            span {
+                                class: "w-5 text-center cursor-pointer hover:bg-secondary",
+                                class: if edit.changed(i..i + 1) {"text-error font-bold"},
+                                onclick: move |_| {
+                                    input.write().clear();
+                                    *cursor.write() = Some(i);
+                                },
+                                "{bytes[i]:02X}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::SIMPLE_DB;
+
+    #[test]
+    fn patch_header_fields() {
+        let viewer = Viewer::new(Rc::from(SIMPLE_DB)).unwrap();
+        let edit = Edit::new(&viewer);
+        let fields = viewer.first_part().fields();
+        let user_version = fields.iter().find(|f| f.offset == 60).unwrap();
+        let bytes = user_version.encode("0x01020304").unwrap();
+        let patched = edit.patched(60, &bytes).unwrap();
+        assert_eq!(patched[60..64], [1, 2, 3, 4]);
+        assert_eq!(
+            Viewer::new(patched).unwrap().reader.header.user_version,
+            0x01020304
+        );
+
+        let page_size = fields.iter().find(|f| f.offset == 16).unwrap();
+        assert!(page_size.encode("65536").is_err());
+        let corrupt = edit.patched(16, &page_size.encode("1000").unwrap());
+        assert!(Viewer::new(corrupt.unwrap()).is_err());
+
+        assert!(edit.patched(SIMPLE_DB.len() - 1, &[0, 0]).is_none());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let viewer = Viewer::new(Rc::from(SIMPLE_DB)).unwrap();
+        let mut edit = Edit::new(&viewer);
+        assert!(!edit.undo());
+        assert!(edit.apply(60, &[1]));
+        assert!(edit.apply(60, &[2, 3]));
+        assert!(!edit.apply(SIMPLE_DB.len(), &[0]));
+        assert_eq!(edit.bytes[60..62], [2, 3]);
+        assert!(edit.changed(60..61) && !edit.changed(0..60));
+        assert_eq!(edit.changed_bytes(), 2);
+
+        assert!(edit.undo());
+        assert_eq!(edit.bytes[60..62], [1, SIMPLE_DB[61]]);
+        assert!(edit.undo());
+        assert!(!edit.modified() && edit.can_redo());
+        assert_eq!(edit.changed_bytes(), 0);
+        assert!(edit.redo());
+        assert_eq!(edit.bytes[60], 1);
+
+        // 新的修改清空重做历史
+        edit.apply(0, b"s");
+        assert!(!edit.can_redo());
+
+        // 修改第 2 页的页类型，出现新的问题
+        let page2 = viewer.reader.header.real_page_size();
+        edit.apply(page2, &[0xff]);
+        assert_eq!(edit.touched_pages(page2), [1, 2]);
+        let patched = reparse(&edit).unwrap();
+        let diagnostics = patched.reader.diagnostics();
+        assert!(diagnostics.iter().any(|d| edit.is_new(d)));
+        // 写回原来的值不算修改
+        edit.apply(page2, &SIMPLE_DB[page2..page2 + 1]);
+        edit.apply(0, b"S");
+        edit.apply(60, &SIMPLE_DB[60..61]);
+        assert!(!edit.modified());
+    }
+}
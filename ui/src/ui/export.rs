@@ -0,0 +1,132 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use dioxus_logger::tracing::error;
+use serde::Serialize;
+
+use crate::parser::{Analysis, DBHeader, Diagnostic, Page, SchemaEntry};
+
+use super::{state::AppState, viewer::Viewer, Field, Parts};
+
+/// 导出的完整解析结果，所有偏移都是文件偏移
+#[derive(Debug, Serialize)]
+pub struct Export<'a> {
+    pub file_size: usize,
+    pub header: &'a DBHeader,
+    pub schema: &'a [Rc<SchemaEntry>],
+    pub pages: Vec<Rc<Page>>,
+    pub diagnostics: Rc<Vec<Diagnostic>>,
+    pub analysis: Rc<Analysis>,
+    /// 界面上展示的每一部分及其字段
+    pub parts: Vec<ExportPart>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportPart {
+    pub label: String,
+    pub fields: Rc<[Field]>,
+}
+
+impl Viewer {
+    /// 导出整个文件，会遍历所有 B-Tree 并解析每一页
+    pub fn export(&self) -> Export<'_> {
+        let analysis = self.analysis();
+        let pages: Vec<Rc<Page>> = self.reader.pages().collect();
+        Export {
+            file_size: self.bytes.len(),
+            header: &self.reader.header,
+            schema: &self.reader.schema,
+            diagnostics: self.diagnostics(),
+            analysis,
+            parts: self
+                .parts
+                .iter()
+                .cloned()
+                .chain(pages.iter().map(|p| p.clone() as Rc<dyn Parts>))
+                .map(|p| ExportPart {
+                    label: p.label(),
+                    fields: p.fields(),
+                })
+                .collect(),
+            pages,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.export())
+    }
+}
+
+/// 让浏览器把 data 作为文件 file_name 下载。data 为字符串时按文本保存，为数组时按字节保存。
+fn download(file_name: &str, mime: &str, data: serde_json::Value) {
+    let eval = eval(
+        r#"
+        const [name, mime, data] = await dioxus.recv();
+        const content = typeof data === "string" ? data : new Uint8Array(data);
+        const url = URL.createObjectURL(new Blob([content], { type: mime }));
+        const a = document.createElement("a");
+        a.href = url;
+        a.download = name;
+        a.click();
+        URL.revokeObjectURL(url);
+        "#,
+    );
+    if let Err(e) = eval.send(serde_json::json!([file_name, mime, data])) {
+        error!("Download failed: {e:?}");
+    }
+}
+
+pub fn download_text(file_name: &str, mime: &str, text: String) {
+    download(file_name, mime, text.into());
+}
+
+pub fn download_bytes(file_name: &str, bytes: &[u8]) {
+    download(file_name, "application/vnd.sqlite3", bytes.into());
+}
+
+/// 把当前数据库的解析结果下载为 JSON
+pub fn ExportButton() -> Element {
+    let state = use_context::<AppState>();
+    rsx! {
+        div {
+            class: "btn btn-ghost tracking-tighter font-bold",
+            onclick: move |_| {
+                let name = format!("{}.json", state.current_db.read());
+                match state.viewer.read().to_json() {
+                    Ok(json) => download_text(&name, "application/json", json),
+                    Err(e) => error!("Failed to export JSON: {e}"),
+                }
+            },
+            "Export JSON"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_included_database() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&viewer.to_json().unwrap()).unwrap();
+        assert_eq!(json["header"]["header"], "SQLite format 3\0");
+        assert_eq!(json["pages"].as_array().unwrap().len(), 2);
+
+        let page = &json["pages"][1]["BTree"];
+        assert_eq!(
+            page["info"]["offset"],
+            viewer.reader.header.real_page_size()
+        );
+        assert_eq!(page["owner"], "simple");
+        let column = &page["cells"][0]["payload"]["record"]["columns"][0];
+        assert!(column["offset"].is_u64());
+        assert!(column["value"].is_object());
+
+        let header = &json["parts"][0];
+        assert_eq!(header["label"], "Database Header");
+        assert_eq!(header["fields"][0]["offset"], 0);
+    }
+}
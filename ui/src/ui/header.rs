@@ -1,9 +1,31 @@
-use crate::parser::{DBHeader, TextEncoding};
+use std::{ops::Range, rc::Rc};
 
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+use crate::parser::{varint::encode_varint, Bytes, DBHeader, TextEncoding};
+
+use super::{catalog, i18n::Text};
+
+/// 界面上展示的一部分：数据库头、分析报告或某一页
 pub trait Parts: std::fmt::Debug {
     fn label(&self) -> String;
-    fn desc(&self) -> String;
-    fn fields(&self) -> Vec<Field>;
+    fn desc(&self) -> Text;
+    /// 每次调用都重新生成字段。Viewer 中的 part 由 CachedPart 包装，只生成一次
+    fn fields(&self) -> Rc<[Field]>;
+    /// 下一层的部分，例如页中的单元、单元中的记录。侧栏展开节点时才调用
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        vec![]
+    }
+    /// 是否有下一层。折叠的节点用它决定是否显示展开按钮，不必生成子部分
+    fn has_children(&self) -> bool {
+        false
+    }
+    /// 主文件中的页或页里的子部分所在的页号和占用的字节。
+    /// 数据库头由 Viewer 确定，伴随文件中的部分没有
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        None
+    }
 }
 
 impl Parts for DBHeader {
@@ -11,157 +33,143 @@ impl Parts for DBHeader {
         "Database Header".to_string()
     }
 
-    fn desc(&self) -> String {
-        "The first 100 bytes of the database file comprise the database file header. All multibyte fields in the database file header are stored with the most significant byte first (big-endian).".to_string()
+    fn desc(&self) -> Text {
+        catalog::db_header()
     }
 
-    fn fields(&self) -> Vec<Field> {
-        vec![
+    fn fields(&self) -> Rc<[Field]> {
+        Rc::new([
             Field::new(
-                "Magic header string, which corresponds to the UTF-8 string: 'SQLite format 3\\000. Every valid SQLite database file begins with these 16 bytes (in hex): 53 51 4c 69 74 65 20 66 6f 72 6d 61 74 20 33 00.",
+                catalog::magic(),
                 0,
                 16,
-                Value::Text(self.header.clone()),
-            ),
-            Field::new(
-                "Page size of the database, interpreted as a big-endian integer and must be a power of two between 512 and 32786, inclusive. Starting from version 3.7.1 page size of 65536 bytes is supported, but since it won't fit in a two-byte integer, big-endian magic number 1 is used to represent it: 0x00 0x01.",
-                16,
-                2,
-                Value::U16(self.page_size),
+                Value::Text(self.magic().into_owned()),
             ),
+            Field::new(catalog::page_size(), 16, 2, Value::U16(self.page_size)),
             Field::new(
-                "write version: 1 for legacy, 2 for WAL",
+                catalog::write_version(),
                 18,
                 1,
                 Value::U8(self.write_version),
             ),
+            Field::new(catalog::read_version(), 19, 1, Value::U8(self.read_version)),
             Field::new(
-                "read version: 1 for legacy, 2 for WAL",
-                19,
-                1,
-                Value::U8(self.read_version),
-            ),
-            Field::new(
-                "每页尾部保留的字节数，通常为0， 如果设置为非0，则这些字节不会用于存储数据",
+                catalog::reserved_space(),
                 20,
                 1,
-                Value::U8(self.reserved_page_size)
+                Value::U8(self.reserved_page_size),
             ),
             Field::new(
-                "定义 B-Tree 叶节点中嵌入负载数据的最大比例，must be 64",
+                catalog::max_payload_fraction(),
                 21,
                 1,
-                Value::U8(self.max_embeded_payload_fraction)
+                Value::U8(self.max_embeded_payload_fraction),
             ),
             Field::new(
-                "定义 B-Tree 叶节点中嵌入负载数据的最小比例，must be 32",
+                catalog::min_payload_fraction(),
                 22,
                 1,
-                Value::U8(self.min_embeded_payload_fraction)
+                Value::U8(self.min_embeded_payload_fraction),
             ),
             Field::new(
-                "叶节点负载数据比例， must be 32",
+                catalog::leaf_payload_fraction(),
                 23,
                 1,
-                Value::U8(self.leaf_payload_fraction)
+                Value::U8(self.leaf_payload_fraction),
             ),
             Field::new(
-                "每次修改数据库文件时递增，用于检测是否有其他进程修改了数据库。当另一个进程修改数据库时，通常希望刷新其数据页的缓存，因其已经更新。WAL模式：使用wal-index检测数据库的更改。",
+                catalog::file_change_counter(),
                 24,
                 4,
                 Value::U32(self.file_change_counter),
             ),
+            Field::new(catalog::db_size(), 28, 4, Value::U32(self.db_size)),
             Field::new(
-                "数据库文件大小（以页为单位），指示数据库当前包含的页数",
-                28,
-                4,
-                Value::U32(self.db_size)
-            ),
-            Field::new(
-                "第一个空闲列表主干页的页码。指向空闲列表的第一个页面，用于记录未分配的页面",
+                catalog::first_freelist_trunk(),
                 32,
                 4,
-                Value::U32(self.first_freelist_trunk_page_number)
+                Value::U32(self.first_freelist_trunk_page_number),
             ),
             Field::new(
-                "空闲列表中的总页数。统计当前数据库中的空闲页数量",
+                catalog::freelist_count(),
                 36,
                 4,
-                Value::U32(self.total_number_of_freelist_pages)
+                Value::U32(self.total_number_of_freelist_pages),
             ),
             Field::new(
-                "the schema cookie，用于验证模式是否发生改变，每次模式改变时会递增",
+                catalog::schema_cookie(),
                 40,
                 4,
-                Value::U32(self.schema_cookie)
+                Value::U32(self.schema_cookie),
             ),
             Field::new(
-                "指示当前数据库的模式版本，支持的值为1，2，3，4",
+                catalog::schema_format(),
                 44,
                 4,
-                Value::U32(self.schema_format)
+                Value::U32(self.schema_format),
             ),
             Field::new(
-                "默认页缓存大小",
+                catalog::default_cache_size(),
                 48,
                 4,
-                Value::U32(self.default_page_cache_size)
+                Value::U32(self.default_page_cache_size),
             ),
             Field::new(
-                "最大根 B-Tree 页的页码。在自动清理（auto-vacuum）或增量清理（incremental-vacuum）模式下使用；否则为 0。",
+                catalog::largest_root_page(),
                 52,
                 4,
-                Value::U32(self.lagest_root_btree_page_number)
+                Value::U32(self.lagest_root_btree_page_number),
             ),
             Field::new(
-                "指定数据使用的文本编码,1 means UTF-8.2 means UTF-16le.3 means UTF-16be.",
+                catalog::text_encoding(),
                 56,
                 4,
-                Value::U32(self.text_encoding)
+                Value::U32(self.text_encoding),
             ),
             Field::new(
-                "用户版本，用户可通过 PRAGMA user_version 读写此值",
+                catalog::user_version(),
                 60,
                 4,
-                Value::U32(self.user_version)
+                Value::U32(self.user_version),
             ),
             Field::new(
-                "是否启用了增量清理模式, 非0表示启用，0表示禁用",
+                catalog::incremental_vacuum(),
                 64,
                 4,
-                Value::U32(self.is_incremental_vacuum_mode)
+                Value::U32(self.is_incremental_vacuum_mode),
             ),
             Field::new(
-                "应用程序id， 通过 PRAGMA application_id 设置，用于存储应用程序标识符",
+                catalog::application_id(),
                 68,
                 4,
-                Value::U32(self.application_id)
+                Value::U32(self.application_id),
             ),
             Field::new(
-                "为未来扩展预留，必须填充为0",
+                catalog::expansion_reserved(),
                 72,
                 20,
-                Value::Text(String::from_utf8_lossy(&self.expansion_reserved).to_string())
+                Value::Text(String::from_utf8_lossy(&self.expansion_reserved).to_string()),
             ),
             Field::new(
-                "版本有效数字。指示上次写入操作的事物ID，用于数据库一致性",
+                catalog::version_valid_for(),
                 92,
                 4,
-                Value::U32(self.version_valid_for)
+                Value::U32(self.version_valid_for),
             ),
             Field::new(
-                "SQLite 版本号。例如，版本3.35.5表示为3035005",
+                catalog::sqlite_version(),
                 96,
                 4,
-                Value::Version(self.sqlite_version_number)
-            )
-        ]
+                Value::Version(self.sqlite_version_number),
+            ),
+        ])
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Field {
-    pub desc: &'static str,
+    /// 英文和中文的描述，克隆 Field 时不复制字符串
+    pub desc: Text,
     pub offset: usize,
     pub size: usize,
     pub value: Value,
@@ -170,36 +178,184 @@ pub struct Field {
 impl Field {
     /// 将Filed的value转换成16进制字符串
     pub fn to_hex(&self) -> String {
-        let pretty_hex = |bytes: &[u8]| -> String {
-            bytes
-                .iter()
-                .map(|b| format!("{:02X}", b))
-                .collect::<Vec<String>>()
-                .join(" ")
-        };
+        self.to_bytes()
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Field 的值按文件中的格式编码后的字节
+    pub fn to_bytes(&self) -> Vec<u8> {
         match &self.value {
-            Value::U8(v) => pretty_hex(&v.to_be_bytes()),
-            Value::U16(v) => pretty_hex(&v.to_be_bytes()),
-            Value::U32(v) => pretty_hex(&v.to_be_bytes()),
-            Value::Text(v) => pretty_hex(v.as_bytes()),
-            Value::Version(v) => pretty_hex(&v.to_be_bytes()),
-            Value::Bool(v) => pretty_hex(&v.to_be_bytes()),
-            Value::Encoding(v) => pretty_hex(&v.to_be_bytes()),
-            Value::Array(v) => pretty_hex(&v),
+            Value::U8(v) => v.to_be_bytes().to_vec(),
+            Value::U16(v) => v.to_be_bytes().to_vec(),
+            Value::U32(v) => v.to_be_bytes().to_vec(),
+            Value::Text(v) => v.as_bytes().to_vec(),
+            Value::Version(v) => v.to_be_bytes().to_vec(),
+            Value::Bool(v) => v.to_be_bytes().to_vec(),
+            Value::Encoding(v) => v.to_be_bytes().to_vec(),
+            Value::Array(v) => v.to_vec(),
+            Value::Varint(v) => encode_varint(*v),
+            // record 中的整数按 Field 的大小截取低位字节
+            Value::I64(v) => v.to_be_bytes()[8 - self.size.min(8)..].to_vec(),
+            Value::F64(v) => v.to_be_bytes().to_vec(),
+            Value::Null => vec![],
+        }
+    }
+
+    /// 把用户输入解析成与原值同类型的新值，并编码成恰好 size 个字节。
+    /// 文本不足 size 字节时用 0 补齐。
+    pub fn encode(&self, input: &str) -> Result<Vec<u8>> {
+        let input = input.trim();
+        if self.size == 0 {
+            bail!("The value is implied by its serial type and takes no bytes");
+        }
+        let value = match &self.value {
+            Value::U8(_) => Value::U8(parse_int(input)?),
+            Value::Bool(_) => Value::Bool(match input {
+                "true" => 1,
+                "false" => 0,
+                _ => parse_int(input)?,
+            }),
+            Value::U16(_) => Value::U16(parse_int(input)?),
+            Value::U32(_) => Value::U32(parse_int(input)?),
+            Value::Version(_) => Value::Version(parse_version(input)?),
+            Value::Encoding(_) => Value::Encoding(match input.to_ascii_uppercase().as_str() {
+                "UTF-8" | "UTF8" => TextEncoding::UTF8,
+                "UTF-16 LE" | "UTF-16LE" | "UTF16LE" => TextEncoding::UTF16le,
+                "UTF-16 BE" | "UTF-16BE" | "UTF16BE" => TextEncoding::UTF16be,
+                _ => TextEncoding::try_from(parse_int::<u32>(input)?).map_err(|e| anyhow!(e))?,
+            }),
+            Value::Varint(_) => Value::Varint(parse_int(input)?),
+            Value::I64(_) => {
+                let v: i64 = parse_int(input)?;
+                let bits = 8 * self.size.min(8) as u32;
+                let (min, max) = (-1i64 << (bits - 1), (1i64 << (bits - 1)) - 1);
+                if bits < 64 && !(min..=max).contains(&v) {
+                    bail!(
+                        "{v} does not fit in {} byte(s), the range is {min} to {max}",
+                        self.size
+                    );
+                }
+                Value::I64(v)
+            }
+            Value::F64(_) => Value::F64(
+                input
+                    .parse()
+                    .map_err(|_| anyhow!("{input:?} is not a number"))?,
+            ),
+            Value::Text(_) => {
+                let mut text = input.to_string();
+                if text.len() > self.size {
+                    bail!(
+                        "The text is {} bytes, at most {} bytes fit",
+                        text.len(),
+                        self.size
+                    );
+                }
+                text.extend(std::iter::repeat_n('\0', self.size - text.len()));
+                Value::Text(text)
+            }
+            Value::Array(_) => Value::Array(parse_hex(input)?.into()),
+            Value::Null => bail!("NULL can't be edited"),
+        };
+        let bytes = Field {
+            value,
+            ..self.clone()
+        }
+        .to_bytes();
+        if bytes.len() != self.size {
+            bail!(
+                "The new value takes {} byte(s), but the field is {} byte(s)",
+                bytes.len(),
+                self.size
+            );
         }
+        Ok(bytes)
+    }
+}
+
+/// 可编辑的整数类型的取值范围
+trait Bounded {
+    const MIN: i128;
+    const MAX: i128;
+}
+
+macro_rules! bounded {
+    ($($t:ty),*) => {
+        $(impl Bounded for $t {
+            const MIN: i128 = <$t>::MIN as i128;
+            const MAX: i128 = <$t>::MAX as i128;
+        })*
+    };
+}
+
+bounded!(u8, u16, u32, i64);
+
+/// 解析十进制或 0x 开头的十六进制整数，超出类型范围时报错
+fn parse_int<T: TryFrom<i128> + Bounded>(input: &str) -> Result<T> {
+    let v = match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => input.parse::<i128>(),
+    }
+    .map_err(|_| anyhow!("{input:?} is not an integer"))?;
+    T::try_from(v).map_err(|_| anyhow!("{v} is out of range {} to {}", T::MIN, T::MAX))
+}
+
+/// 版本号可以写成 3.45.1 或者 3045001
+fn parse_version(input: &str) -> Result<u32> {
+    let parts: Vec<&str> = input.split('.').collect();
+    if parts.len() == 1 {
+        return parse_int(input);
+    }
+    let [a, b, c] = parts[..] else {
+        bail!("{input:?} is not a version like 3.45.1");
+    };
+    let (a, b, c): (u32, u32, u32) = (parse_int(a)?, parse_int(b)?, parse_int(c)?);
+    if b > 999 || c > 999 {
+        bail!("Minor and patch versions must be below 1000");
     }
+    a.checked_mul(1_000_000)
+        .and_then(|a| a.checked_add(b * 1000 + c))
+        .ok_or_else(|| anyhow!("{input:?} is out of range"))
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// 空白分隔或连续的十六进制字节，例如 "00 FF" 或 "00ff"
+fn parse_hex(input: &str) -> Result<Vec<u8>> {
+    let digits: String = input.split_whitespace().collect();
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("{input:?} is not hex");
+    }
+    if !digits.len().is_multiple_of(2) {
+        bail!("{input:?} has an odd number of hex digits");
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| anyhow!("{:?} is not a hex byte", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Value {
     U8(u8),
     Bool(u8),
     U16(u16),
     U32(u32),
-    Array(Box<[u8]>),
+    Array(Bytes),
     Text(String),
     Encoding(TextEncoding),
     Version(u32),
+    Varint(i64),
+    I64(i64),
+    F64(f64),
+    Null,
 }
 
 impl std::fmt::Display for Value {
@@ -209,9 +365,13 @@ impl std::fmt::Display for Value {
             Self::Bool(v) => write!(f, "{:?}", *v != 0),
             Self::U16(v) => write!(f, "{v}"),
             Self::U32(v) => write!(f, "{v}"),
-            Self::Array(v) => write!(f, "{:?}", *v),
+            Self::Array(v) => write!(f, "{:?}", &**v),
             Self::Text(v) => write!(f, "{:?}", v),
             Self::Encoding(v) => write!(f, "{v}"),
+            Self::Varint(v) => write!(f, "{v}"),
+            Self::I64(v) => write!(f, "{v}"),
+            Self::F64(v) => write!(f, "{v}"),
+            Self::Null => write!(f, "NULL"),
             Self::Version(mut v) => {
                 // 3 \times 10^6 + 35 \times 10^3 + 5 = 3035005
 
@@ -227,7 +387,7 @@ impl std::fmt::Display for Value {
 }
 
 impl Field {
-    pub fn new(desc: &'static str, offset: usize, size: usize, value: Value) -> Self {
+    pub fn new(desc: Text, offset: usize, size: usize, value: Value) -> Self {
         Self {
             desc,
             offset,
@@ -244,11 +404,46 @@ mod tests {
     #[test]
     fn field_to_hex_works() {
         let field = Field::new(
-            "Page size of the database, interpreted as a big-endian integer and must be a power of two between 512 and 32786, inclusive. Starting from version 3.7.1 page size of 65536 bytes is supported, but since it won't fit in a two-byte integer, big-endian magic number 1 is used to represent it: 0x00 0x01.",
+            catalog::page_size(),
             16,
             2,
-            Value::Text("SQLite format 3\0".to_string())
+            Value::Text("SQLite format 3\0".to_string()),
         );
         println!("{}", field.to_hex());
     }
+
+    #[test]
+    fn field_encode_validates_range() {
+        let field = Field::new(Text::default(), 18, 1, Value::U8(1));
+        assert_eq!(field.encode("2").unwrap(), [2]);
+        assert_eq!(field.encode("0xff").unwrap(), [255]);
+        assert!(field.encode("256").is_err());
+        assert!(field.encode("-1").is_err());
+        assert!(field.encode("two").is_err());
+
+        let version = Field::new(Text::default(), 96, 4, Value::Version(3045001));
+        assert_eq!(version.encode("3.46.0").unwrap(), 3046000u32.to_be_bytes());
+
+        let magic = Field::new(
+            Text::default(),
+            0,
+            16,
+            Value::Text("SQLite format 3\0".to_string()),
+        );
+        assert_eq!(magic.encode("SQLite").unwrap()[6..], [0; 10]);
+        assert!(magic.encode("SQLite format 3 and more").is_err());
+
+        let int = Field::new(Text::default(), 0, 1, Value::I64(5));
+        assert_eq!(int.encode("-128").unwrap(), [0x80]);
+        assert!(int.encode("128").is_err());
+        assert!(Field::new(Text::default(), 0, 0, Value::I64(1))
+            .encode("0")
+            .is_err());
+
+        let varint = Field::new(Text::default(), 0, 1, Value::Varint(5));
+        assert!(varint.encode("300").is_err());
+        let array = Field::new(Text::default(), 0, 2, Value::Array(vec![0, 0].into()));
+        assert_eq!(array.encode("0a FF").unwrap(), [10, 255]);
+        assert!(array.encode("é0").is_err());
+    }
 }
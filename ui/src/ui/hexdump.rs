@@ -0,0 +1,195 @@
+#![allow(non_snake_case)]
+
+use std::ops::Range;
+
+use dioxus::prelude::*;
+
+use crate::parser::SpanKind;
+
+use super::{state::AppState, Field};
+
+/// 每行的字节数
+pub const ROW: usize = 16;
+
+/// 字节按所属结构分组着色：(图例中的名字, 背景色)
+const GROUPS: [(&str, &str); 8] = [
+    ("Header", "bg-sky-200"),
+    ("Cell pointer", "bg-amber-200"),
+    ("Free space", "bg-gray-200"),
+    ("Cell", "bg-rose-200"),
+    ("Record header", "bg-violet-200"),
+    ("Column", "bg-green-200"),
+    ("Overflow", "bg-orange-200"),
+    ("Freelist", "bg-stone-300"),
+];
+
+/// 结构在 GROUPS 中的分组
+fn group(kind: SpanKind) -> usize {
+    use SpanKind::*;
+    match kind {
+        Page | DatabaseHeader | PageHeader => 0,
+        CellPointerArray | CellPointer => 1,
+        Unallocated | Freeblock | Fragment | Reserved => 2,
+        Cell | LeftChild | PayloadSize | Rowid | OverflowPointer | Payload => 3,
+        Record | RecordHeader | HeaderSize | SerialType => 4,
+        Column => 5,
+        OverflowNext | OverflowContent | OverflowUnused => 6,
+        FreelistNext | FreelistCount | FreelistLeafPointer | FreelistUnused | FreelistLeaf => 7,
+    }
+}
+
+/// range 中每个字节所在的最小的字段在 fields 中的位置
+fn field_map(fields: &[Field], range: Range<usize>) -> Vec<Option<usize>> {
+    let mut owners = vec![None; range.len()];
+    let mut order: Vec<usize> = (0..fields.len()).collect();
+    // 大的字段先填，包含在其中的小字段覆盖它
+    order.sort_by_key(|&i| std::cmp::Reverse(fields[i].size));
+    for i in order {
+        let field = &fields[i];
+        let start = field.offset.max(range.start);
+        let end = (field.offset + field.size).min(range.end);
+        if start < end {
+            owners[start - range.start..end - range.start].fill(Some(i));
+        }
+    }
+    owners
+}
+
+/// 选中部分的十六进制转储：偏移、每行 16 字节和 ASCII。字节按所属结构着色，
+/// 悬停在字节上选中包含它的字段，选中的字段的字节高亮显示。
+pub fn HexDump() -> Element {
+    let state = use_context::<AppState>();
+    let mut selected_field = state.selected_field;
+    let viewer = state.viewer.read();
+    let part = state.selected_part.read().clone();
+    let Some(range) = viewer.part_range(&part) else {
+        return rsx! {
+            div {
+                class: "p-4 text-xs",
+                "Only the database header and pages have a hex dump."
+            }
+        };
+    };
+    let bytes = viewer.bytes.clone();
+    let range = range.start..range.end.min(bytes.len());
+    let kinds = viewer.innermost(range.clone());
+    let fields = part.fields();
+    let owners = field_map(&fields, range.clone());
+    let selected = selected_field
+        .read()
+        .as_ref()
+        .map(|f| f.offset..f.offset + f.size)
+        .unwrap_or_default();
+    let start = range.start;
+
+    rsx! {
+        div {
+            class: "p-4 font-mono text-xs overflow-auto",
+            div {
+                class: "flex flex-wrap gap-2 pb-2 font-sans",
+                for (name, colour) in GROUPS {
+                    span {
+                        class: "px-1 {colour}",
+                        "{name}"
+                    }
+                }
+            }
+            for row in range.clone().step_by(ROW) {
+                HexRow {
+                    offset: row,
+                    ascii: rsx! {
+                        for i in row..(row + ROW).min(range.end) {
+                            span {
+                                class: if selected.contains(&i) {"bg-accent text-accent-content"},
+                                "{ascii(bytes[i])}"
+                            }
+                        }
+                    },
+                    for i in row..(row + ROW).min(range.end) {
+                        span {
+                            class: "w-5 text-center",
+                            class: if selected.contains(&i) {
+                                "bg-accent text-accent-content font-bold"
+                            } else {
+                                kinds[i - start].map_or("", |k| GROUPS[group(k)].1)
+                            },
+                            onmouseover: {
+                                let fields = fields.clone();
+                                let owner = owners[i - start];
+                                move |_| {
+                                    if let Some(owner) = owner {
+                                        *selected_field.write() = Some(fields[owner].clone());
+                                    }
+                                }
+                            },
+                            "{bytes[i]:02X}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 十六进制转储和十六进制编辑器中的一行：偏移、children 中每个字节的十六进制，然后是 ascii
+#[component]
+pub fn HexRow(offset: usize, ascii: Element, children: Element) -> Element {
+    rsx! {
+        div {
+            class: "flex space-x-2",
+            div {
+                class: "w-16 text-green-700",
+                "{offset:08X}"
+            }
+            div {
+                class: "flex",
+                {children}
+            }
+            div {
+                class: "whitespace-pre",
+                {ascii}
+            }
+        }
+    }
+}
+
+/// 可打印的 ASCII 字符原样显示，其它字节显示为 .
+pub fn ascii(b: u8) -> char {
+    if b.is_ascii_graphic() || b == b' ' {
+        b as char
+    } else {
+        '.'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::Viewer;
+
+    #[test]
+    fn bytes_belong_to_the_smallest_field() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        let page = viewer.page_part(2).unwrap();
+        let range = viewer.part_range(&page).unwrap();
+        let fields = page.fields();
+        let owners = field_map(&fields, range.clone());
+        assert_eq!(owners.len(), range.len());
+        for (offset, owner) in range.zip(owners) {
+            let Some(owner) = owner else {
+                continue;
+            };
+            let field = &fields[owner];
+            assert!(field.offset <= offset && offset < field.offset + field.size);
+            // 没有更小的字段包含这个字节
+            assert!(fields
+                .iter()
+                .filter(|f| f.offset <= offset && offset < f.offset + f.size)
+                .all(|f| f.size >= field.size));
+        }
+        // 数据库头的第 16 字节属于页大小字段
+        let header = viewer.part_range(&viewer.first_part()).unwrap();
+        let owners = field_map(&viewer.first_part().fields(), header);
+        assert_eq!(owners[16], Some(1));
+    }
+}
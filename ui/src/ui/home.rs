@@ -1,23 +1,86 @@
-use crate::{
-    parser::Reader,
-    ui::{header::Field, state::AppState, viewer::Viewer},
+#![allow(non_snake_case)]
+
+use crate::ui::{
+    analysis::{AnalysisReport, ANALYSIS_LABEL},
+    diagnostics::{DiagnosticsList, DIAGNOSTICS_LABEL},
+    diagram::BTreeDiagram,
+    diff::{CompareSelect, CompareView},
+    edit::{EditStatus, FieldEditor, HexEditor},
+    export::ExportButton,
+    header::{Field, Parts},
+    hexdump::HexDump,
+    inspector::Inspector,
+    lookup::{step_highlights, Highlight, TreeLookup},
+    open::{AddYoursButton, DropZone},
+    route::Route,
+    search::SearchBar,
+    state::AppState,
+    tree::{Node, TreeNode},
 };
 use dioxus::prelude::*;
+use dioxus_logger::tracing::error;
 
 use super::state::Format;
+/// 所有地址共用的布局。地址变化时跟随它改变选择，选择变化时更新地址。
 #[component]
 pub fn Home() -> Element {
+    let state = use_context::<AppState>();
+    let route = use_route::<Route>();
+    // 打开页面时的地址还没有被跟随，这之前不能用选择覆盖它
+    let mut followed = use_signal(|| false);
+    // 跟随失败的地址，例如指向还没有打开的文件。保留它，打开文件后再跟随一次
+    let mut pending = use_signal(|| None::<Route>);
+    use_effect(use_reactive((&route,), move |(route,)| {
+        // 在任务中读取状态，effect 只在地址变化时重新运行
+        spawn(async move {
+            match state.follow(&route) {
+                Ok(()) => {
+                    pending.set(None);
+                    followed.set(true);
+                }
+                Err(e) => {
+                    let mut open_error = state.open_error;
+                    *open_error.write() = Some(format!("{e:#}"));
+                    pending.set(Some(route));
+                }
+            }
+        });
+    }));
+    use_effect(move || {
+        // 打开的文件变化时重新运行
+        state.user_dbs.read();
+        let Some(route) = pending.peek().clone() else {
+            return;
+        };
+        spawn(async move {
+            if let Err(e) = state.follow(&route) {
+                error!("{e:#}");
+            }
+            pending.set(None);
+            followed.set(true);
+        });
+    });
+    use_effect(move || {
+        if !followed() {
+            return;
+        }
+        let link = state.link();
+        if !state.at(&router().current::<Route>()) {
+            navigator().replace(link);
+        }
+    });
     rsx! {
-        Header {}
-        Body {}
+        DropZone {
+            Header {}
+            Body {}
+        }
     }
 }
 
 pub fn Header() -> Element {
-    let mut current_db = use_context::<AppState>().current_db;
-    let mut viewer = use_context::<AppState>().viewer;
-    let mut selected_part = use_context::<AppState>().selected_part;
-    let mut selected_field = use_context::<AppState>().selected_field;
+    let state = use_context::<AppState>();
+    let current_db = state.current_db;
+    let open_error = state.open_error;
     rsx! {
         div {
             class: "h-12 flex items-center bg-primary",
@@ -34,7 +97,7 @@ pub fn Header() -> Element {
 
                 div {
                     class: "join-item btn btn-secondary tracking-tighter font-bold",
-                    "Example database"
+                    "Database"
                 }
 
                 // 下拉菜单选择对应数据库
@@ -42,37 +105,53 @@ pub fn Header() -> Element {
                     class: "join-item select select-secondary select-bordered font-bold tracking-tighter",
 
                     oninput: move |e| {
-                        match e.value().as_str() {
-                            // 选择对应的数据库
-                            name => {
-                                *current_db.write() = name.to_string();
-                                let new_viewer = Viewer::new_from_included(name).expect("Viewer failed");
-                                let first_part = new_viewer.first_part();
-                                *selected_part.write() = first_part;
-                                *selected_field.write() = None;
-                                *viewer.write() = new_viewer;
-                            }
-                        };
+                        // 选择对应的数据库
+                        if let Err(e) = state.open_db(&e.value()) {
+                            error!("Failed to open database: {e:#}");
+                        }
                     },
-                    // 设置不同的数据库选项
-                    for name in viewer.read().included_dbnames() {
+                    // 设置不同的数据库选项：内置的示例和用户打开的文件
+                    for name in state.db_names() {
                         option {
-                            selected: if *name == current_db() {"true"},
+                            selected: if name == current_db() {"true"},
                             "{name}",
                         }
                     }
                 }
             }
+            CompareSelect {}
             div {class: "flex-grow"}
-            div {
-                class: "btn btn-ghost tracking-tighter font-bold",
-                "Add Yours",
+            if let Some(e) = open_error() {
+                div {
+                    class: "text-error font-bold tracking-tighter px-2",
+                    "{e}"
+                }
             }
+            LanguageButton {}
+            ExportButton {}
+            AddYoursButton {}
+        }
+    }
+}
+
+/// 切换说明文字的语言，按钮上是切换后的语言
+fn LanguageButton() -> Element {
+    let mut lang = use_context::<AppState>().lang;
+    rsx! {
+        button {
+            class: "btn btn-ghost tracking-tighter font-bold",
+            title: "Language of the descriptions",
+            onclick: move |_| {
+                let other = lang().other();
+                lang.set(other);
+            },
+            "{lang().other().name()}"
         }
     }
 }
 
 pub fn Body() -> Element {
+    let selected_part = use_context::<AppState>().selected_part;
     rsx! {
         div {
             class: "flex w-full",
@@ -85,11 +164,25 @@ pub fn Body() -> Element {
 
             div {
                 class: "flex flex-col w-full",
+                EditStatus {}
                 div {
                     Description {}
                 }
                 div {
-                    Visual {}
+                    TreeLookup {}
+                }
+                div {
+                    SearchBar {}
+                }
+                div {
+                    CompareView {}
+                }
+                div {
+                    match selected_part.read().label().as_str() {
+                        ANALYSIS_LABEL => rsx! { AnalysisReport {} },
+                        DIAGNOSTICS_LABEL => rsx! { DiagnosticsList {} },
+                        _ => rsx! { Visual {} },
+                    }
                 }
                 div {class: "flex-grow" }
             }
@@ -98,31 +191,76 @@ pub fn Body() -> Element {
 }
 
 /// 展示解析出的数据库结构（Parts），
-/// 用户可以点击以查看详细信息。
+/// 用户可以点击以查看详细信息。有伴随文件时按文件分组。
 pub fn SideBar() -> Element {
-    let viewer = use_context::<AppState>().viewer;
-    let parts = viewer.read().parts.clone();
-    let mut selected_part = use_context::<AppState>().selected_part;
-    let mut selected_field = use_context::<AppState>().selected_field;
+    let state = use_context::<AppState>();
+    let viewer = state.viewer;
+    let descent = state.descent;
+    let descent_step = state.descent_step;
+    let apply_wal = state.apply_wal;
+    // 查找路径上已经走过的页
+    let path: Vec<String> = descent
+        .read()
+        .as_ref()
+        .map(|d| {
+            d.steps
+                .iter()
+                .take(descent_step() + 1)
+                .filter_map(|s| viewer.read().reader.page(s.page))
+                .map(|p| p.label())
+                .collect()
+        })
+        .unwrap_or_default();
+    // (文件名, 说明, 文件中的 parts)，只有主文件时不显示文件名
+    let mut groups = vec![(None, None, viewer.read().tree.clone())];
+    for file in viewer.read().companions.iter() {
+        groups[0].0 = Some(state.current_db.read().clone());
+        groups.push((
+            Some(file.name.clone()),
+            file.note.clone(),
+            file.parts.clone(),
+        ));
+    }
     rsx! {
         div {
-            class: "rounded-box p-4 h-[calc(100vh-48px)] w-fit",
+            class: "rounded-box p-4 h-[calc(100vh-48px)] w-fit overflow-auto",
             div {
                 class: "font-bold truncate pb-4",
                 "Structure",
             }
-            ul {
-                for part in parts {
-                    li {
-                        button {
-                            class: "w-full text-left btn-sm btn-ghost btn-block font-normal truncate",
-                            class: if selected_part.read().label() == part.label() {"btn-active"},
-                            onclick: move |_| {
-                                *selected_part.write() = part.clone();
-                                *selected_field.write() = None;
-                            },
-                            "+ {&part.label()}",
-                        }
+            if viewer.read().has_wal() {
+                label {
+                    class: "label cursor-pointer justify-start gap-2 pb-4 text-sm",
+                    title: "Show the database as SQLite reads it, with the committed WAL frames copied back",
+                    input {
+                        class: "checkbox checkbox-sm",
+                        r#type: "checkbox",
+                        checked: apply_wal(),
+                        onchange: move |e| {
+                            if let Err(e) = state.set_apply_wal(e.checked()) {
+                                error!("Failed to apply the WAL: {e:#}");
+                            }
+                        },
+                    }
+                    "Apply WAL"
+                }
+            }
+            for (name, note, parts) in groups {
+                if let Some(name) = name {
+                    div {
+                        class: "font-bold text-sm truncate pt-2",
+                        "{name}"
+                    }
+                }
+                if let Some(note) = note {
+                    div {
+                        class: "text-xs opacity-70 max-w-56",
+                        "{note}"
+                    }
+                }
+                ul {
+                    for part in parts {
+                        TreeNode { node: Node(part), path: path.clone() }
                     }
                 }
             }
@@ -135,28 +273,43 @@ pub fn SideBar() -> Element {
 pub fn Description() -> Element {
     let selected_part = use_context::<AppState>().selected_part;
     let selected_field = use_context::<AppState>().selected_field;
+    let viewer = use_context::<AppState>().viewer;
+    let lang = use_context::<AppState>().lang;
+    let desc = selected_part.read().desc();
     match selected_field() {
         None => {
             rsx! {
                 div {
                     class: "p-5 h-72 w-full overflow-auto",
-                    "{selected_part().desc()}"
+                    "{desc.get(lang())}"
                 }
             }
         }
         Some(field) => {
+            // 从外到内覆盖该字段第一个字节的结构，伴随文件中的字段只属于选中的部分
+            let structures = if viewer.read().in_main_file(&selected_part.read()) {
+                viewer
+                    .read()
+                    .explain(field.offset)
+                    .into_iter()
+                    .map(|s| s.label)
+                    .collect::<Vec<_>>()
+                    .join(" › ")
+            } else {
+                selected_part.read().label()
+            };
             rsx! {
                 div {
                     class: "p-5 h-72 w-full overflow-auto",
                     div {
-                        "{selected_part().desc()}"
+                        "{desc.get(lang())}"
                     }
                     div {
                         class: "flex pt-6 text-sm space-x-6",
                         // 域的描述
                         div {
                             class: "w-1/2",
-                            "{field.desc}"
+                            "{field.desc.get(lang())}"
                         }
                         // 域的详细信息
                         div {
@@ -196,6 +349,14 @@ pub fn Description() -> Element {
                                             "{field.to_hex()}"
                                         }
                                     }
+                                    tr {
+                                        td {
+                                            "Structure"
+                                        }
+                                        td {
+                                            "{structures}"
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -207,16 +368,49 @@ pub fn Description() -> Element {
 }
 
 /// 按字段显示当前选中pair的field内容，
-/// 提供切换格式化模式的按钮。
+/// 提供切换格式化模式的按钮。双击字段可以修改它的值。
 pub fn Visual() -> Element {
     let selected_part = use_context::<AppState>().selected_part;
     let fields = selected_part().fields();
     let mut selected_field = use_context::<AppState>().selected_field;
     let mut formatting = use_context::<AppState>().format;
+    let viewer = use_context::<AppState>().viewer;
+    let descent = use_context::<AppState>().descent;
+    let descent_step = use_context::<AppState>().descent_step;
+    let edit = use_context::<AppState>().edit;
+    let mut hex_editor = use_context::<AppState>().hex_editor;
+    let mut hex_dump = use_context::<AppState>().hex_dump;
+    let mut inspector = use_context::<AppState>().inspector;
+    let mut diagram = use_context::<AppState>().diagram;
+    // 正在编辑的字段的偏移
+    let mut editing = use_signal(|| None::<usize>);
+    // 伴随文件中的字段不是主文件中的字节，不能编辑
+    let editable = viewer.read().in_main_file(&selected_part.read());
+
+    // 当前页在查找路径上时，高亮比较过和选中的单元
+    let highlights = descent
+        .read()
+        .as_ref()
+        .and_then(|d| d.steps.get(descent_step()).cloned())
+        .and_then(|step| {
+            let page = viewer.read().reader.page(step.page)?;
+            (page.label() == selected_part.read().label()).then(|| step_highlights(&page, &step))
+        })
+        .unwrap_or_default();
 
     rsx! {
         div {
             class: "flex items-center bg-secondary",
+            div {
+                class: "px-4 text-xs tracking-tighter",
+                if !editable {
+                    "Companion files are read-only"
+                } else if hex_editor() {
+                    "Click a byte and type two hex digits to change it"
+                } else {
+                    "Double-click a field to edit it"
+                }
+            }
             div { class: "flex-grow" }
             div {
                 class: "btn btn-xs btn-ghost tracking-tighter font-bold",
@@ -244,28 +438,107 @@ pub fn Visual() -> Element {
                 },
                 "Text"
             }
+
+            div {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                class: if hex_dump() {"btn-active"},
+                onclick: move |_| {
+                    let active = hex_dump();
+                    *hex_dump.write() = !active;
+                },
+                "Hex dump"
+            }
+
+            div {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                class: if inspector() {"btn-active"},
+                onclick: move |_| {
+                    let active = inspector();
+                    *inspector.write() = !active;
+                },
+                "Inspector"
+            }
+
+            div {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                class: if diagram() {"btn-active"},
+                onclick: move |_| {
+                    let active = diagram();
+                    *diagram.write() = !active;
+                },
+                "B-tree"
+            }
+
+            div {
+                class: "btn btn-xs btn-ghost tracking-tighter font-bold",
+                class: if hex_editor() {"btn-active"},
+                onclick: move |_| {
+                    let active = hex_editor();
+                    *hex_editor.write() = !active;
+                },
+                "Hex editor"
+            }
         }
 
-        div {
-            class: "flex flex-wrap p-4 text-xs",
-            for field in fields {
+        if hex_editor() {
+            HexEditor {}
+        } else {
+            div {
+                class: "flex",
                 div {
-                    div {
-                        class: "mb-0 mt-1 leading-tight tracking-tighter font-medium text-green-700",
-                        "{field.offset}"
+                    class: "flex flex-wrap content-start p-4 text-xs",
+                    class: if hex_dump() {"w-1/2"},
+                    for field in fields.iter().cloned() {
+                        div {
+                            class: match highlights.iter().find(|(r, _)| r.contains(&field.offset)).map(|h| h.1) {
+                                Some(Highlight::Chosen) => "ring-2 ring-accent",
+                                Some(Highlight::Probe) => "ring-1 ring-warning",
+                                None => "",
+                            },
+                            div {
+                                class: "mb-0 mt-1 leading-tight tracking-tighter font-medium text-green-700",
+                                "{field.offset}"
+                            }
+                            div {
+                                class: "p-1 outline outline-1 outline-secondary hover:bg-secondary border-t-2",
+                                // 悬停在十六进制转储中时选中的字段
+                                class: if selected_field.read().as_ref().is_some_and(|f| f.offset == field.offset && f.size == field.size) {"bg-accent"} else {"bg-primary"},
+                                // 修改过的字段
+                                class: if editable && edit.read().changed(field.offset..field.offset + field.size) {"border-error"} else {"border-green-700"},
+                                // 选中时，显示filed的Description
+                                onmouseover: {
+                                    let field = field.clone();
+                                    move |_| *selected_field.write() = Some(field.clone())
+                                },
+                                // 双击编辑
+                                ondoubleclick: move |_| if editable {
+                                    *editing.write() = Some(field.offset)
+                                },
+                                if editing() == Some(field.offset) {
+                                    FieldEditor {field: field.clone(), editing}
+                                } else {
+                                    FormattedValue {field: field.clone()}
+                                }
+                            }
+
+                        }
                     }
+                }
+                if hex_dump() {
                     div {
-                        class: "p-1 outline outline-1 outline-secondary bg-primary hover:bg-secondary border-t-2 border-green-700",
-                        // 选中时，显示filed的Description
-                        onmouseover: move |_| {
-                            *selected_field.write() = Some(field.clone());
-                        },
-                        FormattedValue {field: field.clone()}
+                        class: "w-1/2",
+                        HexDump {}
                     }
-
                 }
             }
         }
+
+        if inspector() {
+            Inspector {}
+        }
+        if diagram() {
+            BTreeDiagram {}
+        }
     }
 }
 
@@ -0,0 +1,155 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign},
+    rc::Rc,
+};
+
+use serde::Serialize;
+
+/// 说明文字的语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Lang {
+    /// 切换按钮上显示的另一种语言
+    pub fn other(self) -> Self {
+        match self {
+            Self::En => Self::Zh,
+            Self::Zh => Self::En,
+        }
+    }
+
+    /// 语言自己的名字
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Zh => "简体中文",
+        }
+    }
+}
+
+/// 英文和简体中文两个版本的说明。克隆时不复制字符串。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Text {
+    pub en: Rc<str>,
+    pub zh: Rc<str>,
+}
+
+impl Text {
+    pub fn new(en: impl Into<Rc<str>>, zh: impl Into<Rc<str>>) -> Self {
+        Self {
+            en: en.into(),
+            zh: zh.into(),
+        }
+    }
+
+    pub fn get(&self, lang: Lang) -> &str {
+        match lang {
+            Lang::En => &self.en,
+            Lang::Zh => &self.zh,
+        }
+    }
+
+    /// 两种语言的说明用不同的分隔符连接，例如英文的 ", " 和中文的 "、"
+    pub fn join(items: &[Text], en: &str, zh: &str) -> Self {
+        let en_items: Vec<&str> = items.iter().map(|t| &*t.en).collect();
+        let zh_items: Vec<&str> = items.iter().map(|t| &*t.zh).collect();
+        Self::new(en_items.join(en), zh_items.join(zh))
+    }
+}
+
+/// 在说明后面接上另一段说明
+impl Add for Text {
+    type Output = Text;
+
+    fn add(self, rhs: Text) -> Text {
+        Text::new(
+            format!("{}{}", self.en, rhs.en),
+            format!("{}{}", self.zh, rhs.zh),
+        )
+    }
+}
+
+impl AddAssign for Text {
+    fn add_assign(&mut self, rhs: Text) {
+        *self = std::mem::take(self) + rhs;
+    }
+}
+
+/// 目录函数的参数在某种语言的说明中显示的样子：Text 取这种语言的版本，其它值原样显示
+pub trait Localize {
+    fn localize(&self, lang: Lang) -> impl Display + '_;
+}
+
+impl Localize for Text {
+    fn localize(&self, lang: Lang) -> impl Display + '_ {
+        self.get(lang)
+    }
+}
+
+macro_rules! localize_as_is {
+    ($($t:ty),*) => {
+        $(impl Localize for $t {
+            fn localize(&self, _: Lang) -> impl Display + '_ {
+                self
+            }
+        })*
+    };
+}
+
+localize_as_is!(&str, usize, u32, i64);
+
+/// 定义目录中的说明：每条是一个返回 Text 的函数，两种语言的格式串可以引用函数的参数
+macro_rules! catalog {
+    ($(
+        $(#[$doc:meta])*
+        $name:ident($($arg:ident: $ty:ty),*) {
+            en: $en:literal,
+            zh: $zh:literal $(,)?
+        }
+    )*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name($($arg: $ty),*) -> $crate::ui::i18n::Text {
+                let en = {
+                    $(let $arg = $crate::ui::i18n::Localize::localize(&$arg, $crate::ui::i18n::Lang::En);)*
+                    format!($en)
+                };
+                let zh = {
+                    $(let $arg = $crate::ui::i18n::Localize::localize(&$arg, $crate::ui::i18n::Lang::Zh);)*
+                    format!($zh)
+                };
+                $crate::ui::i18n::Text::new(en, zh)
+            }
+        )*
+    };
+}
+
+pub(crate) use catalog;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    catalog! {
+        greeting(name: &str, place: Text) {
+            en: "Hello, {name} from {place}.",
+            zh: "{place}的{name}，你好。",
+        }
+    }
+
+    #[test]
+    fn pick_the_language() {
+        let mut text = greeting("SQLite", Text::new("the file", "文件"));
+        text += Text::new(" Bye.", "再见。");
+        assert_eq!(text.get(Lang::En), "Hello, SQLite from the file. Bye.");
+        assert_eq!(text.get(Lang::Zh), "文件的SQLite，你好。再见。");
+        let list = Text::join(&[text.clone(), text], ", ", "、");
+        assert!(list.zh.contains("。、文件"));
+        assert_eq!(Lang::En.other(), Lang::Zh);
+    }
+}
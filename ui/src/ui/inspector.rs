@@ -0,0 +1,218 @@
+#![allow(non_snake_case)]
+
+use std::{ops::Range, rc::Rc};
+
+use dioxus::prelude::*;
+
+use crate::parser::{
+    inspect::{bits, integers, Float, TextReading},
+    varint::read_varint,
+};
+
+use super::state::AppState;
+
+/// 文本和二进制位最多显示的字节数
+const TEXT_LIMIT: usize = 256;
+const BITS_LIMIT: usize = 32;
+
+/// 十进制或 0x 开头的十六进制
+fn parse_number(input: &str) -> Option<usize> {
+    let input = input.trim();
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => input.parse().ok(),
+    }
+}
+
+/// 可以检查的字节和第一个字节的偏移。主文件中的部分可以检查文件中的任意字节，
+/// 伴随文件中只有选中字段自己的字节
+fn window(state: &AppState) -> (Rc<[u8]>, usize) {
+    let viewer = state.viewer.read();
+    if viewer.in_main_file(&state.selected_part.read()) {
+        return (viewer.bytes.clone(), 0);
+    }
+    match &*state.selected_field.read() {
+        Some(field) => (field.to_bytes().into(), field.offset),
+        None => (Rc::new([]), 0),
+    }
+}
+
+/// 把选中的字节同时按各种方式解读：各种宽度和字节序的整数、varint、浮点数、文本和二进制位。
+/// 默认检查选中的字段，也可以输入任意的偏移和长度，用来分析解析器还不认识的字节。
+pub fn Inspector() -> Element {
+    let state = use_context::<AppState>();
+    let mut follow = use_signal(|| true);
+    let mut custom = use_signal(|| 0..0);
+
+    let (bytes, base) = window(&state);
+    let selection: Range<usize> = if follow() {
+        state
+            .selected_field
+            .read()
+            .as_ref()
+            .map_or(0..0, |f| f.offset..f.offset + f.size)
+    } else {
+        custom()
+    };
+    let end = base + bytes.len();
+    let start = selection.start.clamp(base, end);
+    let selection = start..selection.end.clamp(start, end);
+    let len = selection.len();
+    // 定宽的读法从选区开头读，可以超出选区
+    let at = &bytes[start - base..];
+    let selected = &bytes[start - base..selection.end - base];
+
+    let integers = integers(at);
+    let varint = read_varint(at);
+    let floats = [("Big-endian", true), ("Little-endian", false)]
+        .into_iter()
+        .filter_map(|(name, big)| Some((name, Float::read(at, big)?)));
+    let texts = TextReading::ALL.map(|t| (t.name(), t.decode(&selected[..len.min(TEXT_LIMIT)])));
+    let bits = bits(&selected[..len.min(BITS_LIMIT)]);
+    let beyond = "opacity-50";
+
+    rsx! {
+        div {
+            class: "flex items-center gap-2 px-4 py-1 bg-secondary text-xs",
+            div {
+                class: "font-bold tracking-tighter",
+                "Inspector"
+            }
+            label { "Offset" }
+            input {
+                class: "input input-xs input-bordered w-24 font-mono",
+                value: "{start}",
+                onchange: move |e| {
+                    if let Some(offset) = parse_number(&e.value()) {
+                        custom.set(offset..offset.saturating_add(len.max(1)));
+                        follow.set(false);
+                    }
+                },
+            }
+            label { "Length" }
+            input {
+                class: "input input-xs input-bordered w-16 font-mono",
+                value: "{len}",
+                onchange: move |e| {
+                    if let Some(n) = parse_number(&e.value()) {
+                        custom.set(start..start.saturating_add(n));
+                        follow.set(false);
+                    }
+                },
+            }
+            button {
+                class: "btn btn-xs btn-ghost",
+                class: if follow() {"btn-active"},
+                title: "Inspect the selected field",
+                onclick: move |_| follow.toggle(),
+                "Follow selection"
+            }
+            if len == 0 {
+                div { "Select a field or enter an offset and a length" }
+            }
+        }
+        if !at.is_empty() {
+            div {
+                class: "flex flex-wrap gap-6 p-4 text-xs",
+                table {
+                    class: "table table-xs w-auto font-mono",
+                    thead {
+                        tr {
+                            th { "Type" }
+                            th { "Big-endian" }
+                            th { "Little-endian" }
+                        }
+                    }
+                    tbody {
+                        for reading in integers {
+                            tr {
+                                class: if reading.size > len {beyond},
+                                title: if reading.size > len {"Reads past the selection"},
+                                td { "{reading.name()}" }
+                                td { "{reading.big}" }
+                                td { "{reading.little}" }
+                            }
+                        }
+                        if let Some((value, n)) = varint {
+                            tr {
+                                class: if n > len {beyond},
+                                td { "varint" }
+                                td { colspan: 2, "{value} ({n} byte(s))" }
+                            }
+                        }
+                    }
+                }
+                table {
+                    class: "table table-xs w-auto font-mono",
+                    class: if len < 8 {beyond},
+                    thead {
+                        tr {
+                            th { "f64" }
+                            th { "Value" }
+                            th { "Sign" }
+                            th { "Exponent" }
+                            th { "Mantissa" }
+                            th { "Formula" }
+                        }
+                    }
+                    tbody {
+                        for (name, float) in floats {
+                            tr {
+                                td { "{name}" }
+                                td { "{float.value}" }
+                                td { "{float.sign}" }
+                                td { "{float.exponent} (0x{float.exponent:03X})" }
+                                td { "0x{float.mantissa:013X}" }
+                                td { "{float.formula()}" }
+                            }
+                        }
+                    }
+                }
+                table {
+                    class: "table table-xs w-auto",
+                    tbody {
+                        for (name, (text, error)) in texts {
+                            tr {
+                                td { "{name}" }
+                                td {
+                                    class: "font-mono whitespace-pre-wrap break-all",
+                                    "{text:?}"
+                                }
+                                td {
+                                    class: "text-error",
+                                    if let Some(error) = error {
+                                        "Invalid at byte {error}"
+                                    }
+                                }
+                            }
+                        }
+                        tr {
+                            td { "Bits" }
+                            td {
+                                class: "font-mono break-all",
+                                colspan: 2,
+                                "{bits}"
+                                if len > BITS_LIMIT { " …" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_offsets() {
+        assert_eq!(parse_number("16"), Some(16));
+        assert_eq!(parse_number(" 0x10 "), Some(16));
+        assert_eq!(parse_number("-1"), None);
+    }
+}
@@ -0,0 +1,211 @@
+#![allow(non_snake_case)]
+
+use std::ops::Range;
+
+use dioxus::prelude::*;
+
+use crate::parser::{compare::parse_key, DescentStep, Page};
+
+use super::state::AppState;
+
+/// 查找路径上需要高亮的字节范围
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// 二分查找比较过的单元
+    Probe,
+    /// 最终选中的单元或指针
+    Chosen,
+}
+
+/// 下降路径中某一步在该页上需要高亮的范围（文件偏移）
+pub fn step_highlights(page: &Page, step: &DescentStep) -> Vec<(Range<usize>, Highlight)> {
+    let Page::BTree(page) = page else {
+        return vec![];
+    };
+    let cell_range = |i: usize| page.cells.get(i).map(|c| c.offset..c.offset + c.size());
+    let pointer_range = |i: usize| {
+        let start = page.cell_pointers_offset() + 2 * i;
+        start..start + 2
+    };
+    let mut ranges = vec![];
+    for &i in &step.probes {
+        if Some(i) != step.cell {
+            ranges.extend(cell_range(i).map(|r| (r, Highlight::Probe)));
+            ranges.push((pointer_range(i), Highlight::Probe));
+        }
+    }
+    match step.cell {
+        Some(i) => {
+            ranges.extend(cell_range(i).map(|r| (r, Highlight::Chosen)));
+            ranges.push((pointer_range(i), Highlight::Chosen));
+        }
+        // 内部页上没有选中单元，说明走的是最右指针
+        None if step.child.is_some() => {
+            ranges.push((
+                page.header_offset + 8..page.header_offset + 12,
+                Highlight::Chosen,
+            ));
+        }
+        None => {}
+    }
+    ranges
+}
+
+/// 跳到下降路径的第 i 步，选中该步访问的页
+fn go_to_step(mut state: AppState, i: usize) {
+    let Some(page) = state
+        .descent
+        .read()
+        .as_ref()
+        .and_then(|d| d.steps.get(i))
+        .map(|s| s.page)
+    else {
+        return;
+    };
+    if let Some(part) = state.viewer.read().page_part(page) {
+        *state.selected_part.write() = part;
+        *state.selected_field.write() = None;
+    }
+    *state.descent_step.write() = i;
+}
+
+/// 选一张表或一个索引，输入 rowid 或索引键，像 SQLite 一样从根页开始二分查找，一路下降到叶子页。
+/// 索引键按 SQLite 的 record 比较规则（类型顺序、排序规则、DESC）比较。
+/// 每一步访问的页和选中的单元按顺序高亮。
+pub fn TreeLookup() -> Element {
+    let mut state = use_context::<AppState>();
+    let mut selected = use_signal(|| 0usize);
+    let mut input = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+
+    // (schema 下标, 名字, 是否按 rowid 查找)
+    let trees: Vec<(usize, String, bool)> = state
+        .viewer
+        .read()
+        .reader
+        .schema
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.root_page > 0 && (e.kind == "table" || e.kind == "index"))
+        .map(|(i, e)| {
+            (
+                i,
+                e.name.clone(),
+                e.kind == "table" && !e.is_without_rowid(),
+            )
+        })
+        .collect();
+    // 切换数据库后，之前选中的表可能不存在了
+    let current = trees
+        .iter()
+        .find(|t| t.0 == selected())
+        .or(trees.first())
+        .map(|t| (t.0, t.2));
+    let by_rowid = current.is_some_and(|t| t.1);
+
+    let descent = state.descent.read().clone();
+    let step = (state.descent_step)();
+    let status = descent.as_ref().map(|d| {
+        let s = &d.steps[step.min(d.steps.len() - 1)];
+        let what = match (s.child, s.cell) {
+            (Some(child), Some(cell)) => format!("cell {cell} → left child page {child}"),
+            (Some(child), None) => format!("right-most pointer → page {child}"),
+            _ => match d.found {
+                Some((page, cell)) if page == s.page => format!("found in cell {cell}"),
+                Some((page, cell)) => format!("found in cell {cell} of interior page {page}"),
+                None => "not found".to_string(),
+            },
+        };
+        format!(
+            "Step {}/{}: page {}, {} comparison(s), {what}",
+            step + 1,
+            d.steps.len(),
+            s.page,
+            s.probes.len()
+        )
+    });
+    let steps = descent.as_ref().map_or(0, |d| d.steps.len());
+
+    rsx! {
+        div {
+            class: "flex items-center gap-2 px-4 py-1 bg-secondary text-xs",
+            div {
+                class: "font-bold tracking-tighter",
+                "Find"
+            }
+            select {
+                class: "select select-xs select-bordered",
+                onchange: move |e| selected.set(e.value().parse().unwrap_or(0)),
+                for (i, name, _) in trees {
+                    option {
+                        value: "{i}",
+                        selected: Some(i) == current.map(|t| t.0),
+                        "{name}"
+                    }
+                }
+            }
+            input {
+                class: "input input-xs input-bordered w-40",
+                placeholder: if by_rowid { "rowid" } else { "key, e.g. 'abc', 3" },
+                value: "{input}",
+                oninput: move |e| input.set(e.value()),
+            }
+            button {
+                class: "btn btn-xs btn-primary",
+                onclick: move |_| {
+                    let Some((i, by_rowid)) = current else {
+                        return;
+                    };
+                    let reader = state.viewer.read().reader.clone();
+                    let entry = &reader.schema[i];
+                    let result = if by_rowid {
+                        match input().trim().parse::<i64>() {
+                            Ok(rowid) => reader.find_rowid(entry.root_page, rowid),
+                            Err(_) => Err(anyhow::anyhow!("rowid must be an integer")),
+                        }
+                    } else {
+                        parse_key(&input()).and_then(|key| reader.find_index_key(entry, &key))
+                    };
+                    match result {
+                        Ok(descent) => {
+                            error.set(None);
+                            *state.descent.write() = Some(descent);
+                            go_to_step(state, 0);
+                        }
+                        Err(e) => {
+                            error.set(Some(e.to_string()));
+                            *state.descent.write() = None;
+                        }
+                    }
+                },
+                "Go"
+            }
+            if let Some(status) = status {
+                button {
+                    class: "btn btn-xs btn-ghost",
+                    disabled: step == 0,
+                    onclick: move |_| go_to_step(state, step.saturating_sub(1)),
+                    "◀"
+                }
+                div { "{status}" }
+                button {
+                    class: "btn btn-xs btn-ghost",
+                    disabled: step + 1 >= steps,
+                    onclick: move |_| go_to_step(state, step + 1),
+                    "▶"
+                }
+                button {
+                    class: "btn btn-xs btn-ghost",
+                    onclick: move |_| *state.descent.write() = None,
+                    "Clear"
+                }
+            }
+            if let Some(error) = error() {
+                div {
+                    class: "text-error",
+                    "{error}"
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,25 @@
+pub mod analysis;
+pub mod catalog;
+pub mod companion;
+pub mod diagnostics;
+pub mod diagram;
+pub mod diff;
+pub mod edit;
+pub mod export;
 mod header;
+pub mod hexdump;
 pub mod home;
+pub mod i18n;
+pub mod inspector;
+pub mod lookup;
+pub mod open;
+mod page;
+pub mod route;
+pub mod search;
+pub mod session;
 pub mod state;
+pub mod tree;
 pub mod viewer;
 pub use header::Field;
 pub use header::Parts;
+pub use header::Value;
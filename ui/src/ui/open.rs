@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+
+use std::{rc::Rc, sync::Arc};
+
+use dioxus::{
+    html::{FileEngine, HasFileData},
+    prelude::*,
+};
+use dioxus_logger::tracing::error;
+
+use super::{session::FileKind, state::AppState};
+
+/// 数据库文件的扩展名
+pub const DB_EXTENSIONS: [&str; 4] = [".db", ".sqlite", ".sqlite3", ".db3"];
+
+/// 文件选择框接受的扩展名：数据库文件和它们的伴随文件
+fn accept() -> String {
+    DB_EXTENSIONS
+        .iter()
+        .flat_map(|ext| {
+            [FileKind::Main]
+                .into_iter()
+                .chain(FileKind::COMPANIONS)
+                .map(move |kind| format!("{ext}{}", kind.suffix()))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 按扩展名判断是否是数据库文件或伴随文件，拖入的文件不经过文件选择框的过滤
+pub fn is_db_file(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let (main, _) = FileKind::of(&name);
+    DB_EXTENSIONS.iter().any(|ext| main.ends_with(ext))
+}
+
+/// 在浏览器中读取用户选择或拖入的文件，与同名的伴随文件一起加入数据库列表。文件不会离开浏览器。
+async fn load_files(mut state: AppState, files: Arc<dyn FileEngine>) {
+    let mut loaded = vec![];
+    let mut errors = vec![];
+    for name in files.files() {
+        let file_name = name.rsplit(['/', '\\']).next().unwrap_or(&name).to_string();
+        if !is_db_file(&file_name) {
+            errors.push(format!("{file_name} is not a .db or .sqlite file"));
+            continue;
+        }
+        match files.read_file(&name).await {
+            Some(bytes) => loaded.push((file_name, Rc::from(bytes))),
+            None => errors.push(format!("Failed to read {file_name}")),
+        }
+    }
+    let added = state.add_files(loaded);
+    errors.extend(added.orphans.into_iter().map(|name| {
+        let (main, _) = FileKind::of(&name);
+        format!("Open {name} together with {main}")
+    }));
+    for e in added.failed {
+        error!("{e}");
+        errors.push(e);
+    }
+    if !errors.is_empty() {
+        *state.open_error.write() = Some(errors.join("; "));
+    }
+}
+
+/// 打开文件选择框，选择本地的数据库文件
+pub fn AddYoursButton() -> Element {
+    let state = use_context::<AppState>();
+    rsx! {
+        label {
+            class: "btn btn-ghost tracking-tighter font-bold",
+            input {
+                class: "hidden",
+                r#type: "file",
+                accept: accept(),
+                multiple: true,
+                onchange: move |e| async move {
+                    if let Some(files) = e.files() {
+                        load_files(state, files).await;
+                    }
+                },
+            }
+            "Add Yours"
+        }
+    }
+}
+
+/// 可以把数据库文件拖到其中任意位置的区域
+#[component]
+pub fn DropZone(children: Element) -> Element {
+    let state = use_context::<AppState>();
+    let mut dragging = use_signal(|| false);
+    rsx! {
+        div {
+            class: if dragging() { "min-h-screen outline-dashed outline-4 -outline-offset-4 outline-accent" } else { "min-h-screen" },
+            prevent_default: "ondragover ondrop",
+            ondragover: move |_| dragging.set(true),
+            ondragleave: move |_| dragging.set(false),
+            ondrop: move |e| async move {
+                dragging.set(false);
+                if let Some(files) = e.files() {
+                    load_files(state, files).await;
+                }
+            },
+            {children}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_database_extensions() {
+        assert!(is_db_file("chinook.db"));
+        assert!(is_db_file("Places.SQLITE"));
+        assert!(is_db_file("data.sqlite3"));
+        assert!(!is_db_file("notes.txt"));
+        assert!(!is_db_file("db"));
+        assert!(is_db_file("chinook.db-wal"));
+        assert!(is_db_file("data.sqlite-journal"));
+        assert!(!is_db_file("notes.txt-wal"));
+        assert!(accept().contains(".sqlite-shm"));
+    }
+}
@@ -0,0 +1,546 @@
+use std::{ops::Range, rc::Rc};
+
+use crate::parser::{
+    page::{BTreePage, Cell, FreelistTrunkPage, OverflowPage, PageHeader, Payload},
+    Page, PageType, Record, RecordValue, SchemaEntry,
+};
+
+use super::{catalog, i18n::Text, viewer::CachedPart, Field, Parts, Value};
+
+impl Parts for Page {
+    fn label(&self) -> String {
+        let number = self.info().number;
+        match self {
+            Self::BTree(p) if p.owner.as_ref().is_some_and(|o| o.is_without_rowid()) => {
+                format!("Page {number} ({}, WITHOUT ROWID)", p.header.page_type)
+            }
+            Self::BTree(p) => format!("Page {number} ({})", p.header.page_type),
+            Self::Overflow(_) => format!("Page {number} (Overflow)"),
+            Self::FreelistTrunk(_) => format!("Page {number} (Freelist Trunk)"),
+            Self::FreelistLeaf(_) => format!("Page {number} (Freelist Leaf)"),
+            Self::Unknown(_) => format!("Page {number}"),
+        }
+    }
+
+    fn desc(&self) -> Text {
+        match self {
+            Self::BTree(p) => btree_desc(p),
+            Self::Overflow(_) => catalog::overflow_page(),
+            Self::FreelistTrunk(_) => catalog::freelist_trunk_page(),
+            Self::FreelistLeaf(_) => catalog::freelist_leaf_page(),
+            Self::Unknown(_) => catalog::unknown_page(),
+        }
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        match self {
+            Self::BTree(p) => btree_fields(p).into(),
+            Self::Overflow(p) => overflow_fields(p).into(),
+            Self::FreelistTrunk(p) => freelist_trunk_fields(p).into(),
+            Self::FreelistLeaf(_) | Self::Unknown(_) => Rc::new([]),
+        }
+    }
+
+    /// B-Tree 页的单元
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        let Self::BTree(page) = self else {
+            return vec![];
+        };
+        page.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let part = CellPart {
+                    page: page.info.number,
+                    page_type: page.header.page_type,
+                    index,
+                    cell: cell.clone(),
+                    owner: page.owner.clone(),
+                };
+                Rc::new(CachedPart::new(Rc::new(part))) as Rc<dyn Parts>
+            })
+            .collect()
+    }
+
+    fn has_children(&self) -> bool {
+        matches!(self, Self::BTree(page) if !page.cells.is_empty())
+    }
+
+    /// 整页
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        let info = self.info();
+        Some((info.number, info.offset..info.offset + info.size))
+    }
+}
+
+/// B-Tree 页中的一个单元
+#[derive(Debug)]
+struct CellPart {
+    page: u32,
+    page_type: PageType,
+    index: usize,
+    cell: Cell,
+    owner: Option<Rc<SchemaEntry>>,
+}
+
+impl Parts for CellPart {
+    fn label(&self) -> String {
+        match self.cell.rowid {
+            Some((rowid, _)) => format!("Cell {} (rowid {rowid})", self.index),
+            None => format!("Cell {}", self.index),
+        }
+    }
+
+    fn desc(&self) -> Text {
+        let mut desc = catalog::cell(self.index, self.page)
+            + match self.page_type {
+                PageType::LeafTable => catalog::leaf_table_cell(),
+                PageType::InteriorTable => catalog::interior_table_cell(),
+                PageType::LeafIndex => catalog::leaf_index_cell(),
+                PageType::InteriorIndex => catalog::interior_index_cell(),
+            };
+        if self
+            .cell
+            .payload
+            .as_ref()
+            .is_some_and(|p| p.first_overflow.is_some())
+        {
+            desc += catalog::cell_overflows();
+        }
+        desc
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let mut fields = vec![];
+        cell_fields(self.index, &self.cell, self.owner.as_deref(), &mut fields);
+        fields.into()
+    }
+
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        let Some(payload) = self.cell.payload.as_ref().filter(|p| p.record.is_some()) else {
+            return vec![];
+        };
+        let part = RecordPart {
+            page: self.page,
+            cell: self.index,
+            payload: payload.clone(),
+            owner: self.owner.clone(),
+            rowid: self.cell.rowid.map(|r| r.0),
+        };
+        vec![Rc::new(CachedPart::new(Rc::new(part)))]
+    }
+
+    fn has_children(&self) -> bool {
+        self.cell
+            .payload
+            .as_ref()
+            .is_some_and(|p| p.record.is_some())
+    }
+
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        let start = self.cell.offset;
+        Some((self.page, start..start + self.cell.size()))
+    }
+}
+
+/// 单元负载中的 record
+#[derive(Debug)]
+struct RecordPart {
+    page: u32,
+    cell: usize,
+    payload: Payload,
+    owner: Option<Rc<SchemaEntry>>,
+    rowid: Option<i64>,
+}
+
+impl Parts for RecordPart {
+    fn label(&self) -> String {
+        "Record".to_string()
+    }
+
+    fn desc(&self) -> Text {
+        catalog::record(self.cell)
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        let mut fields = vec![];
+        if let Some(record) = &self.payload.record {
+            record_fields(
+                self.cell,
+                &self.payload,
+                record,
+                self.owner.as_deref(),
+                self.rowid,
+                &mut fields,
+            );
+        }
+        fields.into()
+    }
+
+    /// 每一列的串行类型和值
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        let Some(record) = &self.payload.record else {
+            return vec![];
+        };
+        let fields = self.fields();
+        let n = record.columns.len();
+        let columns = self.owner.as_deref().map_or(&[][..], |o| &o.columns[..]);
+        let (_, local) = self.location().expect("records are in a page");
+        record
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(j, column)| {
+                let name = columns
+                    .get(j)
+                    .map_or(format!("column {j}"), |c| c.name.clone());
+                let mut value = column.value.to_string();
+                if value.chars().count() > 24 {
+                    value = value.chars().take(24).collect::<String>() + "…";
+                }
+                let part = ColumnPart {
+                    page: self.page,
+                    label: format!("{name} = {value}"),
+                    fields: Rc::new([fields[1 + j].clone(), fields[1 + n + j].clone()]),
+                    local: local.clone(),
+                };
+                Rc::new(part) as Rc<dyn Parts>
+            })
+            .collect()
+    }
+
+    fn has_children(&self) -> bool {
+        self.payload
+            .record
+            .as_ref()
+            .is_some_and(|r| !r.columns.is_empty())
+    }
+
+    /// 负载保存在本页的部分，溢出页中的字节不在其中
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        let start = self.payload.offset;
+        Some((self.page, start..start + self.payload.local_size))
+    }
+}
+
+/// record 中的一列：串行类型和值两个字段
+#[derive(Debug)]
+struct ColumnPart {
+    page: u32,
+    label: String,
+    fields: Rc<[Field]>,
+    /// record 保存在本页的字节
+    local: Range<usize>,
+}
+
+impl Parts for ColumnPart {
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn desc(&self) -> Text {
+        self.fields[1].desc.clone()
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        self.fields.clone()
+    }
+
+    /// 从串行类型到值的字节。值在溢出页中时只有本页中的部分
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        let [serial, value] = &self.fields[..] else {
+            return None;
+        };
+        let start = serial.offset.max(self.local.start);
+        let end = (value.offset + value.size)
+            .min(self.local.end)
+            .max(serial.offset + serial.size);
+        Some((self.page, start..end))
+    }
+}
+
+fn btree_desc(page: &BTreePage) -> Text {
+    if let Some(owner) = page.owner.as_ref().filter(|o| o.is_without_rowid()) {
+        return without_rowid_desc(page, owner);
+    }
+    let page_type = page.header.page_type;
+    let mut desc = catalog::btree_page(
+        page_type,
+        page.owner
+            .as_ref()
+            .map(|o| (o.kind.as_str(), o.name.as_str())),
+    );
+    desc += match page_type {
+        PageType::LeafTable => catalog::leaf_table_page(),
+        PageType::InteriorTable => catalog::interior_table_page(),
+        PageType::LeafIndex => catalog::leaf_index_page(),
+        PageType::InteriorIndex => catalog::interior_index_page(),
+    };
+    desc += catalog::page_layout();
+    if let Some(owner) = &page.owner {
+        if !owner.columns.is_empty() {
+            desc += catalog::record_columns(record_columns(owner));
+        }
+    }
+    desc
+}
+
+/// WITHOUT ROWID 表的页：页类型是索引页，但每个单元保存的是完整的表行
+fn without_rowid_desc(page: &BTreePage, owner: &SchemaEntry) -> Text {
+    let mut desc =
+        catalog::without_rowid_page(catalog::page_type(page.header.page_type), &owner.name);
+    if !page.header.page_type.is_leaf() {
+        desc += catalog::without_rowid_interior();
+    }
+    if let Some(table) = owner.table() {
+        let physical: Vec<&str> = owner.columns.iter().map(|c| c.name.as_str()).collect();
+        let declared: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        let primary_key: Vec<&str> = table.primary_key.iter().map(|c| c.name.as_str()).collect();
+        desc += catalog::without_rowid_order(
+            &primary_key.join(", "),
+            &physical.join(", "),
+            &declared.join(", "),
+        );
+    }
+    desc + catalog::record_columns(record_columns(owner))
+}
+
+fn record_columns(owner: &SchemaEntry) -> Text {
+    let columns: Vec<Text> = owner.columns.iter().map(catalog::column_summary).collect();
+    Text::join(&columns, ", ", "、")
+}
+
+/// 页头的字段，h 是页头在文件中的偏移
+pub fn page_header_fields(h: usize, header: &PageHeader) -> Vec<Field> {
+    let mut fields = vec![
+        Field::new(
+            catalog::page_type_field(),
+            h,
+            1,
+            Value::U8(header.page_type.to_u8()),
+        ),
+        Field::new(
+            catalog::first_freeblock(),
+            h + 1,
+            2,
+            Value::U16(header.first_freeblock),
+        ),
+        Field::new(
+            catalog::cell_count(),
+            h + 3,
+            2,
+            Value::U16(header.cell_count),
+        ),
+        Field::new(
+            catalog::cell_content_start(),
+            h + 5,
+            2,
+            Value::U16(header.cell_content_start),
+        ),
+        Field::new(
+            catalog::fragmented_free_bytes(),
+            h + 7,
+            1,
+            Value::U8(header.fragmented_free_bytes),
+        ),
+    ];
+    if let Some(right_most) = header.right_most_pointer {
+        fields.push(Field::new(
+            catalog::right_most_pointer(),
+            h + 8,
+            4,
+            Value::U32(right_most),
+        ));
+    }
+    fields
+}
+
+fn btree_fields(page: &BTreePage) -> Vec<Field> {
+    let mut fields = page_header_fields(page.header_offset, &page.header);
+    let pointers = page.cell_pointers_offset();
+    for (i, &pointer) in page.cell_pointers.iter().enumerate() {
+        fields.push(Field::new(
+            catalog::cell_pointer(i),
+            pointers + 2 * i,
+            2,
+            Value::U16(pointer),
+        ));
+    }
+
+    for (i, cell) in page.cells.iter().enumerate() {
+        cell_fields(i, cell, page.owner.as_deref(), &mut fields);
+    }
+    fields
+}
+
+fn cell_fields(i: usize, cell: &Cell, owner: Option<&SchemaEntry>, fields: &mut Vec<Field>) {
+    let mut pos = cell.offset;
+    if let Some(child) = cell.left_child {
+        fields.push(Field::new(
+            catalog::left_child(i),
+            pos,
+            4,
+            Value::U32(child),
+        ));
+        pos += 4;
+    }
+    if let Some((size, len)) = cell.payload_size {
+        fields.push(Field::new(
+            catalog::payload_size(i),
+            pos,
+            len,
+            Value::Varint(size as i64),
+        ));
+        pos += len;
+    }
+    if let Some((rowid, len)) = cell.rowid {
+        fields.push(Field::new(
+            catalog::rowid(i),
+            pos,
+            len,
+            Value::Varint(rowid),
+        ));
+    }
+    let Some(payload) = &cell.payload else {
+        return;
+    };
+    match &payload.record {
+        Some(record) => record_fields(i, payload, record, owner, cell.rowid.map(|r| r.0), fields),
+        None => fields.push(Field::new(
+            catalog::invalid_payload(i),
+            payload.offset,
+            payload.local_size,
+            Value::Array(payload.bytes.slice(0..payload.local_size)),
+        )),
+    }
+    if let Some(next) = payload.first_overflow {
+        fields.push(Field::new(
+            catalog::first_overflow(i),
+            payload.offset + payload.local_size,
+            4,
+            Value::U32(next),
+        ));
+    }
+}
+
+fn record_fields(
+    i: usize,
+    payload: &Payload,
+    record: &Record,
+    owner: Option<&SchemaEntry>,
+    rowid: Option<i64>,
+    fields: &mut Vec<Field>,
+) {
+    let columns = owner.map_or(&[][..], |o| &o.columns[..]);
+    let without_rowid = owner.is_some_and(|o| o.is_without_rowid());
+    let mut header_desc = catalog::record_header_size(i);
+    if let Some(owner) = owner.filter(|_| without_rowid) {
+        // 按声明顺序还原表的一行
+        let row: Vec<String> = owner
+            .declared_order(record)
+            .into_iter()
+            .map(|j| {
+                let name = columns
+                    .get(j)
+                    .map_or(format!("column {j}"), |c| c.name.clone());
+                format!("{name} = {}", record.columns[j].value)
+            })
+            .collect();
+        header_desc += catalog::declared_row(&row.join(", "));
+    }
+    fields.push(Field::new(
+        header_desc,
+        payload.file_offset(0),
+        record.header_size_len,
+        Value::Varint(record.header_size),
+    ));
+    for (j, column) in record.columns.iter().enumerate() {
+        fields.push(Field::new(
+            catalog::serial_type_field(
+                i,
+                catalog::column_label(columns, j),
+                catalog::serial_type(column.serial_type),
+            ),
+            payload.file_offset(column.serial_type_offset),
+            column.serial_type_size,
+            Value::Varint(column.serial_type),
+        ));
+    }
+    for (j, column) in record.columns.iter().enumerate() {
+        let mut desc = catalog::column_value(i, catalog::column_label(columns, j));
+        if let Some(declared) = columns
+            .get(j)
+            .filter(|_| without_rowid)
+            .and_then(|c| c.declared)
+        {
+            desc += catalog::declared_column(declared + 1);
+            if columns[j].primary_key {
+                desc += catalog::primary_key_first();
+            } else {
+                desc += catalog::after_primary_key();
+            }
+        }
+        if columns.get(j).is_some_and(|c| c.rowid_alias) {
+            if let Some(rowid) = rowid {
+                desc += catalog::rowid_alias(rowid);
+            }
+        }
+        let value = match &column.value {
+            RecordValue::Null => Value::Null,
+            RecordValue::Integer(v) => Value::I64(*v),
+            RecordValue::Real(v) => Value::F64(*v),
+            RecordValue::Text(v) => Value::Text(v.clone()),
+            // 直接引用负载中的字节，不复制 blob
+            RecordValue::Blob(_) => Value::Array(
+                payload
+                    .bytes
+                    .slice(column.offset..column.offset + column.size),
+            ),
+        };
+        fields.push(Field::new(
+            desc,
+            payload.file_offset(column.offset),
+            column.size,
+            value,
+        ));
+    }
+}
+
+fn overflow_fields(page: &OverflowPage) -> Vec<Field> {
+    vec![
+        Field::new(
+            catalog::overflow_next(),
+            page.info.offset,
+            4,
+            Value::U32(page.next),
+        ),
+        Field::new(
+            catalog::overflow_content(),
+            page.info.offset + 4,
+            page.content.len(),
+            Value::Array(page.content.clone()),
+        ),
+    ]
+}
+
+fn freelist_trunk_fields(page: &FreelistTrunkPage) -> Vec<Field> {
+    let offset = page.info.offset;
+    let mut fields = vec![
+        Field::new(catalog::trunk_next(), offset, 4, Value::U32(page.next)),
+        Field::new(
+            catalog::trunk_leaf_count(),
+            offset + 4,
+            4,
+            Value::U32(page.leaves.len() as u32),
+        ),
+    ];
+    for (i, &leaf) in page.leaves.iter().enumerate() {
+        fields.push(Field::new(
+            catalog::trunk_leaf(i),
+            offset + 8 + 4 * i,
+            4,
+            Value::U32(leaf),
+        ));
+    }
+    fields
+}
@@ -0,0 +1,207 @@
+#![allow(non_snake_case)]
+
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use dioxus::prelude::*;
+
+use crate::parser::Page;
+
+use super::{
+    home::Home,
+    viewer::{field_at, Viewer},
+    Field, Parts,
+};
+
+/// 页面的地址。除了首页，地址指向数据库中的一页、一个单元或一个字节，
+/// 复制地址就能让别人打开同一个位置。
+#[derive(Clone, Routable, Debug, PartialEq)]
+#[rustfmt::skip]
+pub enum Route {
+    #[layout(Home)]
+        #[route("/")]
+        Index {},
+        #[route("/db/:name")]
+        Database { name: String },
+        #[route("/db/:name/page/:n")]
+        DatabasePage { name: String, n: u32 },
+        #[route("/db/:name/page/:n/cell/:i")]
+        PageCell { name: String, n: u32, i: usize },
+        #[route("/db/:name/offset/:byte")]
+        FileOffset { name: String, byte: usize },
+}
+
+// 每个地址由 Home 根据状态显示，地址本身的组件不显示内容
+
+#[component]
+fn Index() -> Element {
+    None
+}
+
+#[component]
+fn Database(name: String) -> Element {
+    None
+}
+
+#[component]
+fn DatabasePage(name: String, n: u32) -> Element {
+    None
+}
+
+#[component]
+fn PageCell(name: String, n: u32, i: usize) -> Element {
+    None
+}
+
+#[component]
+fn FileOffset(name: String, byte: usize) -> Element {
+    None
+}
+
+impl Route {
+    /// 指向 db 中选中的部分和字段的地址。选中字段时指向字段的第一个字节，
+    /// 否则指向选中的页；其它部分只记录数据库。
+    pub fn link(db: &str, viewer: &Viewer, part: &Rc<dyn Parts>, field: Option<&Field>) -> Self {
+        let name = db.to_string();
+        if let Some(field) = field.filter(|_| viewer.in_main_file(part)) {
+            return Self::FileOffset {
+                name,
+                byte: field.offset,
+            };
+        }
+        match viewer.page_number(part) {
+            Some(n) => Self::DatabasePage { name, n },
+            None => Self::Database { name },
+        }
+    }
+
+    /// 地址中的数据库，首页没有
+    pub fn db(&self) -> Option<&str> {
+        match self {
+            Self::Index {} => None,
+            Self::Database { name }
+            | Self::DatabasePage { name, .. }
+            | Self::PageCell { name, .. }
+            | Self::FileOffset { name, .. } => Some(name),
+        }
+    }
+
+    /// 地址在 viewer 中指向的部分和字段。单元指向它的第一个字段，字节指向包含它的最小的字段。
+    pub fn target(&self, viewer: &Viewer) -> Result<(Rc<dyn Parts>, Option<Field>)> {
+        let page = |n: u32| {
+            viewer
+                .page_part(n)
+                .ok_or_else(|| anyhow!("The database has no page {n}"))
+        };
+        Ok(match *self {
+            Self::Index {} | Self::Database { .. } => (viewer.first_part(), None),
+            Self::DatabasePage { n, .. } => (page(n)?, None),
+            Self::PageCell { n, i, .. } => {
+                let part = page(n)?;
+                let offset = match viewer.reader.page(n).as_deref() {
+                    Some(Page::BTree(page)) => page.cells.get(i).map(|c| c.offset),
+                    _ => None,
+                }
+                .ok_or_else(|| anyhow!("Page {n} has no cell {i}"))?;
+                let field = field_at(&part, offset);
+                (part, field)
+            }
+            Self::FileOffset { byte, .. } => {
+                let part = viewer
+                    .part_at(byte)
+                    .ok_or_else(|| anyhow!("Offset {byte} is past the end of the file"))?;
+                let field = field_at(&part, byte);
+                (part, field)
+            }
+        })
+    }
+
+    /// 地址是否指向 db 中选中的部分和字段。只有数据库的地址不限定部分，首页不指向任何位置。
+    pub fn points_at(
+        &self,
+        db: &str,
+        viewer: &Viewer,
+        part: &Rc<dyn Parts>,
+        field: Option<&Field>,
+    ) -> bool {
+        match self {
+            Self::Index {} => false,
+            Self::Database { name } => name == db,
+            _ if self.db() != Some(db) => false,
+            _ => self
+                .target(viewer)
+                .is_ok_and(|(p, f)| Rc::ptr_eq(&p, part) && f.as_ref() == field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_point_back_at_the_selection() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        let page = viewer.page_part(2).unwrap();
+        let cell = Route::PageCell {
+            name: "Simple".to_string(),
+            n: 2,
+            i: 1,
+        };
+        let (part, field) = cell.target(&viewer).unwrap();
+        assert!(Rc::ptr_eq(&part, &page));
+        let field = field.unwrap();
+        assert!(field.desc.en.starts_with("Cell 1:"));
+        assert!(cell.points_at("Simple", &viewer, &part, Some(&field)));
+        assert!(!cell.points_at("Big Page", &viewer, &part, Some(&field)));
+
+        // 选中字段的地址指向字段的第一个字节，再解析回同一个字段
+        let link = Route::link("Simple", &viewer, &part, Some(&field));
+        assert_eq!(
+            link,
+            Route::FileOffset {
+                name: "Simple".to_string(),
+                byte: field.offset
+            }
+        );
+        assert!(link.points_at("Simple", &viewer, &part, Some(&field)));
+        let link = Route::link("Simple", &viewer, &page, None);
+        assert_eq!(link.to_string(), "/db/Simple/page/2");
+
+        // 数据库头中的字节
+        let (part, field) = Route::FileOffset {
+            name: "Simple".to_string(),
+            byte: 16,
+        }
+        .target(&viewer)
+        .unwrap();
+        assert!(Rc::ptr_eq(&part, &viewer.first_part()));
+        assert_eq!(field.unwrap().offset, 16);
+
+        let missing = Route::PageCell {
+            name: "Simple".to_string(),
+            n: 2,
+            i: 999,
+        };
+        assert!(missing.target(&viewer).is_err());
+    }
+
+    #[test]
+    fn names_survive_the_url() {
+        let route = Route::FileOffset {
+            name: "Big Page".to_string(),
+            byte: 4096,
+        };
+        let url = route.to_string();
+        assert_eq!(url, "/db/Big%20Page/offset/4096");
+        assert_eq!(url.parse::<Route>().unwrap(), route);
+        assert_eq!(
+            "/db/Simple/page/2/cell/3".parse::<Route>().unwrap(),
+            Route::PageCell {
+                name: "Simple".to_string(),
+                n: 2,
+                i: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,199 @@
+#![allow(non_snake_case)]
+
+use dioxus::prelude::*;
+
+use crate::parser::{
+    search::{search_bytes, Match, Query, SearchKind},
+    Page,
+};
+
+use super::{route::Route, state::AppState, viewer::Viewer};
+
+/// 最多列出的匹配数
+const LIMIT: usize = 500;
+
+/// 包含 offset 的结构：页所属的表或索引，然后从单元到最内层的结构
+fn owner(viewer: &Viewer, offset: usize) -> String {
+    let chain = viewer.explain(offset);
+    let inner: Vec<&str> = chain.iter().skip(1).map(|s| s.label.as_str()).collect();
+    let page_size = viewer.reader.header.real_page_size();
+    let page = viewer.reader.page((offset / page_size) as u32 + 1);
+    // 数据库头虽然在第 1 页，但不属于 sqlite_schema
+    let tree = match page.as_deref() {
+        Some(Page::BTree(p)) if offset >= 100 => p.owner.as_ref().map(|o| o.name.clone()),
+        _ => None,
+    };
+    match (tree, inner.is_empty()) {
+        (Some(tree), false) => format!("{tree}: {}", inner.join(" › ")),
+        (Some(tree), true) => tree,
+        (None, false) => inner.join(" › "),
+        (None, true) => chain.first().map_or(String::new(), |s| s.label.clone()),
+    }
+}
+
+/// 在整个文件中查找字节串、文本、整数或 rowid，列出匹配的页、偏移和所在的结构，
+/// 点击一项选中包含它的字段。
+pub fn SearchBar() -> Element {
+    let state = use_context::<AppState>();
+    let mut kind = use_signal(|| SearchKind::Text);
+    let mut input = use_signal(String::new);
+    let mut results = use_signal(|| None::<Vec<Match>>);
+    let mut error = use_signal(|| None::<String>);
+
+    // 切换数据库或修改文件后之前的结果不再有效
+    use_effect(move || {
+        state.viewer.read();
+        results.set(None);
+    });
+
+    let mut run = move || {
+        let viewer = state.viewer.read();
+        let found = Query::parse(kind(), &input()).map(|query| match query {
+            Query::Rowid(rowid) => viewer.reader.find_rowids(rowid),
+            query => search_bytes(&viewer.bytes, &query, LIMIT + 1),
+        });
+        match found {
+            Ok(found) => {
+                error.set(None);
+                results.set(Some(found));
+            }
+            Err(e) => {
+                error.set(Some(e.to_string()));
+                results.set(None);
+            }
+        }
+    };
+
+    let viewer = state.viewer.read();
+    let page_size = viewer.reader.header.real_page_size();
+    let selected = state.selected_field.read().clone();
+    let rows: Vec<(Match, usize, String)> = results
+        .read()
+        .iter()
+        .flatten()
+        .take(LIMIT)
+        .map(|m| {
+            (
+                m.clone(),
+                m.offset / page_size + 1,
+                owner(&viewer, m.offset),
+            )
+        })
+        .collect();
+    let count = results.read().as_ref().map(|r| r.len());
+
+    rsx! {
+        div {
+            class: "flex items-center gap-2 px-4 py-1 bg-secondary text-xs",
+            div {
+                class: "font-bold tracking-tighter",
+                "Search"
+            }
+            select {
+                class: "select select-xs select-bordered",
+                onchange: move |e| {
+                    if let Some(k) = SearchKind::ALL.into_iter().find(|k| k.name() == e.value()) {
+                        kind.set(k);
+                    }
+                },
+                for k in SearchKind::ALL {
+                    option {
+                        value: k.name(),
+                        selected: k == kind(),
+                        "{k.name()}"
+                    }
+                }
+            }
+            input {
+                class: "input input-xs input-bordered w-48",
+                placeholder: kind().placeholder(),
+                value: "{input}",
+                oninput: move |e| input.set(e.value()),
+                onkeydown: move |e| {
+                    if e.key() == Key::Enter {
+                        run();
+                    }
+                },
+            }
+            button {
+                class: "btn btn-xs btn-primary",
+                onclick: move |_| run(),
+                "Search"
+            }
+            match count {
+                Some(0) => rsx! { div { "No matches" } },
+                Some(n) if n > LIMIT => rsx! { div { "Showing the first {LIMIT} matches" } },
+                Some(n) => rsx! { div { "{n} match(es)" } },
+                None => None,
+            }
+            if count.is_some() {
+                button {
+                    class: "btn btn-xs btn-ghost",
+                    onclick: move |_| results.set(None),
+                    "Clear"
+                }
+            }
+            if let Some(error) = error() {
+                div {
+                    class: "text-error",
+                    "{error}"
+                }
+            }
+        }
+        if !rows.is_empty() {
+            div {
+                class: "max-h-48 overflow-auto px-4 text-xs bg-base-200",
+                table {
+                    class: "table table-xs",
+                    thead {
+                        tr {
+                            th { "Page" }
+                            th { "Offset" }
+                            th { "Match" }
+                            th { "Owner" }
+                        }
+                    }
+                    tbody {
+                        for (m, page, owner) in rows {
+                            tr {
+                                class: "hover cursor-pointer",
+                                class: if selected.as_ref().is_some_and(|f| f.offset <= m.offset && m.offset < f.offset + f.size) {"bg-accent text-accent-content"},
+                                onclick: {
+                                    let offset = m.offset;
+                                    move |_| {
+                                        let route = Route::FileOffset {
+                                            name: state.current_db.read().clone(),
+                                            byte: offset,
+                                        };
+                                        if let Err(e) = state.follow(&route) {
+                                            error.set(Some(format!("{e:#}")));
+                                        }
+                                    }
+                                },
+                                td { "{page}" }
+                                td { class: "font-mono", "{m.offset} (0x{m.offset:X})" }
+                                td { "{m.len} byte(s), {m.encoding}" }
+                                td { "{owner}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_the_owner_of_a_match() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        assert_eq!(owner(&viewer, 16), "Database header");
+        let page = viewer.page_part(2).unwrap();
+        let cell = viewer.part_range(&page.children()[0]).unwrap();
+        let found = owner(&viewer, cell.start);
+        assert!(found.contains("Cell 0"), "{found}");
+    }
+}
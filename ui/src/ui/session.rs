@@ -0,0 +1,233 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+
+/// 一起打开的文件的种类，伴随文件的名字是主文件名加上后缀
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Main,
+    Wal,
+    Shm,
+    Journal,
+}
+
+impl FileKind {
+    pub const COMPANIONS: [FileKind; 3] = [FileKind::Wal, FileKind::Shm, FileKind::Journal];
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Main => "",
+            Self::Wal => "-wal",
+            Self::Shm => "-shm",
+            Self::Journal => "-journal",
+        }
+    }
+
+    /// 文件属于哪个主文件，以及它的种类。后缀不区分大小写，例如 `a.db-WAL`
+    pub fn of(file_name: &str) -> (&str, FileKind) {
+        Self::COMPANIONS
+            .into_iter()
+            .find_map(|kind| {
+                let split = file_name.len().checked_sub(kind.suffix().len())?;
+                let suffix = file_name.get(split..)?;
+                suffix
+                    .eq_ignore_ascii_case(kind.suffix())
+                    .then(|| (&file_name[..split], kind))
+            })
+            .unwrap_or((file_name, FileKind::Main))
+    }
+}
+
+/// 用户打开的数据库文件和与它同名的 -wal、-shm、-journal 文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    /// 主文件的名字，也是下拉菜单中显示的名字
+    pub name: String,
+    pub db: Rc<[u8]>,
+    pub wal: Option<Rc<[u8]>>,
+    pub shm: Option<Rc<[u8]>>,
+    pub journal: Option<Rc<[u8]>>,
+}
+
+impl Session {
+    pub fn new(name: String, db: Rc<[u8]>) -> Self {
+        Self {
+            name,
+            db,
+            wal: None,
+            shm: None,
+            journal: None,
+        }
+    }
+
+    pub fn file(&self, kind: FileKind) -> Option<&Rc<[u8]>> {
+        match kind {
+            FileKind::Main => Some(&self.db),
+            FileKind::Wal => self.wal.as_ref(),
+            FileKind::Shm => self.shm.as_ref(),
+            FileKind::Journal => self.journal.as_ref(),
+        }
+    }
+
+    fn set(&mut self, kind: FileKind, bytes: Rc<[u8]>) {
+        match kind {
+            FileKind::Main => self.db = bytes,
+            FileKind::Wal => self.wal = Some(bytes),
+            FileKind::Shm => self.shm = Some(bytes),
+            FileKind::Journal => self.journal = Some(bytes),
+        }
+    }
+
+    /// 伴随文件的名字和内容
+    pub fn companions(&self) -> impl Iterator<Item = (FileKind, String, &Rc<[u8]>)> {
+        FileKind::COMPANIONS.into_iter().filter_map(|kind| {
+            let bytes = self.file(kind)?;
+            Some((kind, format!("{}{}", self.name, kind.suffix()), bytes))
+        })
+    }
+}
+
+/// add_files 的结果
+#[derive(Debug, Default, PartialEq)]
+pub struct Added {
+    /// 最后一个成功打开的会话名
+    pub opened: Option<String>,
+    /// 找不到主文件的伴随文件
+    pub orphans: Vec<String>,
+    /// 无法打开的数据库的错误信息
+    pub failed: Vec<String>,
+}
+
+/// 名字为 name 的会话，没有完全相同的名字时不区分大小写地查找
+fn find_session<'a>(sessions: &'a mut [Session], name: &str) -> Option<&'a mut Session> {
+    let index = sessions.iter().position(|s| s.name == name).or_else(|| {
+        sessions
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(name))
+    })?;
+    Some(&mut sessions[index])
+}
+
+/// 把一次打开的文件按名字配对后加入 sessions。新的主文件替换同名的会话和它的伴随文件，
+/// 伴随文件加入同名的主文件的会话，无论主文件是这次还是之前打开的。
+/// 每个涉及的会话都单独用 open 检查，失败的会话恢复为打开前的样子，不影响其它会话。
+pub fn add_files(
+    sessions: &mut Vec<Session>,
+    mut files: Vec<(String, Rc<[u8]>)>,
+    mut open: impl FnMut(&Session) -> Result<()>,
+) -> Added {
+    // 先处理主文件，同一次打开的伴随文件才能找到它
+    files.sort_by_key(|(name, _)| FileKind::of(name).1 != FileKind::Main);
+    let before = sessions.clone();
+    let mut added = Added::default();
+    let mut touched: Vec<String> = vec![];
+    for (file_name, bytes) in files {
+        let (name, kind) = FileKind::of(&file_name);
+        let name = if kind == FileKind::Main {
+            sessions.retain(|s| s.name != name);
+            sessions.push(Session::new(name.to_string(), bytes));
+            name.to_string()
+        } else if let Some(session) = find_session(sessions, name) {
+            session.set(kind, bytes);
+            session.name.clone()
+        } else {
+            added.orphans.push(file_name);
+            continue;
+        };
+        touched.retain(|n| *n != name);
+        touched.push(name);
+    }
+    for name in touched {
+        let index = sessions
+            .iter()
+            .position(|s| s.name == name)
+            .expect("just added");
+        match open(&sessions[index]) {
+            Ok(()) => added.opened = Some(name),
+            Err(e) => {
+                added.failed.push(format!("{e:#}"));
+                match before.iter().find(|s| s.name == name) {
+                    Some(old) => sessions[index] = old.clone(),
+                    None => {
+                        sessions.remove(index);
+                    }
+                }
+            }
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str) -> (String, Rc<[u8]>) {
+        (name.to_string(), Rc::from(name.as_bytes()))
+    }
+
+    #[test]
+    fn pair_companions_by_name() {
+        assert_eq!(FileKind::of("a.db-wal"), ("a.db", FileKind::Wal));
+        assert_eq!(FileKind::of("a.sqlite"), ("a.sqlite", FileKind::Main));
+
+        assert_eq!(FileKind::of("a.db-WAL"), ("a.db", FileKind::Wal));
+
+        let mut sessions = vec![];
+        let added = add_files(
+            &mut sessions,
+            vec![
+                file("a.db-wal"),
+                file("b.db-journal"),
+                file("a.db"),
+                file("A.DB-SHM"),
+            ],
+            |_| Ok(()),
+        );
+        assert_eq!(added.opened.as_deref(), Some("a.db"));
+        assert_eq!(added.orphans, vec!["b.db-journal"]);
+        assert_eq!(sessions.len(), 1);
+        let names: Vec<_> = sessions[0].companions().map(|(_, name, _)| name).collect();
+        assert_eq!(names, vec!["a.db-wal", "a.db-shm"]);
+
+        // 之后单独打开的伴随文件加入已有的会话，重新打开的主文件清除旧的伴随文件
+        add_files(&mut sessions, vec![file("a.db-journal")], |_| Ok(()));
+        assert!(sessions[0].journal.is_some());
+        add_files(&mut sessions, vec![file("a.db")], |_| Ok(()));
+        assert_eq!(sessions[0].companions().count(), 0);
+    }
+
+    #[test]
+    fn check_each_database_on_its_own() {
+        let mut sessions = vec![];
+        let bad = |s: &Session| {
+            anyhow::ensure!(
+                !s.name.starts_with("bad"),
+                "{} is not a readable SQLite database",
+                s.name
+            );
+            Ok(())
+        };
+        let added = add_files(
+            &mut sessions,
+            vec![file("a.db"), file("bad.db"), file("b.db")],
+            bad,
+        );
+        assert_eq!(added.opened.as_deref(), Some("b.db"));
+        assert_eq!(
+            added.failed,
+            vec!["bad.db is not a readable SQLite database"]
+        );
+        let names: Vec<_> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["a.db", "b.db"]);
+
+        // 伴随文件使会话无法打开时，会话保持原样
+        let added = add_files(&mut sessions, vec![file("a.db-wal")], |s| {
+            anyhow::ensure!(s.wal.is_none(), "bad WAL");
+            Ok(())
+        });
+        assert_eq!(added.opened, None);
+        assert_eq!(added.failed, vec!["bad WAL"]);
+        assert!(sessions[0].wal.is_none());
+    }
+}
@@ -1,19 +1,54 @@
 use std::rc::Rc;
 
-use dioxus::signals::Signal;
+use anyhow::{bail, Result};
+use dioxus::signals::{Readable, Signal, Writable};
 
-use super::{viewer::Viewer, Field, Parts};
+use crate::parser::Descent;
+
+use super::{
+    edit::Edit,
+    i18n::Lang,
+    route::Route,
+    session::{self, Added, Session},
+    viewer::Viewer,
+    Field, Parts,
+};
 
 /// 全局共享状态
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct AppState {
-    /// 当前选中的数据库
+    /// 当前选中的数据库，对用户打开的文件是会话中主文件的名字
     pub current_db: Signal<String>,
     pub viewer: Signal<Viewer>,
     pub selected_part: Signal<Rc<dyn Parts>>,
     pub selected_field: Signal<Option<Field>>,
     /// 字段的显示格式
     pub format: Signal<Format>,
+    /// 最近一次 B-Tree 查找的下降路径
+    pub descent: Signal<Option<Descent>>,
+    /// 当前展示到下降路径的第几步
+    pub descent_step: Signal<usize>,
+    /// 用户对文件的修改
+    pub edit: Signal<Edit>,
+    /// 以十六进制编辑器代替字段显示选中的部分
+    pub hex_editor: Signal<bool>,
+    /// 在字段旁边显示选中部分的十六进制转储
+    pub hex_dump: Signal<bool>,
+    /// 在字段下面显示选中字节的各种解读
+    pub inspector: Signal<bool>,
+    /// 在字段下面显示表或索引的 B-Tree 图
+    pub diagram: Signal<bool>,
+    /// 用户在浏览器中打开的数据库和它们的伴随文件，按打开顺序排列
+    pub user_dbs: Signal<Vec<Session>>,
+    /// 展示把 WAL 写回后的数据库，而不是主文件本身
+    pub apply_wal: Signal<bool>,
+    /// 最近一次打开文件失败的原因
+    pub open_error: Signal<Option<String>>,
+    /// 说明文字的语言
+    pub lang: Signal<Lang>,
+    /// 比较模式中与当前数据库比较的数据库和它的名字，不比较时为 None 和空字符串
+    pub other: Signal<Option<Viewer>>,
+    pub other_db: Signal<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -38,7 +73,135 @@ impl AppState {
             selected_part: Signal::new(viewer.first_part()),
             selected_field: Signal::new(None),
             format: Signal::new(Format::Hybrid),
+            descent: Signal::new(None),
+            descent_step: Signal::new(0),
+            edit: Signal::new(Edit::new(&viewer)),
+            hex_editor: Signal::new(false),
+            hex_dump: Signal::new(false),
+            inspector: Signal::new(false),
+            diagram: Signal::new(false),
             viewer: Signal::new(viewer),
+            user_dbs: Signal::new(vec![]),
+            apply_wal: Signal::new(false),
+            open_error: Signal::new(None),
+            lang: Signal::new(Lang::En),
+            other: Signal::new(None),
+            other_db: Signal::new(String::new()),
+        }
+    }
+
+    /// 下拉菜单中的所有数据库：内置的示例，然后是用户打开的文件
+    pub fn db_names(&self) -> Vec<String> {
+        let mut names = self.viewer.read().included_dbnames();
+        names.extend(self.user_dbs.read().iter().map(|s| s.name.clone()));
+        names
+    }
+
+    /// 解析名为 name 的数据库，用户打开的文件优先于同名的示例
+    fn load(self, name: &str) -> Result<Viewer> {
+        let session = self
+            .user_dbs
+            .read()
+            .iter()
+            .find(|s| s.name == name)
+            .cloned();
+        match session {
+            Some(session) => Viewer::new_from_session(&session, *self.apply_wal.read()),
+            None => Viewer::new_from_included(name),
+        }
+    }
+
+    /// 切换到名为 name 的数据库
+    pub fn open_db(self, name: &str) -> Result<()> {
+        let viewer = self.load(name)?;
+        self.show(name.to_string(), viewer);
+        Ok(())
+    }
+
+    /// 与名为 name 的数据库比较，None 时退出比较模式
+    pub fn compare_with(mut self, name: Option<&str>) -> Result<()> {
+        let other = name.map(|name| self.load(name)).transpose()?;
+        *self.other_db.write() = name.unwrap_or_default().to_string();
+        *self.other.write() = other;
+        Ok(())
+    }
+
+    /// 加入用户打开的文件，按名字把伴随文件与主文件配对，然后切换到最后一个能打开的会话。
+    /// 每个数据库单独解析，无法解析的不加入列表，也不影响同一次打开的其它文件。
+    pub fn add_files(mut self, files: Vec<(String, Rc<[u8]>)>) -> Added {
+        let apply_wal = *self.apply_wal.read();
+        let mut sessions = self.user_dbs.read().clone();
+        let mut viewer = None;
+        let added = session::add_files(&mut sessions, files, |session| {
+            viewer = Some(Viewer::new_from_session(session, apply_wal)?);
+            Ok(())
+        });
+        *self.user_dbs.write() = sessions;
+        if let (Some(name), Some(viewer)) = (added.opened.clone(), viewer) {
+            self.show(name, viewer);
         }
+        added
+    }
+
+    /// 切换是否把 WAL 写回，重新打开当前的数据库
+    pub fn set_apply_wal(mut self, apply: bool) -> Result<()> {
+        let old = *self.apply_wal.read();
+        *self.apply_wal.write() = apply;
+        let result = self.open_db(&self.current_db.read().clone());
+        if result.is_err() {
+            *self.apply_wal.write() = old;
+        }
+        result
+    }
+
+    /// 打开地址指向的数据库，选中地址指向的部分和字段。地址已经指向当前的选择时不做任何事。
+    pub fn follow(mut self, route: &Route) -> Result<()> {
+        if self.at(route) {
+            return Ok(());
+        }
+        let Some(name) = route.db() else {
+            return Ok(());
+        };
+        if *self.current_db.read() != name {
+            if !self.db_names().iter().any(|n| n == name) {
+                bail!("The link points into {name}: open that file, then follow the link again");
+            }
+            self.open_db(name)?;
+        }
+        let (part, field) = route.target(&self.viewer.read())?;
+        *self.selected_part.write() = part;
+        *self.selected_field.write() = field;
+        Ok(())
+    }
+
+    /// 指向当前选择的地址
+    pub fn link(&self) -> Route {
+        Route::link(
+            &self.current_db.read(),
+            &self.viewer.read(),
+            &self.selected_part.read(),
+            self.selected_field.read().as_ref(),
+        )
+    }
+
+    /// route 是否指向当前的选择
+    pub fn at(&self, route: &Route) -> bool {
+        route.points_at(
+            &self.current_db.read(),
+            &self.viewer.read(),
+            &self.selected_part.read(),
+            self.selected_field.read().as_ref(),
+        )
+    }
+
+    /// 展示新的数据库，清除与之前的数据库相关的状态
+    fn show(mut self, name: String, viewer: Viewer) {
+        *self.current_db.write() = name;
+        *self.selected_part.write() = viewer.first_part();
+        *self.selected_field.write() = None;
+        *self.descent.write() = None;
+        *self.edit.write() = Edit::new(&viewer);
+        *self.open_error.write() = None;
+        *self.viewer.write() = viewer;
     }
 }
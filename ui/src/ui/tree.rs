@@ -0,0 +1,190 @@
+#![allow(non_snake_case)]
+
+use std::{ops::Range, rc::Rc};
+
+use dioxus::prelude::*;
+
+use super::{catalog, i18n::Text, state::AppState, viewer::CachedPart, Field, Parts};
+
+/// 侧栏中一个节点最多直接显示的子节点数，更多的页按页号分段
+const FANOUT: usize = 100;
+
+/// 按下标取出一页，下标 0 是第 1 页
+pub type PageFn = Rc<dyn Fn(usize) -> Option<Rc<dyn Parts>>>;
+
+/// 连续的一段页，展开后显示更小的段或其中的页
+struct PageRange {
+    pages: PageFn,
+    range: Range<usize>,
+}
+
+impl std::fmt::Debug for PageRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageRange")
+            .field("range", &self.range)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Parts for PageRange {
+    fn label(&self) -> String {
+        format!("Pages {}–{}", self.range.start + 1, self.range.end)
+    }
+
+    fn desc(&self) -> Text {
+        catalog::page_range(self.range.start + 1, self.range.end)
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        Rc::new([])
+    }
+
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        chunks(&self.pages, self.range.clone())
+    }
+
+    fn has_children(&self) -> bool {
+        true
+    }
+}
+
+/// range 中的页，超过 FANOUT 页时分成不超过 FANOUT 段，每段的页数是 FANOUT 的幂
+fn chunks(pages: &PageFn, range: Range<usize>) -> Vec<Rc<dyn Parts>> {
+    if range.len() <= FANOUT {
+        return range.filter_map(|i| pages(i)).collect();
+    }
+    let mut step = FANOUT;
+    while range.len().div_ceil(step) > FANOUT {
+        step *= FANOUT;
+    }
+    range
+        .clone()
+        .step_by(step)
+        .map(|start| {
+            let part = PageRange {
+                pages: pages.clone(),
+                range: start..(start + step).min(range.end),
+            };
+            Rc::new(CachedPart::new(Rc::new(part))) as Rc<dyn Parts>
+        })
+        .collect()
+}
+
+/// 侧栏中主文件的顶层节点：top 中的部分，然后是 count 页或页的分段。
+/// 页在所在的节点展开时才通过 pages 取出
+pub fn page_tree(top: &[Rc<dyn Parts>], count: usize, pages: PageFn) -> Vec<Rc<dyn Parts>> {
+    let mut tree = top.to_vec();
+    tree.extend(chunks(&pages, 0..count));
+    tree
+}
+
+/// 按指针比较的 part，作为组件的属性
+#[derive(Clone, Debug)]
+pub struct Node(pub Rc<dyn Parts>);
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// 侧栏中的一个节点。子节点在第一次展开时才生成；
+/// path 中的页在 B-Tree 查找路径上，高亮显示。
+#[component]
+pub fn TreeNode(node: Node, path: Vec<String>) -> Element {
+    let state = use_context::<AppState>();
+    let mut selected_part = state.selected_part;
+    let mut selected_field = state.selected_field;
+    let mut expanded = use_signal(|| false);
+    let part = node.0;
+    let label = part.label();
+    let selected = Rc::ptr_eq(&selected_part.read(), &part);
+    rsx! {
+        li {
+            div {
+                class: "flex items-center",
+                if part.has_children() {
+                    button {
+                        class: "btn-xs btn-ghost w-6 shrink-0",
+                        title: if expanded() { "Collapse" } else { "Expand" },
+                        onclick: move |_| expanded.toggle(),
+                        if expanded() { "▾" } else { "▸" }
+                    }
+                } else {
+                    span { class: "w-6 shrink-0" }
+                }
+                button {
+                    class: "w-full text-left btn-sm btn-ghost btn-block font-normal truncate",
+                    class: if selected {"btn-active"},
+                    class: if path.contains(&label) {"text-accent font-bold"},
+                    onclick: {
+                        let part = part.clone();
+                        move |_| {
+                            *selected_part.write() = part.clone();
+                            *selected_field.write() = None;
+                        }
+                    },
+                    "{label}",
+                }
+            }
+            if expanded() {
+                ul {
+                    class: "pl-4",
+                    for child in part.children() {
+                        TreeNode { node: Node(child), path: path.clone() }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::Viewer;
+
+    #[derive(Debug)]
+    struct Leaf(usize);
+
+    impl Parts for Leaf {
+        fn label(&self) -> String {
+            format!("Page {}", self.0)
+        }
+
+        fn desc(&self) -> Text {
+            Text::default()
+        }
+
+        fn fields(&self) -> Rc<[Field]> {
+            Rc::new([])
+        }
+    }
+
+    #[test]
+    fn group_pages_by_number() {
+        let pages: Vec<Rc<dyn Parts>> = (1..=100_000)
+            .map(|n| Rc::new(Leaf(n)) as Rc<dyn Parts>)
+            .collect();
+        let tree = page_tree(&[], pages.len(), {
+            let pages = pages.clone();
+            Rc::new(move |i| pages.get(i).cloned())
+        });
+        let labels: Vec<_> = tree.iter().map(|p| p.label()).collect();
+        assert_eq!(labels.len(), 10);
+        assert_eq!(labels[0], "Pages 1–10000");
+        assert_eq!(labels[9], "Pages 90001–100000");
+        let ranges = tree[9].children();
+        assert_eq!(ranges.len(), 100);
+        assert_eq!(ranges[99].label(), "Pages 99901–100000");
+        // 最后一层是原来的页，展开两次得到同样的节点
+        let leaves = ranges[99].children();
+        assert_eq!(leaves.len(), 100);
+        assert!(Rc::ptr_eq(&leaves[99], &pages[99_999]));
+        assert!(Rc::ptr_eq(&tree[9].children()[0], &ranges[0]));
+
+        // 页不多时直接列出
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        assert_eq!(viewer.tree.len(), viewer.parts.len() + 2);
+    }
+}
@@ -1,27 +1,337 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    ops::Range,
+    rc::{Rc, Weak},
+};
 
-use crate::parser::Reader;
+use crate::parser::{
+    reader::DEFAULT_CACHE_PAGES, Analysis, Coverage, Diagnostic, Lru, MemorySource, Reader, Span,
+    SpanKind, Wal,
+};
 
-use super::Parts;
-use anyhow::Result;
+use super::{
+    analysis::ANALYSIS_LABEL,
+    companion::{companion_files, CompanionFile},
+    diagnostics::DIAGNOSTICS_LABEL,
+    i18n::Text,
+    session::{FileKind, Session},
+    tree::page_tree,
+    Field, Parts,
+};
+use anyhow::{anyhow, Context, Result};
 
-pub const SIMPLE_DB: &'static [u8] = include_bytes!("../../examples/simple");
-pub const BIG_PAGE_DB: &'static [u8] = include_bytes!("../../examples/big_page");
+pub const SIMPLE_DB: &[u8] = include_bytes!("../../examples/simple");
+pub const BIG_PAGE_DB: &[u8] = include_bytes!("../../examples/big_page");
+
+/// 最多缓存多少页的 part 和字节覆盖
+const RECENT_PAGES: usize = 64;
+
+fn included_dbs() -> HashMap<&'static str, &'static [u8]> {
+    HashMap::from([("Simple", SIMPLE_DB), ("Big Page", BIG_PAGE_DB)])
+}
+
+/// 缓存 label 和字段的 part。界面每次渲染都要读取它们，
+/// 一页 64 KiB、有上千个单元时重新生成字段的开销很大。
+#[derive(Debug)]
+pub struct CachedPart {
+    part: Rc<dyn Parts>,
+    label: OnceCell<String>,
+    fields: OnceCell<Rc<[Field]>>,
+    children: OnceCell<Vec<Rc<dyn Parts>>>,
+}
+
+impl CachedPart {
+    pub fn new(part: Rc<dyn Parts>) -> Self {
+        Self {
+            part,
+            label: OnceCell::new(),
+            fields: OnceCell::new(),
+            children: OnceCell::new(),
+        }
+    }
+}
+
+impl Parts for CachedPart {
+    fn label(&self) -> String {
+        self.label.get_or_init(|| self.part.label()).clone()
+    }
+
+    fn desc(&self) -> Text {
+        self.part.desc()
+    }
+
+    /// 第一次调用时生成，之后返回同一份
+    fn fields(&self) -> Rc<[Field]> {
+        self.fields.get_or_init(|| self.part.fields()).clone()
+    }
+
+    /// 第一次展开时生成，之后返回同样的 Rc，侧栏可以按指针比较选中的部分
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        self.children.get_or_init(|| self.part.children()).clone()
+    }
+
+    fn has_children(&self) -> bool {
+        self.part.has_children()
+    }
+
+    fn location(&self) -> Option<(u32, Range<usize>)> {
+        self.part.location()
+    }
+}
+
+/// 第一次用到时才生成的 part。空间分析和问题列表要遍历所有 B-Tree，打开文件时不生成
+pub struct LazyPart<T> {
+    label: &'static str,
+    make: Box<dyn Fn() -> T>,
+    value: OnceCell<Rc<T>>,
+}
+
+impl<T> LazyPart<T> {
+    pub fn new(label: &'static str, make: impl Fn() -> T + 'static) -> Self {
+        Self {
+            label,
+            make: Box::new(make),
+            value: OnceCell::new(),
+        }
+    }
+
+    pub fn get(&self) -> Rc<T> {
+        self.value.get_or_init(|| Rc::new((self.make)())).clone()
+    }
+}
+
+impl<T> std::fmt::Debug for LazyPart<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyPart")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 按指针比较，同一个 part 才相等，不会因比较而生成内容
+impl<T> PartialEq for LazyPart<T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl<T: Parts> Parts for LazyPart<T> {
+    fn label(&self) -> String {
+        self.label.to_string()
+    }
+
+    fn desc(&self) -> Text {
+        self.get().desc()
+    }
+
+    fn fields(&self) -> Rc<[Field]> {
+        self.get().fields()
+    }
+
+    fn children(&self) -> Vec<Rc<dyn Parts>> {
+        self.get().children()
+    }
+
+    /// 侧栏渲染折叠的节点时也会调用，不能因此生成内容。空间分析和问题列表都没有下一层
+    fn has_children(&self) -> bool {
+        false
+    }
+}
+
+/// 按需生成的页 part。最近用过的页保留在 recent 中；界面仍在使用的页（例如选中的页、
+/// 展开的侧栏节点）通过弱引用找回同一个 Rc，界面可以按指针比较。
+#[derive(Debug)]
+pub struct PageParts {
+    reader: Rc<Reader>,
+    recent: RefCell<Lru<Rc<dyn Parts>>>,
+    live: RefCell<HashMap<u32, Weak<dyn Parts>>>,
+}
+
+impl PageParts {
+    fn new(reader: Rc<Reader>) -> Self {
+        Self {
+            reader,
+            recent: RefCell::new(Lru::new(RECENT_PAGES)),
+            live: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// 第 number 页的 part，页号超出文件时为 None
+    pub fn get(&self, number: u32) -> Option<Rc<dyn Parts>> {
+        if let Some(part) = self.recent.borrow_mut().get(number) {
+            return Some(part);
+        }
+        let live = self.live.borrow().get(&number).and_then(Weak::upgrade);
+        let part = match live {
+            Some(part) => part,
+            None => {
+                let page = self.reader.page(number)?;
+                let part: Rc<dyn Parts> = Rc::new(CachedPart::new(page));
+                let mut live = self.live.borrow_mut();
+                if live.len() >= 2 * RECENT_PAGES {
+                    live.retain(|_, p| p.strong_count() > 0);
+                }
+                live.insert(number, Rc::downgrade(&part));
+                part
+            }
+        };
+        self.recent.borrow_mut().insert(number, part.clone());
+        Some(part)
+    }
+}
 
 #[derive(Debug)]
 pub struct Viewer {
     pub include_db: HashMap<&'static str, &'static [u8]>,
+    /// 当前数据库文件的全部内容
+    pub bytes: Rc<[u8]>,
+    /// 按需读取 bytes 中的页，只有用到的页才被解析
+    pub reader: Rc<Reader>,
+    /// 数据库头、空间分析和解析中发现的问题，都由 CachedPart 包装
     pub parts: Vec<Rc<dyn Parts>>,
+    analysis: Rc<LazyPart<Analysis>>,
+    diagnostics: Rc<LazyPart<Vec<Diagnostic>>>,
+    /// 每一页，第一次用到时才生成
+    pages: Rc<PageParts>,
+    /// 最近用过的页的字节覆盖
+    coverage: RefCell<Lru<Rc<Coverage>>>,
+    /// 侧栏中主文件的顶层节点。页多时按页号分段，展开后才显示其中的页
+    pub tree: Vec<Rc<dyn Parts>>,
+    /// 与数据库一起打开的 -wal、-shm、-journal 文件
+    pub companions: Rc<[CompanionFile]>,
 }
 
 impl Viewer {
     pub fn new_from_included(name: &str) -> Result<Self> {
-        let include_db = HashMap::from([("Simple", SIMPLE_DB), ("Big Page", BIG_PAGE_DB)]);
-        let bytes = include_db.get(name).unwrap();
-        let reader = Reader::new(bytes)?;
+        let bytes = included_dbs()
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("No included database named {name}"))?;
+        Self::new(Rc::from(bytes))
+    }
+
+    /// 从用户在浏览器中打开的文件创建，name 是文件名，解析失败时出现在错误信息中
+    pub fn new_from_file(name: &str, bytes: Rc<[u8]>) -> Result<Self> {
+        Self::new(bytes).with_context(|| format!("{name} is not a readable SQLite database"))
+    }
+
+    /// 打开会话中的主文件和伴随文件。apply_wal 时展示把 WAL 中已提交的帧写回后的数据库，
+    /// 与 SQLite 读到的内容相同。
+    pub fn new_from_session(session: &Session, apply_wal: bool) -> Result<Self> {
+        let bytes = match &session.wal {
+            Some(wal) if apply_wal => Rc::from(Wal::parse(wal.clone())?.apply(&session.db)?),
+            _ => session.db.clone(),
+        };
+        Ok(Self::new_from_file(&session.name, bytes)?
+            .with_companions(companion_files(session).into()))
+    }
+
+    /// 替换伴随文件，例如修改后重新解析主文件时保留之前的伴随文件
+    pub fn with_companions(mut self, companions: Rc<[CompanionFile]>) -> Self {
+        self.companions = companions;
+        self
+    }
+
+    /// 是否有 WAL 文件
+    pub fn has_wal(&self) -> bool {
+        self.companions.iter().any(|c| c.kind == FileKind::Wal)
+    }
+
+    /// part 是否属于主文件。伴随文件中的字段偏移不是主文件中的偏移，不能编辑。
+    pub fn in_main_file(&self, part: &Rc<dyn Parts>) -> bool {
+        part.location().is_some() || self.parts.iter().any(|p| Rc::ptr_eq(p, part))
+    }
+
+    /// 从内存中的数据库文件创建，例如运行时加载的文件。只读取数据库头和 sqlite_schema，
+    /// 页、空间分析和问题列表都在用到时才生成
+    pub fn new(bytes: Rc<[u8]>) -> Result<Self> {
+        let reader = Rc::new(Reader::open(
+            MemorySource(bytes.clone()),
+            DEFAULT_CACHE_PAGES,
+        )?);
         let header: Rc<dyn Parts> = reader.header.clone();
-        let parts = vec![header];
-        Ok(Self { include_db, parts })
+        let analysis = Rc::new(LazyPart::new(ANALYSIS_LABEL, {
+            let reader = reader.clone();
+            move || {
+                reader.load_all();
+                reader.analyze()
+            }
+        }));
+        let diagnostics = Rc::new(LazyPart::new(DIAGNOSTICS_LABEL, {
+            let reader = reader.clone();
+            move || {
+                reader.load_all();
+                reader.diagnostics()
+            }
+        }));
+        let parts: Vec<Rc<dyn Parts>> = [header, analysis.clone(), diagnostics.clone()]
+            .into_iter()
+            .map(|p| Rc::new(CachedPart::new(p)) as Rc<dyn Parts>)
+            .collect();
+        let pages = Rc::new(PageParts::new(reader.clone()));
+        let tree = page_tree(&parts, reader.page_count() as usize, {
+            let pages = pages.clone();
+            Rc::new(move |i| pages.get(i as u32 + 1))
+        });
+        Ok(Self {
+            include_db: included_dbs(),
+            bytes,
+            reader,
+            parts,
+            analysis,
+            diagnostics,
+            pages,
+            coverage: RefCell::new(Lru::new(RECENT_PAGES)),
+            tree,
+            companions: Rc::new([]),
+        })
+    }
+
+    /// 空间分析，第一次调用时遍历所有 B-Tree
+    pub fn analysis(&self) -> Rc<Analysis> {
+        self.analysis.get()
+    }
+
+    /// 整个文件中的问题，第一次调用时遍历所有 B-Tree
+    pub fn diagnostics(&self) -> Rc<Vec<Diagnostic>> {
+        self.diagnostics.get()
+    }
+
+    /// 尚未生成的问题列表，可以在 Viewer 之外保留，用到时才遍历
+    pub fn lazy_diagnostics(&self) -> Rc<LazyPart<Vec<Diagnostic>>> {
+        self.diagnostics.clone()
+    }
+
+    /// 第 number 页中每个结构占用的字节
+    fn page_coverage(&self, number: u32) -> Rc<Coverage> {
+        if let Some(coverage) = self.coverage.borrow_mut().get(number) {
+            return coverage;
+        }
+        let coverage = Rc::new(self.reader.coverage_of([number]));
+        self.coverage.borrow_mut().insert(number, coverage.clone());
+        coverage
+    }
+
+    /// 覆盖 offset 的所有结构，从外到内
+    pub fn explain(&self, offset: usize) -> Vec<Span> {
+        let page_size = self.reader.header.real_page_size();
+        self.page_coverage((offset / page_size) as u32 + 1)
+            .explain(offset)
+    }
+
+    /// range 中每个字节最内层的结构
+    pub fn innermost(&self, range: Range<usize>) -> Vec<Option<SpanKind>> {
+        let page_size = self.reader.header.real_page_size();
+        let first = (range.start / page_size) as u32 + 1;
+        let last = (range.end.max(range.start + 1) - 1) / page_size + 1;
+        if first as usize == last {
+            self.page_coverage(first).innermost(range)
+        } else {
+            self.reader
+                .coverage_of(first..=last as u32)
+                .innermost(range)
+        }
     }
 
     pub fn included_dbnames(&self) -> Vec<String> {
@@ -31,4 +341,170 @@ impl Viewer {
     pub fn first_part(&self) -> Rc<dyn Parts> {
         self.parts[0].clone()
     }
+
+    /// 第 number 页对应的 Parts
+    pub fn page_part(&self, number: u32) -> Option<Rc<dyn Parts>> {
+        self.pages.get(number)
+    }
+
+    /// part 对应的页号，页中的子部分是它所在的页，不是页的部分没有
+    pub fn page_number(&self, part: &Rc<dyn Parts>) -> Option<u32> {
+        part.location().map(|(number, _)| number)
+    }
+
+    /// part 在文件中占用的字节：数据库头为前 100 字节，页为整页，页中的子部分为它自己的字节，
+    /// 其它部分没有
+    pub fn part_range(&self, part: &Rc<dyn Parts>) -> Option<Range<usize>> {
+        if let Some((_, range)) = part.location() {
+            return Some(range);
+        }
+        Rc::ptr_eq(part, &self.parts[0]).then_some(0..100)
+    }
+
+    /// 包含文件中第 offset 字节的部分：前 100 字节属于数据库头，其它属于所在的页
+    pub fn part_at(&self, offset: usize) -> Option<Rc<dyn Parts>> {
+        if offset >= self.bytes.len() {
+            return None;
+        }
+        if offset < 100 {
+            return Some(self.first_part());
+        }
+        let page_size = self.reader.header.real_page_size();
+        self.page_part((offset / page_size + 1) as u32)
+    }
+}
+
+/// part 中包含第 offset 字节的最小的字段
+pub fn field_at(part: &Rc<dyn Parts>, offset: usize) -> Option<Field> {
+    part.fields()
+        .iter()
+        .filter(|f| f.offset <= offset && offset < f.offset + f.size)
+        .min_by_key(|f| f.size)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::RecordValue,
+        writer::{wal::build_wal, DatabaseBuilder},
+    };
+
+    #[test]
+    fn fields_are_built_once() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        for part in &viewer.parts {
+            assert!(Rc::ptr_eq(&part.fields(), &part.fields()));
+        }
+        let page = viewer.page_part(2).unwrap();
+        assert_eq!(page.label(), "Page 2 (Leaf Table)");
+        assert!(!page.fields().is_empty());
+    }
+
+    #[test]
+    fn expand_page_into_columns() {
+        let viewer = Viewer::new_from_included("Simple").unwrap();
+        let page = viewer.page_part(2).unwrap();
+        assert!(page.has_children());
+        let cells = page.children();
+        assert!(Rc::ptr_eq(&cells[0], &page.children()[0]));
+        let cell = &cells[1];
+        assert!(cell.label().starts_with("Cell 1"));
+        assert!(viewer.in_main_file(cell));
+        assert_eq!(viewer.page_number(cell), Some(2));
+        let range = viewer.part_range(cell).unwrap();
+        let page_range = viewer.part_range(&page).unwrap();
+        assert!(page_range.start <= range.start && range.end <= page_range.end);
+        // 单元的字段是页中以 Cell 1 开头的字段
+        let expected: Vec<_> = page
+            .fields()
+            .iter()
+            .filter(|f| f.desc.en.starts_with("Cell 1:"))
+            .cloned()
+            .collect();
+        assert_eq!(&cell.fields()[..], &expected[..]);
+
+        let record = &cell.children()[0];
+        assert_eq!(record.label(), "Record");
+        let columns = record.children();
+        let record_fields = record.fields();
+        assert_eq!(record_fields.len(), 1 + 2 * columns.len());
+        for column in &columns {
+            let (number, range) = column.location().unwrap();
+            assert_eq!(number, 2);
+            let value = &column.fields()[1];
+            assert!(column.label().contains(" = "));
+            assert!(range.start <= value.offset && value.offset <= range.end);
+            assert!(!column.has_children());
+        }
+    }
+
+    #[test]
+    fn pages_are_parsed_on_demand() {
+        let rows = (0..400)
+            .map(|i| vec![RecordValue::Integer(i), RecordValue::Text("x".repeat(100))])
+            .collect();
+        let bytes = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a, b)", rows)
+            .build()
+            .unwrap();
+        let viewer = Viewer::new(Rc::from(bytes)).unwrap();
+        // 打开时只读取 sqlite_schema
+        assert_eq!(viewer.reader.cache().reads(), 1);
+        let page = viewer.page_part(3).unwrap();
+        assert!(Rc::ptr_eq(&page, &viewer.page_part(3).unwrap()));
+        assert_eq!(viewer.part_range(&page), Some(1024..1536));
+        assert_eq!(viewer.page_number(&page), Some(3));
+
+        // 被淘汰后，界面仍在使用的页还是同一个 part
+        let count = viewer.reader.page_count();
+        let unused = Rc::downgrade(&viewer.page_part(4).unwrap());
+        for n in 5..=count {
+            viewer.page_part(n).unwrap();
+        }
+        assert!(count as usize > RECENT_PAGES + 4);
+        assert!(unused.upgrade().is_none());
+        assert!(Rc::ptr_eq(&page, &viewer.page_part(3).unwrap()));
+
+        assert_eq!(viewer.analysis().total_pages, count as usize);
+        assert!(viewer.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn open_user_file() {
+        let row = vec![RecordValue::Integer(7), RecordValue::Null];
+        let bytes = DatabaseBuilder::new(1024)
+            .table("CREATE TABLE t(a, b)", vec![row; 3])
+            .build()
+            .unwrap();
+        let viewer = Viewer::new_from_file("mine.db", Rc::from(bytes)).unwrap();
+        assert_eq!(viewer.reader.header.real_page_size(), 1024);
+        assert!(viewer.reader.schema.iter().any(|e| e.name == "t"));
+
+        let e = Viewer::new_from_file("notes.db", Rc::from(&b"hello"[..])).unwrap_err();
+        assert!(format!("{e:#}").starts_with("notes.db is not a readable SQLite database"));
+    }
+
+    #[test]
+    fn apply_wal_of_session() {
+        let old = DatabaseBuilder::new(512).build().unwrap();
+        let row = vec![RecordValue::Integer(1)];
+        let new = DatabaseBuilder::new(512)
+            .table("CREATE TABLE t(a)", vec![row])
+            .build()
+            .unwrap();
+        let wal = build_wal(512, &[(1, &new[..512], 0), (2, &new[512..], 2)]);
+        let mut session = Session::new("w.db".to_string(), Rc::from(old));
+        session.wal = Some(Rc::from(wal));
+
+        let raw = Viewer::new_from_session(&session, false).unwrap();
+        assert!(raw.has_wal());
+        assert_eq!(raw.reader.schema.len(), 1);
+        let applied = Viewer::new_from_session(&session, true).unwrap();
+        assert!(applied.reader.schema.iter().any(|e| e.name == "t"));
+        assert_eq!(applied.companions[0].parts.len(), 3);
+        assert!(!applied.in_main_file(&applied.companions[0].parts[0]));
+        assert!(applied.in_main_file(&applied.first_part()));
+    }
 }
@@ -0,0 +1,258 @@
+use crate::parser::{page::local_payload_size, varint::encode_varint, PageType};
+
+/// 正在生成的文件中的所有页，页号从 1 开始
+#[derive(Debug)]
+pub struct Pages {
+    page_size: usize,
+    usable_size: usize,
+    pages: Vec<Vec<u8>>,
+}
+
+impl Pages {
+    pub fn new(page_size: usize, usable_size: usize) -> Self {
+        Self {
+            page_size,
+            usable_size,
+            pages: vec![],
+        }
+    }
+
+    /// 在文件末尾分配一个全 0 的新页
+    pub fn alloc(&mut self) -> u32 {
+        self.pages.push(vec![0; self.page_size]);
+        self.pages.len() as u32
+    }
+
+    pub fn count(&self) -> u32 {
+        self.pages.len() as u32
+    }
+
+    pub fn page_mut(&mut self, number: u32) -> &mut [u8] {
+        &mut self.pages[number as usize - 1]
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.pages.concat()
+    }
+
+    /// 页头的位置，第 1 页的页头在数据库头之后
+    fn header_offset(number: u32) -> usize {
+        if number == 1 {
+            100
+        } else {
+            0
+        }
+    }
+
+    /// 负载的本地部分，超出的部分写入新分配的溢出页，本地部分之后是第一个溢出页的页号
+    fn payload_cell(&mut self, page_type: PageType, payload: &[u8]) -> Vec<u8> {
+        let local = local_payload_size(page_type, self.usable_size, payload.len());
+        let mut cell = payload[..local].to_vec();
+        if local == payload.len() {
+            return cell;
+        }
+        let chunks: Vec<&[u8]> = payload[local..].chunks(self.usable_size - 4).collect();
+        let numbers: Vec<u32> = chunks.iter().map(|_| self.alloc()).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let next = numbers.get(i + 1).copied().unwrap_or(0);
+            let page = self.page_mut(numbers[i]);
+            page[..4].copy_from_slice(&next.to_be_bytes());
+            page[4..4 + chunk.len()].copy_from_slice(chunk);
+        }
+        cell.extend(numbers[0].to_be_bytes());
+        cell
+    }
+
+    /// 写入一个 B-Tree 页。第一个单元放在页的最末尾，之后的单元依次向前排列。
+    fn write_page(
+        &mut self,
+        number: u32,
+        page_type: PageType,
+        cells: &[Vec<u8>],
+        right: Option<u32>,
+    ) {
+        let usable = self.usable_size;
+        let h = Self::header_offset(number);
+        let page = self.page_mut(number);
+        page[h] = page_type.to_u8();
+        page[h + 3..h + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+        let mut pointer = h + 8;
+        if let Some(right) = right {
+            page[h + 8..h + 12].copy_from_slice(&right.to_be_bytes());
+            pointer += 4;
+        }
+        let mut content = usable;
+        for cell in cells {
+            content -= cell.len();
+            page[content..content + cell.len()].copy_from_slice(cell);
+            page[pointer..pointer + 2].copy_from_slice(&(content as u16).to_be_bytes());
+            pointer += 2;
+        }
+        // 65536 写作 0
+        page[h + 5..h + 7].copy_from_slice(&(content as u16).to_be_bytes());
+    }
+
+    /// 叶子页和内部页中可以放单元和单元指针的字节数
+    fn capacity(&self, root: u32, leaf: bool) -> usize {
+        // 根页是第 1 页时，整棵树都按第 1 页的大小排列
+        self.usable_size - Self::header_offset(root) - if leaf { 8 } else { 12 }
+    }
+
+    /// 按 rowid 顺序排列的行生成一棵表 B-Tree，根页为 root
+    pub fn build_table(&mut self, root: u32, rows: &[(i64, Vec<u8>)]) {
+        let cells: Vec<Vec<u8>> = rows
+            .iter()
+            .map(|(rowid, payload)| {
+                let mut cell = encode_varint(payload.len() as i64);
+                cell.extend(encode_varint(*rowid));
+                cell.extend(self.payload_cell(PageType::LeafTable, payload));
+                cell
+            })
+            .collect();
+
+        let capacity = self.capacity(root, true);
+        let mut groups: Vec<Vec<Vec<u8>>> = vec![vec![]];
+        let mut last_rowids = vec![];
+        let mut used = 0;
+        for (cell, (rowid, _)) in cells.into_iter().zip(rows) {
+            let cost = cell.len() + 2;
+            if used + cost > capacity && !groups[groups.len() - 1].is_empty() {
+                groups.push(vec![]);
+                used = 0;
+            }
+            used += cost;
+            groups.last_mut().unwrap().push(cell);
+            if last_rowids.len() < groups.len() {
+                last_rowids.push(*rowid);
+            } else {
+                *last_rowids.last_mut().unwrap() = *rowid;
+            }
+        }
+        if groups.len() == 1 {
+            self.write_page(root, PageType::LeafTable, &groups[0], None);
+            return;
+        }
+        let children = groups
+            .iter()
+            .map(|cells| {
+                let n = self.alloc();
+                self.write_page(n, PageType::LeafTable, cells, None);
+                n
+            })
+            .collect();
+        // 内部页中的键是左子树中最大的 rowid
+        let separators = last_rowids[..last_rowids.len() - 1]
+            .iter()
+            .map(|&rowid| encode_varint(rowid))
+            .collect();
+        self.build_interior(root, PageType::InteriorTable, children, separators);
+    }
+
+    /// 按键的顺序排列的 record 生成一棵索引 B-Tree（或 WITHOUT ROWID 表），根页为 root
+    pub fn build_index(&mut self, root: u32, keys: &[Vec<u8>]) {
+        let cells: Vec<Vec<u8>> = keys
+            .iter()
+            .map(|payload| {
+                let mut cell = encode_varint(payload.len() as i64);
+                cell.extend(self.payload_cell(PageType::LeafIndex, payload));
+                cell
+            })
+            .collect();
+
+        // 叶子页写满后，下一个条目提升到上一层，作为两个叶子页之间的分隔
+        let capacity = self.capacity(root, true);
+        let mut groups: Vec<Vec<Vec<u8>>> = vec![vec![]];
+        let mut separators = vec![];
+        let mut used = 0;
+        for cell in cells {
+            let cost = cell.len() + 2;
+            if used + cost > capacity && !groups[groups.len() - 1].is_empty() {
+                separators.push(cell);
+                groups.push(vec![]);
+                used = 0;
+                continue;
+            }
+            used += cost;
+            groups.last_mut().unwrap().push(cell);
+        }
+        // 最后一个条目成了分隔时，改用前一页的最后一个条目分隔
+        if groups.len() > 1 && groups[groups.len() - 1].is_empty() {
+            let n = groups.len();
+            let moved = separators.pop().unwrap();
+            separators.push(groups[n - 2].pop().unwrap());
+            groups[n - 1].push(moved);
+        }
+        if groups.len() == 1 {
+            self.write_page(root, PageType::LeafIndex, &groups[0], None);
+            return;
+        }
+        let children = groups
+            .iter()
+            .map(|cells| {
+                let n = self.alloc();
+                self.write_page(n, PageType::LeafIndex, cells, None);
+                n
+            })
+            .collect();
+        self.build_interior(root, PageType::InteriorIndex, children, separators);
+    }
+
+    /// 逐层生成内部页，直到只剩一页，写入根页。
+    /// separators[i] 是 children[i] 和 children[i + 1] 之间的键（不含左子页指针的单元内容）。
+    fn build_interior(
+        &mut self,
+        root: u32,
+        page_type: PageType,
+        mut children: Vec<u32>,
+        mut separators: Vec<Vec<u8>>,
+    ) {
+        let capacity = self.capacity(root, false);
+        loop {
+            // 每页的 (子页, 键) 单元和最右子页
+            let mut pages: Vec<(Vec<Vec<u8>>, u32)> = vec![];
+            let mut promoted = vec![];
+            let mut cells = vec![];
+            let mut used = 0;
+            for (i, separator) in separators.into_iter().enumerate() {
+                let cost = 4 + separator.len() + 2;
+                if used + cost > capacity && !cells.is_empty() {
+                    pages.push((std::mem::take(&mut cells), children[i]));
+                    promoted.push(separator);
+                    used = 0;
+                    continue;
+                }
+                used += cost;
+                let mut cell = children[i].to_be_bytes().to_vec();
+                cell.extend(separator);
+                cells.push(cell);
+            }
+            let last = *children.last().unwrap();
+            // 最后一页只剩最右子页时，从前一页借一个单元
+            if cells.is_empty() && !pages.is_empty() {
+                let (prev, right) = pages.last_mut().unwrap();
+                let borrowed = prev.pop().unwrap();
+                let mut cell = right.to_be_bytes().to_vec();
+                cell.extend(promoted.pop().unwrap());
+                cells.push(cell);
+                *right = u32::from_be_bytes(borrowed[..4].try_into().unwrap());
+                promoted.push(borrowed[4..].to_vec());
+            }
+            pages.push((cells, last));
+
+            if pages.len() == 1 {
+                let (cells, right) = &pages[0];
+                self.write_page(root, page_type, cells, Some(*right));
+                return;
+            }
+            children = pages
+                .iter()
+                .map(|(cells, right)| {
+                    let n = self.alloc();
+                    self.write_page(n, page_type, cells, Some(*right));
+                    n
+                })
+                .collect();
+            separators = promoted;
+        }
+    }
+}
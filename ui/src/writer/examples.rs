@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use crate::parser::RecordValue;
+
+use super::DatabaseBuilder;
+
+/// 生成内置的示例数据库，与 ui/examples 中的文件逐字节相同
+pub fn examples() -> Result<Vec<(&'static str, Vec<u8>)>> {
+    Ok(vec![
+        ("simple", one_column_table(4096, "simple").build()?),
+        ("big_page", one_column_table(65536, "big_page").build()?),
+    ])
+}
+
+/// 只有一列的表 name，保存 1 到 4 四行
+fn one_column_table(page_size: usize, name: &str) -> DatabaseBuilder {
+    let rows = (1..=4).map(|i| vec![RecordValue::Integer(i)]).collect();
+    DatabaseBuilder::new(page_size)
+        .file_change_counter(2)
+        .sqlite_version(3037002)
+        .table(&format!("CREATE TABLE {name}(int)"), rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::viewer::{BIG_PAGE_DB, SIMPLE_DB};
+
+    #[test]
+    fn reproduce_included_examples() {
+        let examples = examples().unwrap();
+        assert_eq!(examples[0].1, SIMPLE_DB);
+        assert_eq!(examples[1].1, BIG_PAGE_DB);
+    }
+}
@@ -0,0 +1,631 @@
+pub mod btree;
+pub mod examples;
+pub mod record;
+pub mod wal;
+
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::parser::{
+    compare::{apply_affinity, compare_key},
+    schema::{parse_create, CreateTable, IndexedColumn},
+    Affinity, DBHeader, RecordValue, SchemaEntry, Statement, TextEncoding,
+};
+
+pub use btree::Pages;
+pub use record::encode_record;
+
+/// 按创建顺序保存的对象
+#[derive(Debug, Clone)]
+enum Object {
+    /// CREATE TABLE 语句和各行的值，值按 CREATE TABLE 中声明的顺序排列
+    Table {
+        sql: String,
+        rows: Vec<Vec<RecordValue>>,
+    },
+    Index {
+        sql: String,
+    },
+}
+
+/// 由 Rust 中描述的表和行生成 SQLite 数据库文件，不依赖 sqlite3 命令行工具
+///
+/// ```
+/// use ui::parser::RecordValue;
+/// use ui::writer::DatabaseBuilder;
+///
+/// let bytes = DatabaseBuilder::new(4096)
+///     .table(
+///         "CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT)",
+///         vec![
+///             vec![RecordValue::Integer(1), RecordValue::Text("one".into())],
+///             vec![RecordValue::Null, RecordValue::Text("two".into())],
+///         ],
+///     )
+///     .index("CREATE INDEX t_name ON t(name)")
+///     .build()
+///     .unwrap();
+/// assert_eq!(bytes.len(), 3 * 4096);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseBuilder {
+    page_size: usize,
+    reserved_bytes: u8,
+    encoding: TextEncoding,
+    user_version: u32,
+    application_id: u32,
+    file_change_counter: u32,
+    sqlite_version: u32,
+    objects: Vec<Object>,
+}
+
+impl DatabaseBuilder {
+    /// page_size 是 512 到 65536 之间的 2 的幂
+    pub fn new(page_size: usize) -> Self {
+        Self {
+            page_size,
+            reserved_bytes: 0,
+            encoding: TextEncoding::UTF8,
+            user_version: 0,
+            application_id: 0,
+            file_change_counter: 1,
+            sqlite_version: 3045000,
+            objects: vec![],
+        }
+    }
+
+    /// 每页末尾保留的字节数
+    pub fn reserved_bytes(mut self, n: u8) -> Self {
+        self.reserved_bytes = n;
+        self
+    }
+
+    pub fn encoding(mut self, encoding: TextEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn user_version(mut self, version: u32) -> Self {
+        self.user_version = version;
+        self
+    }
+
+    pub fn application_id(mut self, id: u32) -> Self {
+        self.application_id = id;
+        self
+    }
+
+    /// 文件修改计数，同时写入 version-valid-for
+    pub fn file_change_counter(mut self, n: u32) -> Self {
+        self.file_change_counter = n;
+        self
+    }
+
+    /// 写入数据库头的 SQLite 版本号，例如 3045000
+    pub fn sqlite_version(mut self, version: u32) -> Self {
+        self.sqlite_version = version;
+        self
+    }
+
+    /// 添加一张表。INTEGER PRIMARY KEY 列为 NULL 的行自动分配 rowid。
+    pub fn table(mut self, sql: &str, rows: Vec<Vec<RecordValue>>) -> Self {
+        self.objects.push(Object::Table {
+            sql: sql.to_string(),
+            rows,
+        });
+        self
+    }
+
+    /// 为之前添加的表建索引，不支持表达式索引和部分索引
+    pub fn index(mut self, sql: &str) -> Self {
+        self.objects.push(Object::Index {
+            sql: sql.to_string(),
+        });
+        self
+    }
+
+    /// 生成数据库文件
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let page_size = self.page_size;
+        if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+            bail!("Invalid page size {page_size}");
+        }
+        let usable_size = page_size - self.reserved_bytes as usize;
+        if usable_size < 480 {
+            bail!("Usable size {usable_size} is less than 480");
+        }
+        let trees = self.trees()?;
+
+        let mut pages = Pages::new(page_size, usable_size);
+        pages.alloc();
+        // 先按 sqlite_schema 中的顺序分配根页，其余的页在生成每棵树时分配
+        let roots: Vec<u32> = trees.iter().map(|_| pages.alloc()).collect();
+        let schema_rows: Vec<(i64, Vec<u8>)> = trees
+            .iter()
+            .zip(&roots)
+            .enumerate()
+            .map(|(i, (tree, &root))| {
+                let entry = &tree.entry;
+                let values = [
+                    RecordValue::Text(entry.kind.clone()),
+                    RecordValue::Text(entry.name.clone()),
+                    RecordValue::Text(entry.tbl_name.clone()),
+                    RecordValue::Integer(root as i64),
+                    entry
+                        .sql
+                        .clone()
+                        .map_or(RecordValue::Null, RecordValue::Text),
+                ];
+                (i as i64 + 1, encode_record(&values, self.encoding))
+            })
+            .collect();
+        pages.build_table(1, &schema_rows);
+        for (tree, root) in trees.iter().zip(roots) {
+            match &tree.content {
+                Content::Rows(rows) => pages.build_table(root, rows),
+                Content::Keys(keys) => pages.build_index(root, keys),
+            }
+        }
+
+        let header = DBHeader::new(
+            *b"SQLite format 3\0",
+            // 65536 写作 1
+            if page_size == 65536 {
+                1
+            } else {
+                page_size as u16
+            },
+            1,
+            1,
+            self.reserved_bytes,
+            64,
+            32,
+            32,
+            self.file_change_counter,
+            pages.count(),
+            0,
+            0,
+            if trees.is_empty() { 0 } else { 1 },
+            4,
+            0,
+            0,
+            match self.encoding {
+                TextEncoding::UTF8 => 1,
+                TextEncoding::UTF16le => 2,
+                TextEncoding::UTF16be => 3,
+            },
+            self.user_version,
+            0,
+            self.application_id,
+            &[0; 20],
+            self.file_change_counter,
+            self.sqlite_version,
+        );
+        pages.page_mut(1)[..100].copy_from_slice(&header.to_bytes());
+        Ok(pages.into_bytes())
+    }
+
+    /// 按 sqlite_schema 中的顺序排列的 B-Tree：每张表之后是它的自动索引
+    fn trees(&self) -> Result<Vec<Tree>> {
+        let mut trees = vec![];
+        let mut tables: Vec<(CreateTable, Rows)> = vec![];
+        for object in &self.objects {
+            match object {
+                Object::Table { sql, rows } => {
+                    let entry = entry("table", sql, None)?;
+                    let Some(table) = entry.table().cloned() else {
+                        bail!("Not a CREATE TABLE statement: {sql}");
+                    };
+                    if tables
+                        .iter()
+                        .any(|(t, _)| t.name.eq_ignore_ascii_case(&table.name))
+                    {
+                        bail!("Table {} already exists", table.name);
+                    }
+                    let rows = table_rows(&table, rows)?;
+                    let content = if table.without_rowid {
+                        // WITHOUT ROWID 表保存在以主键为键的索引 B-Tree 中
+                        let records = rows.iter().map(|(_, v)| stored(&table, v)).collect();
+                        let keys = sort_keys(&entry, records, table.primary_key.len())?;
+                        Content::Keys(self.encode_keys(&keys))
+                    } else {
+                        Content::Rows(
+                            rows.iter()
+                                .map(|(rowid, v)| {
+                                    (*rowid, encode_record(&stored(&table, v), self.encoding))
+                                })
+                                .collect(),
+                        )
+                    };
+                    trees.push(Tree { entry, content });
+
+                    for n in 1.. {
+                        let Some(columns) = table.autoindex_columns(n) else {
+                            break;
+                        };
+                        let mut entry = SchemaEntry::new(
+                            "index".to_string(),
+                            format!("sqlite_autoindex_{}_{n}", table.name),
+                            table.name.clone(),
+                            0,
+                            None,
+                        );
+                        entry.resolve_columns(Some(&table));
+                        let keys = index_keys(&table, &rows, &entry, columns, true)?;
+                        trees.push(Tree {
+                            entry,
+                            content: Content::Keys(self.encode_keys(&keys)),
+                        });
+                    }
+                    tables.push((table, rows));
+                }
+                Object::Index { sql } => {
+                    let Statement::CreateIndex(index) = parse_create(sql)? else {
+                        bail!("Not a CREATE INDEX statement: {sql}");
+                    };
+                    if index.where_clause.is_some() || index.columns.iter().any(|c| c.is_expression)
+                    {
+                        bail!("Partial indexes and indexes on expressions are not supported");
+                    }
+                    let (table, rows) = tables
+                        .iter()
+                        .find(|(t, _)| t.name.eq_ignore_ascii_case(&index.table))
+                        .ok_or_else(|| anyhow!("No such table: {}", index.table))?;
+                    let entry = entry("index", sql, Some(table))?;
+                    let keys = index_keys(table, rows, &entry, &index.columns, index.unique)?;
+                    trees.push(Tree {
+                        entry,
+                        content: Content::Keys(self.encode_keys(&keys)),
+                    });
+                }
+            }
+        }
+        Ok(trees)
+    }
+
+    fn encode_keys(&self, keys: &[Vec<RecordValue>]) -> Vec<Vec<u8>> {
+        keys.iter()
+            .map(|k| encode_record(k, self.encoding))
+            .collect()
+    }
+}
+
+/// 按 rowid 排列的 (rowid, 按声明顺序排列的值)
+type Rows = Vec<(i64, Vec<RecordValue>)>;
+
+/// 一棵 B-Tree 的 sqlite_schema 行和内容
+struct Tree {
+    entry: SchemaEntry,
+    content: Content,
+}
+
+enum Content {
+    /// 表 B-Tree：按 rowid 排列的 (rowid, record)
+    Rows(Vec<(i64, Vec<u8>)>),
+    /// 索引 B-Tree：按键排列的 record
+    Keys(Vec<Vec<u8>>),
+}
+
+/// 由 CREATE 语句生成 sqlite_schema 中的一行，索引的 tbl_name 是所属的表
+fn entry(kind: &str, sql: &str, table: Option<&CreateTable>) -> Result<SchemaEntry> {
+    let name = match parse_create(sql)? {
+        Statement::CreateTable(t) => t.name,
+        Statement::CreateIndex(i) => i.name,
+    };
+    let tbl_name = table.map_or_else(|| name.clone(), |t| t.name.clone());
+    let mut entry = SchemaEntry::new(kind.to_string(), name, tbl_name, 0, Some(sql.to_string()));
+    entry.resolve_columns(table);
+    Ok(entry)
+}
+
+/// 对每行应用列的亲和性并分配 rowid，按 rowid 排序
+fn table_rows(table: &CreateTable, rows: &[Vec<RecordValue>]) -> Result<Rows> {
+    let alias = table.rowid_alias();
+    let mut next = 1;
+    let mut out = vec![];
+    for row in rows {
+        if row.len() != table.columns.len() {
+            bail!(
+                "Table {} has {} columns but {} values were supplied",
+                table.name,
+                table.columns.len(),
+                row.len()
+            );
+        }
+        let values: Vec<RecordValue> = row
+            .iter()
+            .zip(&table.columns)
+            .map(|(v, c)| store_affinity(v.clone(), c.affinity))
+            .collect();
+        let rowid = match alias.map(|i| &values[i]) {
+            Some(RecordValue::Integer(i)) => *i,
+            None | Some(RecordValue::Null) => next,
+            Some(v) => bail!("Datatype mismatch: {v:?} is not a valid rowid"),
+        };
+        next = next.max(rowid.saturating_add(1));
+        out.push((rowid, values));
+    }
+    out.sort_by_key(|(rowid, _)| *rowid);
+    if let Some(w) = out.windows(2).find(|w| w[0].0 == w[1].0) {
+        bail!(
+            "UNIQUE constraint failed: {}.rowid = {}",
+            table.name,
+            w[0].0
+        );
+    }
+    Ok(out)
+}
+
+/// 按列的亲和性转换要保存的值。与查找时不同，INTEGER 和 NUMERIC 列中没有小数部分的 REAL 保存为整数。
+fn store_affinity(value: RecordValue, affinity: Affinity) -> RecordValue {
+    match (affinity, apply_affinity(value, affinity)) {
+        (Affinity::Integer | Affinity::Numeric, RecordValue::Real(r))
+            if r.fract() == 0.0 && r.abs() < 9.0e18 =>
+        {
+            RecordValue::Integer(r as i64)
+        }
+        (_, v) => v,
+    }
+}
+
+/// 按存储顺序排列 record 中的值，rowid 别名列保存为 NULL
+fn stored(table: &CreateTable, values: &[RecordValue]) -> Vec<RecordValue> {
+    let alias = table.rowid_alias();
+    table
+        .storage_order()
+        .into_iter()
+        .map(|i| {
+            if alias == Some(i) {
+                RecordValue::Null
+            } else {
+                values[i].clone()
+            }
+        })
+        .collect()
+}
+
+/// 每行在索引中的条目：被索引的列，然后是 rowid（WITHOUT ROWID 表是未被索引的主键列）
+fn index_keys(
+    table: &CreateTable,
+    rows: &Rows,
+    entry: &SchemaEntry,
+    columns: &[IndexedColumn],
+    unique: bool,
+) -> Result<Vec<Vec<RecordValue>>> {
+    let alias = table.rowid_alias();
+    let position = |name: &str| {
+        table
+            .columns
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("Table {} has no column named {name}", table.name))
+    };
+    let mut positions = columns
+        .iter()
+        .map(|c| position(&c.name))
+        .collect::<Result<Vec<_>>>()?;
+    if table.without_rowid {
+        for pk in &table.primary_key {
+            if !columns
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(&pk.name))
+            {
+                positions.push(position(&pk.name)?);
+            }
+        }
+    }
+    let keys = rows
+        .iter()
+        .map(|(rowid, values)| {
+            let mut key: Vec<RecordValue> = positions
+                .iter()
+                .map(|&i| {
+                    if alias == Some(i) {
+                        RecordValue::Integer(*rowid)
+                    } else {
+                        values[i].clone()
+                    }
+                })
+                .collect();
+            if !table.without_rowid {
+                key.push(RecordValue::Integer(*rowid));
+            }
+            key
+        })
+        .collect();
+    sort_keys(entry, keys, if unique { columns.len() } else { 0 })
+}
+
+/// 按索引列的排序规则和方向排序。前 unique 列相同（且不含 NULL）的两个条目违反唯一约束。
+fn sort_keys(
+    entry: &SchemaEntry,
+    mut keys: Vec<Vec<RecordValue>>,
+    unique: usize,
+) -> Result<Vec<Vec<RecordValue>>> {
+    let compare = |a: &[RecordValue], b: &[RecordValue]| {
+        let a: Vec<&RecordValue> = a.iter().collect();
+        compare_key(&a, b, &entry.columns)
+    };
+    keys.sort_by(|a, b| compare(a, b));
+    if unique > 0 {
+        for w in keys.windows(2) {
+            let (a, b) = (&w[0][..unique], &w[1][..unique]);
+            if !a.contains(&RecordValue::Null) && compare(a, b) == Ordering::Equal {
+                bail!("UNIQUE constraint failed: {}", entry.name);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Page, Reader};
+
+    fn text(s: String) -> RecordValue {
+        RecordValue::Text(s)
+    }
+
+    /// 找到的单元中 record 的各列
+    fn found_values(reader: &Reader, found: Option<(u32, usize)>) -> Vec<RecordValue> {
+        let (page, cell) = found.expect("key not found");
+        let page = reader.page(page).unwrap();
+        let Page::BTree(page) = page.as_ref() else {
+            panic!("not a b-tree page");
+        };
+        let record = page.cells[cell]
+            .payload
+            .as_ref()
+            .unwrap()
+            .record
+            .as_ref()
+            .unwrap();
+        record.columns.iter().map(|c| c.value.clone()).collect()
+    }
+
+    #[test]
+    fn round_trip_multi_level_database() {
+        let rows = (1..=2000)
+            .map(|i| {
+                // 每 50 行有一个需要溢出页的大 blob
+                let size = if i % 50 == 0 { 3000 } else { i as usize % 40 };
+                vec![
+                    RecordValue::Null,
+                    text(format!("name-{i:05}")),
+                    RecordValue::Integer(i % 7),
+                    RecordValue::Blob(vec![i as u8; size]),
+                ]
+            })
+            .collect();
+        let pairs = (0..3000)
+            .map(|i| {
+                vec![
+                    // 较长的主键让这棵树至少有三层
+                    text(format!("k{}{}", i % 10, "-".repeat(60))),
+                    RecordValue::Integer(i / 10),
+                    RecordValue::Real(i as f64 / 2.0),
+                ]
+            })
+            .collect();
+        let bytes = DatabaseBuilder::new(1024)
+            .reserved_bytes(8)
+            .table(
+                "CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT UNIQUE, kind INT, body BLOB)",
+                rows,
+            )
+            .index("CREATE INDEX t_kind ON t(kind DESC, name)")
+            .table(
+                "CREATE TABLE w(a TEXT, b INTEGER, c REAL, PRIMARY KEY(b, a)) WITHOUT ROWID",
+                pairs,
+            )
+            .build()
+            .unwrap();
+
+        let reader = Reader::new(&bytes).unwrap();
+        assert_eq!(reader.diagnostics(), vec![]);
+        assert!(reader.coverage().unexplained().is_empty());
+        let names: Vec<&str> = reader.schema.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["sqlite_schema", "t", "sqlite_autoindex_t_1", "t_kind", "w"]
+        );
+
+        let entry = |name: &str| {
+            reader
+                .schema
+                .iter()
+                .find(|e| e.name == name)
+                .unwrap()
+                .clone()
+        };
+        let t = entry("t");
+        for rowid in [1, 50, 777, 2000] {
+            let descent = reader.find_rowid(t.root_page, rowid).unwrap();
+            assert_eq!(descent.steps.len(), 2);
+            let values = found_values(&reader, descent.found);
+            assert_eq!(values[0], RecordValue::Null);
+            assert_eq!(values[1], text(format!("name-{rowid:05}")));
+            let size = if rowid % 50 == 0 {
+                3000
+            } else {
+                rowid as usize % 40
+            };
+            assert_eq!(values[3], RecordValue::Blob(vec![rowid as u8; size]));
+        }
+        assert_eq!(reader.find_rowid(t.root_page, 2001).unwrap().found, None);
+
+        let unique = entry("sqlite_autoindex_t_1");
+        let descent = reader
+            .find_index_key(&unique, &[text("name-01234".into())])
+            .unwrap();
+        let values = found_values(&reader, descent.found);
+        assert_eq!(
+            values,
+            [text("name-01234".into()), RecordValue::Integer(1234)]
+        );
+
+        // kind 是 DESC 列
+        let kind = entry("t_kind");
+        let descent = reader
+            .find_index_key(&kind, &[RecordValue::Integer(3), text("name-00010".into())])
+            .unwrap();
+        let values = found_values(&reader, descent.found);
+        assert_eq!(
+            values,
+            [
+                RecordValue::Integer(3),
+                text("name-00010".into()),
+                RecordValue::Integer(10)
+            ]
+        );
+
+        let w = entry("w");
+        let descent = reader
+            .find_index_key(
+                &w,
+                &[
+                    RecordValue::Integer(42),
+                    text(format!("k5{}", "-".repeat(60))),
+                ],
+            )
+            .unwrap();
+        assert!(descent.steps.len() >= 3);
+        let values = found_values(&reader, descent.found);
+        assert_eq!(
+            values,
+            [
+                RecordValue::Integer(42),
+                text(format!("k5{}", "-".repeat(60))),
+                RecordValue::Real(212.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_tables() {
+        let duplicate = DatabaseBuilder::new(4096).table(
+            "CREATE TABLE t(id INTEGER PRIMARY KEY)",
+            vec![vec![RecordValue::Integer(1)], vec![RecordValue::Integer(1)]],
+        );
+        assert!(duplicate.build().is_err());
+
+        let unique = DatabaseBuilder::new(4096)
+            .table(
+                "CREATE TABLE t(a)",
+                vec![
+                    vec![text("x".into())],
+                    vec![text("x".into())],
+                    vec![RecordValue::Null],
+                    vec![RecordValue::Null],
+                ],
+            )
+            .index("CREATE UNIQUE INDEX t_a ON t(a)");
+        assert!(unique.build().is_err());
+
+        let missing = DatabaseBuilder::new(4096).index("CREATE INDEX i ON t(a)");
+        assert!(missing.build().is_err());
+        assert!(DatabaseBuilder::new(1000).build().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::parser::{varint::encode_varint, RecordValue, TextEncoding};
+
+/// 按数据库的文本编码编码字符串
+pub fn encode_text(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    match encoding {
+        TextEncoding::UTF8 => text.as_bytes().to_vec(),
+        TextEncoding::UTF16le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        TextEncoding::UTF16be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+    }
+}
+
+/// 值的 serial type 和按大端保存的内容。整数使用能容纳它的最短类型，
+/// 0 和 1 使用 schema format 4 引入的 8 和 9，不占内容字节。
+pub fn serial_type(value: &RecordValue, encoding: TextEncoding) -> (i64, Vec<u8>) {
+    match value {
+        RecordValue::Null => (0, vec![]),
+        RecordValue::Integer(0) => (8, vec![]),
+        RecordValue::Integer(1) => (9, vec![]),
+        &RecordValue::Integer(i) => {
+            let (serial_type, size) = match i {
+                -0x80..=0x7f => (1, 1),
+                -0x8000..=0x7fff => (2, 2),
+                -0x80_0000..=0x7f_ffff => (3, 3),
+                -0x8000_0000..=0x7fff_ffff => (4, 4),
+                -0x8000_0000_0000..=0x7fff_ffff_ffff => (5, 6),
+                _ => (6, 8),
+            };
+            (serial_type, i.to_be_bytes()[8 - size..].to_vec())
+        }
+        RecordValue::Real(r) => (7, r.to_be_bytes().to_vec()),
+        RecordValue::Text(t) => {
+            let bytes = encode_text(t, encoding);
+            (13 + 2 * bytes.len() as i64, bytes)
+        }
+        RecordValue::Blob(b) => (12 + 2 * b.len() as i64, b.clone()),
+    }
+}
+
+/// 把一行值编码成 record：header size、各列的 serial type，然后是各列的内容
+pub fn encode_record(values: &[RecordValue], encoding: TextEncoding) -> Vec<u8> {
+    let mut types = vec![];
+    let mut body = vec![];
+    for value in values {
+        let (serial_type, content) = serial_type(value, encoding);
+        types.extend(encode_varint(serial_type));
+        body.extend(content);
+    }
+    // header size 包括它自己，它的长度又影响它的值
+    let mut header_size = types.len() + 1;
+    while encode_varint(header_size as i64).len() + types.len() > header_size {
+        header_size += 1;
+    }
+    let mut record = encode_varint(header_size as i64);
+    record.extend(types);
+    record.extend(body);
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Record;
+
+    #[test]
+    fn record_round_trip() {
+        let values = vec![
+            RecordValue::Null,
+            RecordValue::Integer(0),
+            RecordValue::Integer(1),
+            RecordValue::Integer(-2),
+            RecordValue::Integer(1 << 20),
+            RecordValue::Integer(1 << 40),
+            RecordValue::Integer(i64::MIN),
+            RecordValue::Real(1.5),
+            RecordValue::Text("héllo".to_string()),
+            RecordValue::Blob(vec![1, 2, 3]),
+        ];
+        for encoding in [
+            TextEncoding::UTF8,
+            TextEncoding::UTF16le,
+            TextEncoding::UTF16be,
+        ] {
+            let record = Record::parse(&encode_record(&values, encoding), encoding).unwrap();
+            let parsed: Vec<_> = record.columns.into_iter().map(|c| c.value).collect();
+            assert_eq!(parsed, values);
+        }
+        let types: Vec<i64> = values
+            .iter()
+            .map(|v| serial_type(v, TextEncoding::UTF8).0)
+            .collect();
+        assert_eq!(types, [0, 8, 9, 1, 3, 5, 6, 7, 25, 18]);
+
+        // 超过 127 列时 header size 需要两个字节
+        let wide = vec![RecordValue::Null; 200];
+        let record = Record::parse(
+            &encode_record(&wide, TextEncoding::UTF8),
+            TextEncoding::UTF8,
+        );
+        assert_eq!(record.unwrap().header_size, 202);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::parser::wal::{checksum, FRAME_HEADER_SIZE, WAL_HEADER_SIZE, WAL_MAGIC};
+
+/// 生成 WAL 文件。frames 中每一项是 (页号, 页的内容, 提交帧的数据库页数或 0)，
+/// 校验和按大端计算，salt 固定。
+pub fn build_wal(page_size: u32, frames: &[(u32, &[u8], u32)]) -> Vec<u8> {
+    let salt = [0x1234_5678u32, 0x9abc_def0];
+    let mut bytes = Vec::with_capacity(
+        WAL_HEADER_SIZE + frames.len() * (FRAME_HEADER_SIZE + page_size as usize),
+    );
+    for v in [WAL_MAGIC | 1, 3007000, page_size, 0, salt[0], salt[1]] {
+        bytes.extend(v.to_be_bytes());
+    }
+    let mut sum = checksum(true, (0, 0), &bytes);
+    bytes.extend(sum.0.to_be_bytes());
+    bytes.extend(sum.1.to_be_bytes());
+
+    for &(page_number, content, db_size) in frames {
+        assert_eq!(content.len(), page_size as usize);
+        let start = bytes.len();
+        for v in [page_number, db_size, salt[0], salt[1]] {
+            bytes.extend(v.to_be_bytes());
+        }
+        sum = checksum(true, sum, &bytes[start..start + 8]);
+        sum = checksum(true, sum, content);
+        bytes.extend(sum.0.to_be_bytes());
+        bytes.extend(sum.1.to_be_bytes());
+        bytes.extend(content);
+    }
+    bytes
+}